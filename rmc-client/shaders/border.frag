@@ -0,0 +1,9 @@
+#version 330 core
+
+out vec4 frag_Color;
+
+uniform vec4 uniform_Color;
+
+void main() {
+    frag_Color = uniform_Color;
+}
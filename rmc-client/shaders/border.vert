@@ -0,0 +1,12 @@
+#version 330 core
+
+layout(location = 0) in vec3 in_Position;
+
+uniform mat4 uniform_Mvp;
+// Corners of the border in world units, the cube's vertices are stretched between them.
+uniform vec3 uniform_Min;
+uniform vec3 uniform_Max;
+
+void main() {
+    gl_Position = uniform_Mvp * vec4(mix(uniform_Min, uniform_Max, in_Position), 1.0);
+}
@@ -5,20 +5,33 @@ in vec2 vert_Uv;
 in float vert_Highlighted;
 flat in uint vert_Texture;
 flat in uint vert_Light;
+flat in vec3 vert_Tint;
+in float vert_Distance;
+in float vert_Ao;
 
 out vec4 frag_Color;
 
 uniform sampler2DArray uniform_Texture;
+// See fog_range in game_renderer.rs.
+uniform float uniform_FogNear;
+uniform float uniform_FogFar;
+uniform vec3 uniform_FogColor;
 
 void main() {
     float z = float(vert_Texture);
-    vec4 texel = texture(uniform_Texture, vec3(vert_Uv, z));
+    vec4 texel = texture(uniform_Texture, vec3(vert_Uv, z)) * vec4(vert_Tint, 1.0);
     vec3 highlightColor = vert_Highlighted > 0.5 ? vec3(0.5, 0.5, 0.5) : vec3(0.0, 0.0, 0.0);
 
     if (texel.w == 0) {
         discard;
     }
 
+    // Same ramp as light_to_shade in rmc-common.
     float lightStrength = float(vert_Light) / 255.0;
-    frag_Color = vec4(clamp(0, lightStrength, 1) * vec3(texel) + highlightColor, 1.0);
+    // Fully occluded corners keep half of their light.
+    float ao = 0.5 + 0.5 * vert_Ao;
+    vec3 color = clamp(0, lightStrength, 1) * ao * vec3(texel) + highlightColor;
+    // The fog range is empty when nothing around the origin chunk is loaded.
+    float fog = clamp((vert_Distance - uniform_FogNear) / max(uniform_FogFar - uniform_FogNear, 0.001), 0.0, 1.0);
+    frag_Color = vec4(mix(color, uniform_FogColor, fog), 1.0);
 }
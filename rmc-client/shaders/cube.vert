@@ -7,15 +7,27 @@ layout(location = 3) in vec3 instance_Position;
 layout(location = 4) in uint instance_Texture;
 layout(location = 5) in uvec4 instance_Light1;
 layout(location = 6) in uvec2 instance_Light2;
+layout(location = 7) in uint instance_Shape;
+layout(location = 8) in vec3 instance_Tint;
+layout(location = 9) in uint instance_Variant;
+layout(location = 10) in uvec4 instance_Ao1;
+layout(location = 11) in uvec2 instance_Ao2;
 
 uniform mat4 uniform_Mvp;
 uniform vec3 uniform_Highlighted;
+// World units per block, instance positions are in blocks.
+uniform float uniform_BlockScale;
+uniform vec3 uniform_CameraPosition;
 
 out vec3 vert_Position;
 out vec2 vert_Uv;
 out float vert_Highlighted;
 flat out uint vert_Texture;
 flat out uint vert_Light;
+flat out vec3 vert_Tint;
+out float vert_Distance;
+// Ambient occlusion from 0 to 1, interpolated between the corners of the face.
+out float vert_Ao;
 
 void main() {
     uint light[6] = uint[6](
@@ -27,11 +39,66 @@ void main() {
             instance_Light2.y
         );
 
-    vert_Position = in_Position;
+    vec3 position = in_Position;
+    // Bottom slab
+    if (instance_Shape == 1u) {
+        position.y *= 0.5;
+    }
+    // Dropped item, centered on its position
+    if (instance_Shape == 2u) {
+        position = (position - 0.5) * 0.25;
+    }
+    // Torch, pointing towards face `instance_Shape - 8`
+    if (instance_Shape >= 8u) {
+        vec3 normals[6] = vec3[6](
+                vec3(1.0, 0.0, 0.0),
+                vec3(0.0, 1.0, 0.0),
+                vec3(0.0, 0.0, 1.0),
+                vec3(-1.0, 0.0, 0.0),
+                vec3(0.0, -1.0, 0.0),
+                vec3(0.0, 0.0, -1.0)
+            );
+        vec3 normal = normals[instance_Shape - 8u];
+        position.xz = (position.xz - 0.5) * 0.125 + 0.5;
+        position.y *= 0.625;
+        // Wall torches lean against the wall, slightly raised
+        if (normal.y == 0.0) {
+            position += vec3(-normal.x * 0.4375, 0.2, -normal.z * 0.4375);
+        }
+    }
+
+    vert_Position = position;
     vert_Uv = in_Uv;
+    // The top face is turned by a quarter turn per variant, it's the middle of the top row of the texture.
+    if (in_Face == 1u && instance_Variant != 0u) {
+        vec2 origin = vec2(1.0 / 3.0, 0.0);
+        vec2 size = vec2(1.0 / 3.0, 0.5);
+        vec2 local = (in_Uv - origin) / size - 0.5;
+        for (uint i = 0u; i < instance_Variant; i++) {
+            local = vec2(-local.y, local.x);
+        }
+        vert_Uv = origin + (local + 0.5) * size;
+    }
     vert_Light = light[in_Face];
+
+    // Two bits per corner, ordered like ao::face_ao along the two axes of the face.
+    uint ao[6] = uint[6](
+            instance_Ao1.x,
+            instance_Ao1.y,
+            instance_Ao1.z,
+            instance_Ao1.w,
+            instance_Ao2.x,
+            instance_Ao2.y
+        );
+    vec2 tangent = in_Face % 3u == 0u ? in_Position.yz : in_Face % 3u == 1u ? in_Position.xz : in_Position.xy;
+    uint corner = (tangent.x > 0.5 ? 1u : 0u) + (tangent.y > 0.5 ? 2u : 0u);
+    vert_Ao = float((ao[in_Face] >> (corner * 2u)) & 3u) / 3.0;
     vert_Texture = instance_Texture;
+    // Only the top face is tinted
+    vert_Tint = in_Face == 1u ? instance_Tint : vec3(1.0);
     vert_Highlighted = instance_Position == uniform_Highlighted ? 1.0 : 0.0;
 
-    gl_Position = uniform_Mvp * vec4(in_Position + instance_Position, 1.0);
+    vec3 worldPosition = (position + instance_Position) * uniform_BlockScale;
+    vert_Distance = distance(worldPosition, uniform_CameraPosition);
+    gl_Position = uniform_Mvp * vec4(worldPosition, 1.0);
 }
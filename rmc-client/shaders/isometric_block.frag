@@ -6,6 +6,8 @@ out vec4 frag_Color;
 
 uniform sampler2DArray uniform_Texture;
 uniform uint uniform_TextureLayer;
+// light_to_shade of the light the icon is drawn in.
+uniform float uniform_Shade;
 
 void main() {
     float z = float(uniform_TextureLayer);
@@ -13,5 +15,5 @@ void main() {
     if (texel.w == 0.0) {
         discard;
     }
-    frag_Color = texel;
+    frag_Color = vec4(uniform_Shade * vec3(texel), texel.w);
 }
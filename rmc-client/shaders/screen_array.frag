@@ -0,0 +1,18 @@
+#version 330 core
+
+in vec2 vert_Uv;
+
+out vec4 frag_Color;
+
+uniform sampler2DArray uniform_Texture;
+uniform uint uniform_TextureLayer;
+
+void main() {
+    float z = float(uniform_TextureLayer);
+    // The screen quad samples textures bottom row first, the layers are stored top row first.
+    vec4 texel = texture(uniform_Texture, vec3(vert_Uv.x, 1.0 - vert_Uv.y, z));
+    if (texel.w == 0.0) {
+        discard;
+    }
+    frag_Color = texel;
+}
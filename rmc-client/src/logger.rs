@@ -0,0 +1,31 @@
+//! Prints the simulation events of `rmc-common` to stderr, with the `log` feature.
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+/// Environment variable with the most detailed level that gets printed, like `debug`. Only warnings by default.
+const LEVEL_VAR: &str = "RMC_LOG";
+
+struct StderrLogger;
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("[{} {}] {}", record.level(), record.target(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+pub fn init() {
+    let level = std::env::var(LEVEL_VAR)
+        .ok()
+        .and_then(|level| level.parse().ok())
+        .unwrap_or(LevelFilter::Warn);
+    log::set_logger(&StderrLogger).unwrap();
+    log::set_max_level(level);
+}
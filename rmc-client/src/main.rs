@@ -4,26 +4,263 @@ use glow::HasContext;
 use glyph_brush::Section;
 use ndarray::Array3;
 use renderers::{
-    ChunkRenderer, DrawParams, IsometricBlockRenderer, ScreenQuadRenderer, TextRenderer,
+    game_renderer::{ProjectionMode, FOG_COLOR},
+    player_renderer,
+    text_renderer::RichText,
+    ChunkLods, ChunkRenderer, DrawParams, IsometricBlockRenderer, MeshQueue, MinimapRenderer,
+    ScreenQuadRenderer, TextRenderer,
 };
 use rmc_common::{
-    game::{BlockOrItem, TICK_DELTA, TICK_SPEED},
+    ao::AoCache,
+    game::{
+        BlockInfo, BlockOrItem, InteractionResult, Item, TICK_DELTA, TICK_SPEED,
+        TOGGLE_CREATIVE_KEY,
+    },
     input::{ButtonBuffer, ButtonStateEvent, InputState, KeyboardEvent, MouseButtonEvent},
     world::CHUNK_SIZE,
-    Blend, Game, LookBack,
+    Blend, CameraPath, Game, LookBack,
 };
 use sdl2::{event::Event, keyboard::Keycode};
-use std::{collections::HashMap, mem::MaybeUninit, process::exit, time::Instant};
-use texture::{load_image, DataSource};
+use std::{
+    collections::HashMap,
+    mem::MaybeUninit,
+    process::exit,
+    time::{Duration, Instant},
+};
+use texture::{load_array_texture, load_image, DataSource};
 use vek::{Vec2, Vec3, Vec4};
 
+#[cfg(feature = "log")]
+mod logger;
 pub mod renderers;
 pub mod shader;
 pub mod texture;
 
 // TODO we need to destroy objects...
 
+/// Sections of the debug window, each toggled with a function key or from the window's menu.
+#[derive(Debug, Clone, Copy)]
+struct DebugSections {
+    performance: bool,
+    player: bool,
+    world: bool,
+    lighting: bool,
+}
+
+impl Default for DebugSections {
+    fn default() -> Self {
+        DebugSections {
+            performance: true,
+            player: true,
+            world: true,
+            lighting: false,
+        }
+    }
+}
+
+impl DebugSections {
+    fn entries(&mut self) -> [(&'static str, &'static str, &mut bool); 4] {
+        [
+            ("Performance", "F1", &mut self.performance),
+            ("Player", "F2", &mut self.player),
+            ("World", "F3", &mut self.world),
+            ("Lighting", "F4", &mut self.lighting),
+        ]
+    }
+
+    /// Toggles the section bound to `keycode`, if any.
+    fn toggle(&mut self, keycode: Keycode) {
+        let enabled = match keycode {
+            Keycode::F1 => &mut self.performance,
+            Keycode::F2 => &mut self.player,
+            Keycode::F3 => &mut self.world,
+            Keycode::F4 => &mut self.lighting,
+            _ => return,
+        };
+        *enabled = !*enabled;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AppState {
+    Menu,
+    Playing,
+    Paused,
+}
+
+impl AppState {
+    /// The state after pressing Escape, the menu isn't affected.
+    fn toggle_pause(self) -> AppState {
+        match self {
+            AppState::Menu => AppState::Menu,
+            AppState::Playing => AppState::Paused,
+            AppState::Paused => AppState::Playing,
+        }
+    }
+}
+
+/// Simulation time that hasn't been turned into ticks yet.
+#[derive(Debug, Clone, Copy, Default)]
+struct Accumulator {
+    time: f32,
+}
+
+impl Accumulator {
+    /// Adds a frame's worth of time, only while the game is being played.
+    fn advance(&mut self, dt: f32, state: AppState) {
+        if state == AppState::Playing {
+            self.time += dt * TICK_SPEED;
+        }
+    }
+
+    /// Drops any whole ticks still owed so the game doesn't try to catch up after resuming,
+    /// the partial tick is kept so the blended frame stays where it was.
+    fn resume(&mut self) {
+        self.time %= TICK_DELTA;
+    }
+
+    /// Takes a tick out of the accumulator if there is one.
+    fn tick(&mut self) -> bool {
+        if self.time >= TICK_DELTA {
+            self.time -= TICK_DELTA;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// How far the simulation is between the previous and current tick.
+    /// Clamped so a stall that hasn't been ticked away yet doesn't extrapolate past the current tick.
+    fn alpha(&self) -> f32 {
+        (self.time / TICK_DELTA).clamp(0.0, 1.0)
+    }
+}
+
+/// What to do when a single tick takes longer than `OverrunConfig::max_tick_duration`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum OverrunPolicy {
+    #[default]
+    LogAndContinue,
+    /// Drops the ticks that piled up while the slow one ran instead of catching up on them.
+    SkipTicks,
+    Abort,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct OverrunConfig {
+    policy: OverrunPolicy,
+    max_tick_duration: Duration,
+}
+
+impl Default for OverrunConfig {
+    fn default() -> Self {
+        OverrunConfig {
+            policy: OverrunPolicy::default(),
+            max_tick_duration: Duration::from_secs(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OverrunAction {
+    Continue,
+    Log,
+    SkipTicks,
+    Abort,
+}
+
+fn overrun_action(tick_duration: Duration, config: &OverrunConfig) -> OverrunAction {
+    if tick_duration <= config.max_tick_duration {
+        return OverrunAction::Continue;
+    }
+
+    match config.policy {
+        OverrunPolicy::LogAndContinue => OverrunAction::Log,
+        OverrunPolicy::SkipTicks => OverrunAction::SkipTicks,
+        OverrunPolicy::Abort => OverrunAction::Abort,
+    }
+}
+
+/// Reports a tick that took longer than allowed, through the log with the `log` feature.
+fn report_overrun(tick_duration: Duration, suffix: &str) {
+    #[cfg(feature = "log")]
+    log::warn!("Game is running too slow! ({tick_duration:.2?} tick){suffix}");
+    #[cfg(not(feature = "log"))]
+    println!("Game is running too slow! ({tick_duration:.2?} tick){suffix}");
+}
+
+/// Switches the chunks between being drawn filled and as a wireframe.
+const TOGGLE_WIREFRAME_KEY: Keycode = Keycode::F5;
+
+/// Switches between seeing from the player's eyes and from behind the player.
+const TOGGLE_THIRD_PERSON_KEY: Keycode = Keycode::F10;
+/// How far behind the player's eyes the third person camera is, in world units.
+const THIRD_PERSON_DISTANCE: f32 = 4.0;
+
+/// Switches between drawing with perspective and without, for looking at geometry.
+const TOGGLE_ORTHOGRAPHIC_KEY: Keycode = Keycode::F11;
+/// World units from the middle of the screen to its top when drawing without perspective.
+const ORTHOGRAPHIC_SCALE: f32 = 16.0;
+
+/// Shows which blocks the rays of `Game::reachable_blocks` hit.
+const TOGGLE_REACHABLE_KEY: Keycode = Keycode::F9;
+/// Width of the cone of rays shown by `TOGGLE_REACHABLE_KEY`, in radians.
+const REACHABLE_CONE_ANGLE: f32 = 0.6;
+/// Number of rays cast each frame by `TOGGLE_REACHABLE_KEY`.
+const REACHABLE_RAYS: usize = 64;
+
+/// Adds the current camera as a keyframe of the flythrough.
+const RECORD_KEYFRAME_KEY: Keycode = Keycode::F6;
+/// Starts or stops playing back the flythrough.
+const PLAY_FLYTHROUGH_KEY: Keycode = Keycode::F7;
+/// Removes every keyframe of the flythrough.
+const CLEAR_FLYTHROUGH_KEY: Keycode = Keycode::F8;
+/// How many seconds playing back the whole flythrough takes, no matter the number of keyframes.
+const FLYTHROUGH_DURATION: f32 = 10.0;
+
+/// How long the crosshair pulses for after a block was placed or broken.
+const CROSSHAIR_PULSE: Duration = Duration::from_millis(150);
+/// How much bigger the crosshair is at the start of the pulse.
+const CROSSHAIR_PULSE_SCALE: f32 = 0.25;
+
+/// Lines of the tooltip next to the crosshair.
+fn format_block_info(info: &BlockInfo) -> String {
+    format!(
+        "{}\nLight: {}\nOpen to sky: {}",
+        info.ty.name(),
+        info.light,
+        if info.open_to_sky { "yes" } else { "no" }
+    )
+}
+
+/// Size of every item texture.
+const ITEM_TEXTURE_SIZE: u32 = 16;
+
+fn item_texture(item: Item) -> &'static [u8] {
+    match item {
+        Item::Empty => unreachable!(),
+        Item::Bucket => include_bytes!("../textures/bucket.png"),
+        Item::WaterBucket => include_bytes!("../textures/water_bucket.png"),
+        Item::Pickaxe => include_bytes!("../textures/pickaxe.png"),
+    }
+}
+
+/// Formats `n` with commas between every group of three digits.
+fn thousands(n: usize) -> String {
+    n.to_string()
+        .as_bytes()
+        .rchunks(3)
+        .rev()
+        .map(std::str::from_utf8)
+        .collect::<Result<Vec<&str>, _>>()
+        .unwrap()
+        .join(",")
+}
+
 fn main() {
+    #[cfg(feature = "log")]
+    logger::init();
+
     unsafe {
         let sdl = sdl2::init().unwrap();
         let video = sdl.video().unwrap();
@@ -71,7 +308,7 @@ fn main() {
                 .unwrap();
 
         gl.enable(glow::CULL_FACE);
-        gl.clear_color(0.1, 0.2, 0.3, 1.0);
+        gl.clear_color(FOG_COLOR[0], FOG_COLOR[1], FOG_COLOR[2], 1.0);
 
         let crosshair_image = load_image(
             &gl,
@@ -89,6 +326,19 @@ fn main() {
         let screen_quad_renderer = ScreenQuadRenderer::new(&gl);
         let isometric_block_renderer = IsometricBlockRenderer::new(&gl);
 
+        let mut item_textures = Item::ALL
+            .into_iter()
+            .filter_map(|item| Some((item.texture_layer()?, item)))
+            .collect::<Vec<_>>();
+        item_textures.sort_by_key(|&(layer, _)| layer);
+        let item_array_texture = load_array_texture(
+            &gl,
+            &item_textures
+                .into_iter()
+                .map(|(_, item)| DataSource::Inline(item_texture(item)))
+                .collect::<Vec<_>>(),
+        );
+
         let mut game = LookBack::new_identical(Game::new());
 
         let mut input_state = InputState {
@@ -96,6 +346,7 @@ fn main() {
             mouse_buttons: HashMap::new(),
             mouse_delta: Vec2::zero(),
             scroll_delta: 0,
+            ..Default::default()
         };
 
         let mut hotbar_text_renderer = TextRenderer::new(
@@ -111,14 +362,28 @@ fn main() {
             ),
         );
 
-        let mut game_renderer = GameRenderer::new(&gl, game.curr.world.shape);
+        let mut chunk_lods = ChunkLods::new();
+        chunk_lods.update(
+            game.curr.chunk_coordinate(),
+            game.curr.world.chunks_iter().map(|(pos, _)| pos),
+        );
+
+        // Recreated whenever the highlighted block changes, like the hotbar text.
+        let mut block_info_text: Option<(BlockInfo, TextRenderer)> = None;
+
+        let mut game_renderer = GameRenderer::new(&gl, game.curr.world.shape());
+        let mut mesh_queue = MeshQueue::new();
+        let mut ao_cache = AoCache::new();
         for (pos, chunk) in game.curr.world.chunks_iter() {
-            game_renderer.update_chunk(
-                &gl,
-                game.curr.world.chunk_to_index(pos).unwrap().into_tuple(),
+            mesh_queue.push(
                 pos,
-                &chunk,
-                &game.curr.world,
+                chunk_lods.get(pos).instances(
+                    pos * CHUNK_SIZE as i32,
+                    &chunk,
+                    &game.curr.world,
+                    game.curr.chunk_loader.terrain(),
+                    &mut ao_cache,
+                ),
             );
         }
 
@@ -130,13 +395,32 @@ fn main() {
         let mut dt_buffer = Vec::new();
         let mut buffered_dt = 0.0;
 
+        let mut debug_sections = DebugSections::default();
+        let mut wireframe = false;
+        let mut show_reachable = false;
+        let mut view_bob = true;
+        let mut third_person = false;
+        let mut orthographic = false;
+        let mut overrun_config = OverrunConfig::default();
+        let mut camera_path = CameraPath::new(FLYTHROUGH_DURATION);
+        // Seconds into the flythrough, None when it isn't playing.
+        let mut flythrough_time: Option<f32> = None;
+
+        let mut minimap_renderer = MinimapRenderer::new(&gl, 2);
+        let mut minimap_dirty = true;
+        // When a block was last placed or broken, for the crosshair pulse.
+        let mut last_interaction: Option<Instant> = None;
+
         let mut running = true;
-        let mut accumulator = 0.0;
+        let mut app_state = AppState::Menu;
+        let mut accumulator = Accumulator::default();
+        let mut input_tick = 0;
         while running {
             sdl_time.push(sdl.timer().unwrap().performance_counter());
             let dt = (sdl_time.curr - sdl_time.prev) as f32
                 / sdl.timer().unwrap().performance_frequency() as f32;
-            accumulator += dt * TICK_SPEED;
+            accumulator.advance(dt, app_state);
+            let mut next_state = app_state;
 
             dt_buffer.push(dt);
             if dt_buffer.len() >= 20 {
@@ -188,13 +472,68 @@ fn main() {
 
                 match event {
                     Event::Quit { .. } => running = false,
-                    Event::MouseButtonDown { .. } if !imgui.io().want_capture_mouse => {
+
+                    Event::MouseButtonDown { .. }
+                        if !imgui.io().want_capture_mouse && app_state == AppState::Playing =>
+                    {
                         sdl.mouse().set_relative_mouse_mode(true)
                     }
                     Event::KeyDown {
                         keycode: Some(Keycode::Escape),
+                        repeat: false,
+                        ..
+                    } => {
+                        next_state = app_state.toggle_pause();
+                        sdl.mouse().set_relative_mouse_mode(false);
+                    }
+                    Event::KeyDown {
+                        keycode: Some(TOGGLE_WIREFRAME_KEY),
+                        repeat: false,
+                        ..
+                    } => wireframe = !wireframe,
+                    Event::KeyDown {
+                        keycode: Some(TOGGLE_REACHABLE_KEY),
+                        repeat: false,
+                        ..
+                    } => show_reachable = !show_reachable,
+                    Event::KeyDown {
+                        keycode: Some(TOGGLE_THIRD_PERSON_KEY),
+                        repeat: false,
+                        ..
+                    } => third_person = !third_person,
+                    Event::KeyDown {
+                        keycode: Some(TOGGLE_ORTHOGRAPHIC_KEY),
+                        repeat: false,
+                        ..
+                    } => orthographic = !orthographic,
+                    Event::KeyDown {
+                        keycode: Some(RECORD_KEYFRAME_KEY),
+                        repeat: false,
+                        ..
+                    } => camera_path.push(game.curr.camera),
+                    Event::KeyDown {
+                        keycode: Some(PLAY_FLYTHROUGH_KEY),
+                        repeat: false,
+                        ..
+                    } => {
+                        flythrough_time = match flythrough_time {
+                            Some(_) => None,
+                            None => Some(0.0),
+                        }
+                    }
+                    Event::KeyDown {
+                        keycode: Some(CLEAR_FLYTHROUGH_KEY),
+                        repeat: false,
+                        ..
+                    } => {
+                        camera_path.keyframes.clear();
+                        flythrough_time = None;
+                    }
+                    Event::KeyDown {
+                        keycode: Some(keycode),
+                        repeat: false,
                         ..
-                    } => sdl.mouse().set_relative_mouse_mode(false),
+                    } if !imgui.io().want_capture_keyboard => debug_sections.toggle(keycode),
                     _ => {}
                 }
             }
@@ -219,10 +558,11 @@ fn main() {
             imgui_platform.prepare_frame(&mut imgui, &window, &event_pump);
             let ui = imgui.new_frame();
 
-            while accumulator >= TICK_DELTA {
+            while accumulator.tick() {
                 let start_of_tick = Instant::now();
 
-                input_state.update_held_status();
+                input_tick += 1;
+                input_state.update_held_status(input_tick);
                 for keycode in keyboard_buffer.keys().collect::<Vec<_>>() {
                     if let Some(event) = keyboard_buffer.pull(keycode) {
                         input_state.push_keyboard_event(event);
@@ -234,12 +574,19 @@ fn main() {
                     }
                 }
 
-                game.push_from(|_prev, game| game.update(&input_state));
+                let mut interaction = InteractionResult::default();
+                game.push_from(|_prev, game| {
+                    interaction = game.update(&input_state);
+                });
+                if interaction != InteractionResult::default() {
+                    last_interaction = Some(Instant::now());
+                }
 
                 input_state.mouse_delta = Vec2::zero();
                 input_state.scroll_delta = 0;
 
                 if game.curr.world.origin() != game.prev.world.origin() {
+                    minimap_dirty = true;
                     let diff = game.curr.world.origin() - game.prev.world.origin();
 
                     let chunk_renderers = std::mem::transmute::<_, &mut Array3<MaybeUninit<_>>>(
@@ -249,14 +596,8 @@ fn main() {
                     let mut new_chunk_renderers =
                         Array3::<Option<ChunkRenderer>>::default(chunk_renderers.dim());
                     let mut moved = Array3::from_elem(chunk_renderers.dim(), false);
-                    for (index, _chunk) in game
-                        .prev
-                        .world
-                        .chunks
-                        .indexed_iter()
-                        .filter_map(|(idx, chunk)| chunk.as_ref().map(|chunk| (idx, chunk)))
-                    {
-                        let index = Vec3::<usize>::from(index);
+                    for chunk_coord in game.prev.world.loaded_chunks() {
+                        let index = game.prev.world.chunk_to_index(chunk_coord).unwrap();
                         let (Some(x), Some(y), Some(z)) = index
                             .zip(diff)
                             .map(|(i, o)| i.checked_add_signed(-o as isize))
@@ -289,22 +630,30 @@ fn main() {
                             // `index` is unique and it's explicitly only the indices which have not passed through the loop above.
                             let mut old = chunk_renderers[index].assume_init_read();
                             old.destroy(&gl);
+                            ao_cache.invalidate_chunk(
+                                game.prev.world.index_to_chunk(Vec3::<usize>::from(index)),
+                            );
                         }
                     }
 
                     let mut it = new_chunk_renderers.indexed_iter_mut().map(|(index, c)| {
                         std::mem::take(c).unwrap_or_else(|| {
-                            let mut chunk_renderer = ChunkRenderer::new(&gl);
-                            if let Some(chunk) = &game.curr.world.chunks[index] {
-                                chunk_renderer.update_data(
-                                    &gl,
-                                    game.curr.world.index_to_chunk(Vec3::<usize>::from(index))
-                                        * CHUNK_SIZE as i32,
-                                    chunk.blocks.view(),
-                                    &game.curr.world,
+                            let chunk_coord =
+                                game.curr.world.index_to_chunk(Vec3::<usize>::from(index));
+                            if let Some(chunk) = game.curr.world.chunk_at(chunk_coord) {
+                                ao_cache.invalidate_chunk(chunk_coord);
+                                mesh_queue.push(
+                                    chunk_coord,
+                                    chunk_lods.get(chunk_coord).instances(
+                                        chunk_coord * CHUNK_SIZE as i32,
+                                        &chunk,
+                                        &game.curr.world,
+                                        game.curr.chunk_loader.terrain(),
+                                        &mut ao_cache,
+                                    ),
                                 );
                             }
-                            chunk_renderer
+                            ChunkRenderer::new(&gl)
                         })
                     });
 
@@ -312,126 +661,345 @@ fn main() {
                     game_renderer.chunk_renderers =
                         Array3::from_shape_simple_fn(dim, || it.next().unwrap());
                 } else {
-                    for (pos, chunk) in game.curr.world.chunks_iter() {
-                        let index = game.curr.world.chunk_to_index(pos).unwrap().into_tuple();
-
-                        if game
-                            .prev
-                            .world
-                            .chunk_at_world(pos * CHUNK_SIZE as i32)
-                            .map(|c| c.blocks.clone())
-                            != game
-                                .curr
-                                .world
-                                .chunk_at_world(pos * CHUNK_SIZE as i32)
-                                .map(|c| c.blocks.clone())
+                    // Chunks the game marked stale, and chunks that were loaded this tick.
+                    let mut remesh = game.curr.dirty_mesh_chunks().clone();
+                    for (pos, _) in game.curr.world.chunks_iter() {
+                        if !game.prev.world.is_chunk_loaded(pos) {
+                            ao_cache.invalidate_chunk(pos);
+                            remesh.insert(pos);
+                        }
+                    }
+                    // Only the faces looking at the changed blocks need their ambient occlusion again.
+                    for &pos in game.curr.dirty_mesh_chunks() {
+                        let (Some(prev_chunk), Some(chunk)) =
+                            (game.prev.world.chunk_at(pos), game.curr.world.chunk_at(pos))
+                        else {
+                            continue;
+                        };
+                        for ((local, prev), (_, curr)) in
+                            prev_chunk.iter_blocks().zip(chunk.iter_blocks())
                         {
-                            game_renderer.update_chunk(&gl, index, pos, &chunk, &game.curr.world);
+                            if !prev.same_appearance(&curr) {
+                                ao_cache.invalidate(pos * CHUNK_SIZE as i32 + local);
+                            }
                         }
                     }
+                    for pos in remesh {
+                        let Some(chunk) = game.curr.world.chunk_at(pos) else {
+                            continue;
+                        };
+                        minimap_dirty = true;
+                        mesh_queue.push(
+                            pos,
+                            chunk_lods.get(pos).instances(
+                                pos * CHUNK_SIZE as i32,
+                                &chunk,
+                                &game.curr.world,
+                                game.curr.chunk_loader.terrain(),
+                                &mut ao_cache,
+                            ),
+                        );
+                    }
                 }
 
-                accumulator -= TICK_DELTA;
+                // The old mesh keeps being drawn until the new one is uploaded with the others.
+                let chunk_coords = ndarray::indices(game.curr.world.shape())
+                    .into_iter()
+                    .map(|index| game.curr.world.index_to_chunk(Vec3::<usize>::from(index)));
+                for chunk_coord in chunk_lods.update(game.curr.chunk_coordinate(), chunk_coords) {
+                    if let Some(chunk) = game.curr.world.chunk_at(chunk_coord) {
+                        mesh_queue.push(
+                            chunk_coord,
+                            chunk_lods.get(chunk_coord).instances(
+                                chunk_coord * CHUNK_SIZE as i32,
+                                &chunk,
+                                &game.curr.world,
+                                game.curr.chunk_loader.terrain(),
+                                &mut ao_cache,
+                            ),
+                        );
+                    }
+                }
 
                 let end_of_tick = Instant::now();
-                if end_of_tick.duration_since(start_of_tick).as_secs_f32() > 1.0 {
-                    println!("Game is running too slow!");
-                    exit(-1);
+                let tick_duration = end_of_tick.duration_since(start_of_tick);
+                match overrun_action(tick_duration, &overrun_config) {
+                    OverrunAction::Continue => {}
+                    OverrunAction::Log => report_overrun(tick_duration, ""),
+                    OverrunAction::SkipTicks => {
+                        report_overrun(tick_duration, ", skipping ticks");
+                        accumulator.resume();
+                    }
+                    OverrunAction::Abort => {
+                        report_overrun(tick_duration, "");
+                        exit(-1);
+                    }
                 }
             }
 
             ui.window("Debug")
                 .position([0.0, 0.0], imgui::Condition::Always)
                 .always_auto_resize(true)
+                .menu_bar(true)
                 .build(|| {
-                    ui.text(format!(
-                        "FPS: {:.0} ({:.0}ms)",
-                        1.0 / buffered_dt,
-                        buffered_dt * 1000.0
-                    ));
-                    ui.text(format!(
-                        "Updates: {} / {} (total: {})",
-                        game.curr.block_update_count,
-                        game.curr.dirty_blocks.len(),
-                        game.curr.total_block_update_count,
-                    ));
-                    ui.text(format!("Position: {:.2}", game.curr.camera.position));
-                    ui.text(format!("Block Position: {}", game.curr.block_coordinate()));
-                    ui.text(format!(
-                        "Chunk Position: {} ({:?}) (loaded: {})",
-                        game.curr.chunk_coordinate(),
-                        game.curr.world.chunk_to_index(game.curr.chunk_coordinate()),
-                        game.curr
+                    ui.menu_bar(|| {
+                        ui.menu("Sections", || {
+                            for (name, shortcut, enabled) in debug_sections.entries() {
+                                ui.menu_item_config(name)
+                                    .shortcut(shortcut)
+                                    .build_with_ref(enabled);
+                            }
+                        });
+                        ui.menu("View", || {
+                            ui.menu_item_config("Wireframe")
+                                .shortcut(TOGGLE_WIREFRAME_KEY.name())
+                                .build_with_ref(&mut wireframe);
+                            ui.menu_item_config("Reachable blocks")
+                                .shortcut(TOGGLE_REACHABLE_KEY.name())
+                                .build_with_ref(&mut show_reachable);
+                            ui.menu_item_config("View bob")
+                                .build_with_ref(&mut view_bob);
+                            ui.menu_item_config("Third person")
+                                .shortcut(TOGGLE_THIRD_PERSON_KEY.name())
+                                .build_with_ref(&mut third_person);
+                            ui.menu_item_config("Orthographic")
+                                .shortcut(TOGGLE_ORTHOGRAPHIC_KEY.name())
+                                .build_with_ref(&mut orthographic);
+                            ui.menu_item_config("Block info through water")
+                                .build_with_ref(&mut game.curr.info_through_fluids);
+                        });
+                    });
+
+                    if debug_sections.performance {
+                        ui.text(format!(
+                            "FPS: {:.0} ({:.0}ms)",
+                            1.0 / buffered_dt,
+                            buffered_dt * 1000.0
+                        ));
+                        ui.text(format!(
+                            "Updates: {} / {} (total: {})",
+                            game.curr.block_update_count,
+                            game.curr.pending_block_updates(),
+                            game.curr.total_block_update_count,
+                        ));
+                        let blocks_to_draw = game_renderer.blocks_to_draw(&game.curr);
+                        ui.text(format!(
+                            "Blocks: {} ({} triangles)",
+                            thousands(blocks_to_draw),
+                            thousands(blocks_to_draw * 36)
+                        ));
+                        ui.text("Slow ticks:");
+                        for (label, policy) in [
+                            ("Log", OverrunPolicy::LogAndContinue),
+                            ("Skip", OverrunPolicy::SkipTicks),
+                            ("Abort", OverrunPolicy::Abort),
+                        ] {
+                            ui.same_line();
+                            ui.radio_button(label, &mut overrun_config.policy, policy);
+                        }
+                    }
+
+                    if debug_sections.player {
+                        ui.separator();
+                        let mut position = game.curr.camera.position.into_array();
+                        // Only teleport once the position is done being typed, not on every keystroke.
+                        let entered = ui
+                            .input_float3("Position", &mut position)
+                            .enter_returns_true(true)
+                            .build();
+                        if entered || ui.is_item_deactivated_after_edit() {
+                            game.curr.teleport(position.into());
+                        }
+                        ui.text(format!("Block Position: {}", game.curr.block_coordinate()));
+                        ui.text(format!(
+                            "Orientation: {:.2} {:.2} ({:.2})",
+                            game.curr.camera.yaw.0,
+                            game.curr.camera.pitch.0,
+                            game.curr.camera.look_at()
+                        ));
+                        ui.text(format!("On Ground: {}", game.curr.on_ground));
+                        ui.text(format!(
+                            "Creative ({}): {}",
+                            TOGGLE_CREATIVE_KEY.name(),
+                            game.curr.creative
+                        ));
+                        ui.text(format!("Mining: {:.0}%", game.curr.mining.progress * 100.0));
+                    }
+
+                    if debug_sections.world {
+                        ui.separator();
+                        ui.text(format!(
+                            "Chunk Position: {} ({:?}) (loaded: {})",
+                            game.curr.chunk_coordinate(),
+                            game.curr.world.chunk_to_index(game.curr.chunk_coordinate()),
+                            game.curr.current_chunk_loaded()
+                        ));
+                        ui.text(format!("Seed: {}", game.curr.seed()));
+                        ui.text(format!("Loaded Chunks: {}", game.curr.loaded_chunk_count()));
+                        ui.text(format!(
+                            "Highlight: {:?} ({:?})",
+                            game.curr.highlighted_position().unwrap_or_default(),
+                            game.curr.highlighted_block().unwrap_or_default(),
+                        ));
+                    }
+
+                    if debug_sections.lighting {
+                        ui.separator();
+                        let face_block = game.curr.highlighted_face_block().unwrap_or_default();
+                        ui.text(format!(
+                            "Highlight Light: {} (sky: {})",
+                            face_block.light, face_block.sky_light
+                        ));
+                        let player_block = game
+                            .curr
                             .world
-                            .chunk_at_world(game.curr.block_coordinate())
-                            .is_some()
-                    ));
-                    ui.text(format!(
-                        "Highlight: {:?} ({:?}) (light: {})",
-                        game.curr
-                            .look_at_raycast
-                            .map(|r| r.position)
-                            .unwrap_or_default(),
-                        game.curr
-                            .look_at_raycast
-                            .map(|r| game.curr.world.get_block(r.position))
-                            .flatten()
-                            .unwrap_or_default(),
-                        game.curr
-                            .look_at_raycast
-                            .map(|r| game.curr.world.get_block(r.position + r.normal.as_()))
-                            .flatten()
-                            .map(|b| b.light)
-                            .unwrap_or_default(),
-                    ));
-                    ui.text(format!(
-                        "Orientation: {:.2} {:.2} ({:.2})",
-                        game.curr.camera.yaw.0,
-                        game.curr.camera.pitch.0,
-                        game.curr.camera.look_at()
-                    ));
-                    ui.text(format!("On Ground: {}", game.curr.on_ground));
-                    ui.text(format!(
-                        "Blocks: {} ({} triangles)",
-                        game_renderer
-                            .blocks_to_draw(&game.curr)
-                            .to_string()
-                            .as_bytes()
-                            .rchunks(3)
-                            .rev()
-                            .map(std::str::from_utf8)
-                            .collect::<Result<Vec<&str>, _>>()
-                            .unwrap()
-                            .join(","),
-                        (game_renderer.blocks_to_draw(&game.curr) * 36)
-                            .to_string()
-                            .as_bytes()
-                            .rchunks(3)
-                            .rev()
-                            .map(std::str::from_utf8)
-                            .collect::<Result<Vec<&str>, _>>()
-                            .unwrap()
-                            .join(",")
-                    ));
+                            .get_block(game.curr.block_coordinate())
+                            .unwrap_or_default();
+                        ui.text(format!(
+                            "Player Light: {} (sky: {})",
+                            player_block.light, player_block.sky_light
+                        ));
+                    }
                 });
 
+            match app_state {
+                AppState::Menu => {
+                    ui.window("RMC")
+                        .position([512.0, 384.0], imgui::Condition::Always)
+                        .position_pivot([0.5, 0.5])
+                        .always_auto_resize(true)
+                        .build(|| {
+                            if ui.button("Play") {
+                                next_state = AppState::Playing;
+                            }
+                            if ui.button("Quit") {
+                                running = false;
+                            }
+                        });
+                }
+                AppState::Paused => {
+                    ui.window("Paused")
+                        .position([512.0, 384.0], imgui::Condition::Always)
+                        .position_pivot([0.5, 0.5])
+                        .always_auto_resize(true)
+                        .build(|| {
+                            if ui.button("Resume") {
+                                next_state = AppState::Playing;
+                            }
+                            if ui.button("Quit to Menu") {
+                                next_state = AppState::Menu;
+                            }
+                        });
+                }
+                AppState::Playing => {}
+            }
+
+            if next_state != app_state {
+                if next_state == AppState::Playing {
+                    accumulator.resume();
+                } else {
+                    sdl.mouse().set_relative_mouse_mode(false);
+                }
+                app_state = next_state;
+            }
+
             gl.clear(glow::COLOR_BUFFER_BIT | glow::DEPTH_BUFFER_BIT);
 
-            game_renderer.draw(&gl, &game.prev.blend(&game.curr, accumulator / TICK_DELTA));
+            // Every chunk changed by the ticks is uploaded at once, before anything is drawn.
+            for (chunk_coord, instances) in mesh_queue.drain() {
+                if let Some(index) = game.curr.world.chunk_to_index(chunk_coord) {
+                    game_renderer.chunk_renderers[index.into_tuple()]
+                        .update_instances(&gl, &instances);
+                }
+            }
+
+            // The last frame keeps being drawn while paused since the accumulator doesn't move.
+            let mut blended_game = game.prev.blend(&game.curr, accumulator.alpha());
+            game_renderer.view_bob = view_bob && !third_person && flythrough_time.is_none();
+            // The highlight follows the blended camera instead of snapping to where it was each tick.
+            blended_game.look_at_raycast = blended_game.raycast_from_camera();
+            // The model stands where the player is, so it's placed before the camera moves away from there.
+            game_renderer.player_model = third_person.then(|| {
+                player_renderer::model_matrix(blended_game.feet_position(), blended_game.camera.yaw)
+            });
+            if third_person {
+                blended_game.camera = blended_game.third_person_camera(THIRD_PERSON_DISTANCE);
+            }
+            // The flythrough only moves what's rendered, the player stays where it is.
+            if let Some(time) = flythrough_time {
+                let time = time + dt;
+                if let Some(camera) = camera_path.sample(time) {
+                    blended_game.camera = camera;
+                }
+                flythrough_time = (!camera_path.is_finished(time)).then_some(time);
+            }
+            game_renderer.update_entities(&gl, &blended_game);
+            game_renderer.update_reachable(
+                &gl,
+                &if show_reachable {
+                    game.curr
+                        .reachable_blocks(REACHABLE_CONE_ANGLE, REACHABLE_RAYS)
+                } else {
+                    Vec::new()
+                },
+            );
+            game_renderer.set_projection_mode(if orthographic {
+                ProjectionMode::Orthographic {
+                    scale: ORTHOGRAPHIC_SCALE,
+                }
+            } else {
+                ProjectionMode::Perspective
+            });
+            game_renderer.draw(&gl, &blended_game, wireframe);
 
             imgui_renderer
                 .render(&gl, &imgui_textures, imgui.render())
                 .unwrap();
 
+            let pulse = last_interaction.map_or(0.0, |at| {
+                1.0 - (at.elapsed().as_secs_f32() / CROSSHAIR_PULSE.as_secs_f32()).min(1.0)
+            });
             screen_quad_renderer.draw(
                 &gl,
                 &crosshair_image,
                 DrawParams::default()
-                    .scale(Vec2::one() * 4.0)
+                    .scale(Vec2::one() * 4.0 * (1.0 + CROSSHAIR_PULSE_SCALE * pulse))
                     .position(Vec2::new(1024.0, 768.0) / 2.0)
                     .origin(Vec2::one() / 2.0),
             );
 
+            let block_info = game.curr.highlighted_block_info();
+            if block_info != block_info_text.as_ref().map(|(info, _)| *info) {
+                block_info_text = block_info.map(|info| {
+                    let text = RichText::default()
+                        .scale(24.0)
+                        .push(format_block_info(&info));
+                    (info, TextRenderer::new(&gl, text.section()))
+                });
+            }
+            if let Some((_, text_renderer)) = &mut block_info_text {
+                text_renderer.draw(
+                    &gl,
+                    DrawParams::default()
+                        .position(Vec2::new(1024.0, 768.0) / 2.0 + Vec2::new(24.0, 24.0))
+                        .origin(Vec2::zero()),
+                );
+            }
+
+            if minimap_dirty {
+                minimap_renderer.update(&gl, &game.curr.world);
+                minimap_dirty = false;
+            }
+            minimap_renderer.draw(
+                &gl,
+                &screen_quad_renderer,
+                &blended_game.camera,
+                DrawParams::default()
+                    .scale(Vec2::one() * 2.0)
+                    .position(Vec2::new(1024.0 - 16.0, 16.0))
+                    .origin(Vec2::new(1.0, 0.0)),
+            );
+
             // Hotbar
             {
                 let scale = Vec2::one() * 5.0;
@@ -456,8 +1024,10 @@ fn main() {
                             .origin(Vec2::new(0.0, 1.0)),
                     );
 
-                    if let Some(block_or_item) = game.curr.hotbar.slots[i as usize] {
-                        if let BlockOrItem::Block(block_ty) = block_or_item {
+                    let icon_position = Vec2::new(x, y)
+                        + slot_image.size.as_() * scale / 2.0 * Vec2::new(1.0, -1.0);
+                    match game.curr.hotbar.slots[i as usize] {
+                        Some(BlockOrItem::Block(block_ty)) => {
                             gl.bind_texture(
                                 glow::TEXTURE_2D_ARRAY,
                                 Some(game_renderer.block_array_texture),
@@ -467,14 +1037,25 @@ fn main() {
                                 block_ty,
                                 DrawParams::default()
                                     .scale(scale / 2.5)
-                                    .position(
-                                        Vec2::new(x, y)
-                                            + slot_image.size.as_() * scale / 2.0
-                                                * Vec2::new(1.0, -1.0),
-                                    )
+                                    .position(icon_position)
                                     .origin(Vec2::new(0.5, 0.5)),
                             );
                         }
+                        Some(BlockOrItem::Item(item)) => {
+                            if let Some(layer) = item.texture_layer() {
+                                screen_quad_renderer.draw_layer(
+                                    &gl,
+                                    item_array_texture,
+                                    Vec2::broadcast(ITEM_TEXTURE_SIZE),
+                                    layer,
+                                    DrawParams::default()
+                                        .scale(scale * 0.8)
+                                        .position(icon_position)
+                                        .origin(Vec2::new(0.5, 0.5)),
+                                );
+                            }
+                        }
+                        None => {}
                     }
                 }
 
@@ -523,6 +1104,105 @@ fn main() {
     }
 }
 
+#[test]
+fn test_item_textures() {
+    let textures = Item::ALL
+        .into_iter()
+        .filter(|item| item.texture_layer().is_some())
+        .map(item_texture)
+        .collect::<Vec<_>>();
+    for (i, texture) in textures.iter().enumerate() {
+        let image = image::load_from_memory(texture).unwrap();
+        assert_eq!(
+            (image.width(), image.height()),
+            (ITEM_TEXTURE_SIZE, ITEM_TEXTURE_SIZE)
+        );
+        assert!(!textures[..i].contains(texture));
+    }
+}
+
+#[test]
+fn test_overrun_action() {
+    let config = |policy| OverrunConfig {
+        policy,
+        ..OverrunConfig::default()
+    };
+    let fast = Duration::from_millis(20);
+    let limit = OverrunConfig::default().max_tick_duration;
+    let slow = Duration::from_millis(1500);
+
+    for policy in [
+        OverrunPolicy::LogAndContinue,
+        OverrunPolicy::SkipTicks,
+        OverrunPolicy::Abort,
+    ] {
+        assert_eq!(
+            overrun_action(fast, &config(policy)),
+            OverrunAction::Continue
+        );
+        assert_eq!(
+            overrun_action(limit, &config(policy)),
+            OverrunAction::Continue
+        );
+    }
+
+    assert_eq!(
+        OverrunConfig::default().policy,
+        OverrunPolicy::LogAndContinue
+    );
+    assert_eq!(
+        overrun_action(slow, &OverrunConfig::default()),
+        OverrunAction::Log
+    );
+    assert_eq!(
+        overrun_action(Duration::from_secs(60), &OverrunConfig::default()),
+        OverrunAction::Log
+    );
+    assert_eq!(
+        overrun_action(slow, &config(OverrunPolicy::SkipTicks)),
+        OverrunAction::SkipTicks
+    );
+    assert_eq!(
+        overrun_action(slow, &config(OverrunPolicy::Abort)),
+        OverrunAction::Abort
+    );
+}
+
+#[test]
+fn test_accumulator_alpha_clamped() {
+    let mut accumulator = Accumulator::default();
+    accumulator.advance(TICK_DELTA * 1.3 / TICK_SPEED, AppState::Playing);
+    assert_eq!(accumulator.alpha(), 1.0);
+    assert!(accumulator.tick());
+    assert!((accumulator.alpha() - 0.3).abs() < 1e-4);
+}
+
+#[test]
+fn test_accumulator_resume() {
+    let mut accumulator = Accumulator::default();
+    accumulator.advance(TICK_DELTA * 2.5 / TICK_SPEED, AppState::Playing);
+    assert!(accumulator.tick());
+    assert!(accumulator.tick());
+    assert!(!accumulator.tick());
+    let alpha = accumulator.alpha();
+
+    // Nothing builds up while paused.
+    for _ in 0..100 {
+        accumulator.advance(1.0, AppState::Paused);
+    }
+    assert_eq!(accumulator.alpha(), alpha);
+
+    // Owed ticks are dropped on resume, but the partial tick is kept.
+    accumulator.advance(TICK_DELTA * 3.0 / TICK_SPEED, AppState::Playing);
+    accumulator.resume();
+    assert!((accumulator.alpha() - alpha).abs() < 1e-4);
+    assert!(!accumulator.tick());
+
+    accumulator.advance(TICK_DELTA / TICK_SPEED, AppState::Playing);
+    assert!(accumulator.tick());
+    assert!(!accumulator.tick());
+}
+
 #[test]
 fn test_terrain_sampler() {
     let terrain = rmc_common::game::TerrainSampler::new(6543);
@@ -0,0 +1,132 @@
+use glow::HasContext;
+use rmc_common::{
+    collision::WorldBorder,
+    coords::{block_to_world_units, BLOCK_SCALE},
+};
+use vek::{Mat4, Vec3, Vec4};
+
+use crate::shader::create_shader;
+
+use super::face_to_tri;
+
+/// Color of the border walls, mostly see-through so what's behind them is still visible.
+const BORDER_COLOR: Vec4<f32> = Vec4::new(0.4, 0.6, 1.0, 0.25);
+
+/// Corners of the unit cube, stretched over the border when drawn.
+pub(super) const CORNERS: [[f32; 3]; 8] = [
+    [0.0, 0.0, 0.0],
+    [1.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0],
+    [1.0, 1.0, 0.0],
+    [0.0, 0.0, 1.0],
+    [1.0, 0.0, 1.0],
+    [0.0, 1.0, 1.0],
+    [1.0, 1.0, 1.0],
+];
+
+/// Corners of each face of the cube, in the order `face_to_tri` expects.
+pub(super) const FACES: [[u8; 4]; 6] = [
+    [1, 3, 5, 7],
+    [2, 6, 3, 7],
+    [4, 5, 6, 7],
+    [0, 4, 2, 6],
+    [0, 1, 4, 5],
+    [0, 2, 1, 3],
+];
+
+/// Draws the walls of a `WorldBorder` as a translucent box.
+pub struct BorderRenderer {
+    pub vao: glow::VertexArray,
+    #[allow(dead_code)]
+    pub vbo: glow::Buffer,
+    #[allow(dead_code)]
+    pub ebo: glow::Buffer,
+
+    pub program: glow::Program,
+}
+
+impl BorderRenderer {
+    pub unsafe fn new(gl: &glow::Context) -> Self {
+        let vao = gl.create_vertex_array().unwrap();
+        gl.bind_vertex_array(Some(vao));
+
+        let vbo = gl.create_buffer().unwrap();
+        gl.bind_buffer(glow::ARRAY_BUFFER, Some(vbo));
+        gl.buffer_data_u8_slice(
+            glow::ARRAY_BUFFER,
+            bytemuck::cast_slice(&CORNERS),
+            glow::STATIC_DRAW,
+        );
+
+        gl.enable_vertex_attrib_array(0);
+        gl.vertex_attrib_pointer_f32(0, 3, glow::FLOAT, false, 3 * 4, 0);
+
+        let ebo = gl.create_buffer().unwrap();
+        gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(ebo));
+        gl.buffer_data_u8_slice(
+            glow::ELEMENT_ARRAY_BUFFER,
+            bytemuck::cast_slice::<[u8; 6], u8>(&FACES.map(|face| face_to_tri(&face))),
+            glow::STATIC_DRAW,
+        );
+
+        let program = create_shader(
+            gl,
+            include_str!("../../shaders/border.vert"),
+            include_str!("../../shaders/border.frag"),
+        );
+
+        BorderRenderer {
+            vao,
+            vbo,
+            ebo,
+            program,
+        }
+    }
+
+    /// Draws `border` over what was drawn before with depth testing on, so walls behind blocks stay hidden.
+    pub unsafe fn draw(&self, gl: &glow::Context, mvp: Mat4<f32>, border: WorldBorder) {
+        let min = block_to_world_units(border.0.min, BLOCK_SCALE);
+        let max = block_to_world_units(border.0.max, BLOCK_SCALE);
+
+        // Both sides of the walls are seen from inside, and they shouldn't hide each other.
+        gl.disable(glow::CULL_FACE);
+        gl.depth_mask(false);
+        gl.enable(glow::BLEND);
+        gl.blend_func(glow::SRC_ALPHA, glow::ONE_MINUS_SRC_ALPHA);
+
+        gl.use_program(Some(self.program));
+        gl.uniform_matrix_4_f32_slice(
+            Some(
+                &gl.get_uniform_location(self.program, "uniform_Mvp")
+                    .unwrap(),
+            ),
+            false,
+            mvp.as_col_slice(),
+        );
+        set_vec3(gl, self.program, "uniform_Min", min);
+        set_vec3(gl, self.program, "uniform_Max", max);
+        gl.uniform_4_f32_slice(
+            Some(
+                &gl.get_uniform_location(self.program, "uniform_Color")
+                    .unwrap(),
+            ),
+            BORDER_COLOR.as_slice(),
+        );
+
+        gl.bind_vertex_array(Some(self.vao));
+        gl.draw_elements(glow::TRIANGLES, 36, glow::UNSIGNED_BYTE, 0);
+
+        gl.disable(glow::BLEND);
+        gl.depth_mask(true);
+        gl.enable(glow::CULL_FACE);
+    }
+}
+
+unsafe fn set_vec3(gl: &glow::Context, program: glow::Program, name: &str, v: Vec3<f32>) {
+    gl.uniform_3_f32(
+        Some(&gl.get_uniform_location(program, name).unwrap()),
+        v.x,
+        v.y,
+        v.z,
+    );
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use rmc_common::{
+    ao::AoCache,
+    game::TerrainSampler,
+    world::{Chunk, World},
+};
+use vek::Vec3;
+
+use super::chunk_renderer::{chunk_instances, surface_instances, Instance};
+
+/// Chunks further than this many chunks away from the camera only mesh their surface.
+pub const LOD_DISTANCE: i32 = 4;
+/// How much further than `LOD_DISTANCE` a chunk has to be before it's simplified,
+/// so moving back and forth over the boundary doesn't keep switching meshes.
+const LOD_HYSTERESIS: i32 = 1;
+
+/// How detailed the mesh of a chunk is.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkLod {
+    #[default]
+    Full,
+    /// See `surface_instances`.
+    Surface,
+}
+
+impl ChunkLod {
+    /// Level of detail of a chunk `distance` chunks away from the camera that was `self` before.
+    pub fn select(self, distance: i32) -> ChunkLod {
+        match self {
+            ChunkLod::Full if distance > LOD_DISTANCE + LOD_HYSTERESIS => ChunkLod::Surface,
+            ChunkLod::Surface if distance <= LOD_DISTANCE => ChunkLod::Full,
+            lod => lod,
+        }
+    }
+
+    pub fn instances(
+        self,
+        offset: Vec3<i32>,
+        chunk: &Chunk,
+        world: &World,
+        terrain: &TerrainSampler,
+        ao: &mut AoCache,
+    ) -> Vec<Instance> {
+        match self {
+            ChunkLod::Full => chunk_instances(offset, chunk, world, terrain, ao),
+            ChunkLod::Surface => surface_instances(offset, chunk, world, terrain, ao),
+        }
+    }
+}
+
+/// Distance used for the level of detail, the furthest axis so the boundary is a box around the camera.
+fn chunk_distance(chunk_coord: Vec3<i32>, camera_chunk: Vec3<i32>) -> i32 {
+    (chunk_coord - camera_chunk).map(i32::abs).reduce_max()
+}
+
+/// Level of detail of every loaded chunk, only reselected when the camera moves to another chunk.
+#[derive(Debug, Default)]
+pub struct ChunkLods {
+    camera_chunk: Option<Vec3<i32>>,
+    lods: HashMap<Vec3<i32>, ChunkLod>,
+}
+
+impl ChunkLods {
+    pub fn new() -> Self {
+        ChunkLods::default()
+    }
+
+    /// Chunks that haven't been seen by `update` yet get the level of detail for where the camera is.
+    pub fn get(&self, chunk_coord: Vec3<i32>) -> ChunkLod {
+        match (self.lods.get(&chunk_coord), self.camera_chunk) {
+            (Some(&lod), _) => lod,
+            (None, Some(camera_chunk)) => {
+                ChunkLod::Full.select(chunk_distance(chunk_coord, camera_chunk))
+            }
+            (None, None) => ChunkLod::Full,
+        }
+    }
+
+    /// Reselects the level of detail of `chunk_coords` if the camera is in another chunk than last time,
+    /// returns the chunks whose level of detail changed and need to be meshed again.
+    pub fn update(
+        &mut self,
+        camera_chunk: Vec3<i32>,
+        chunk_coords: impl Iterator<Item = Vec3<i32>>,
+    ) -> Vec<Vec3<i32>> {
+        if self.camera_chunk == Some(camera_chunk) {
+            return Vec::new();
+        }
+
+        let mut changed = Vec::new();
+        let lods = chunk_coords
+            .map(|chunk_coord| {
+                let old = self.get(chunk_coord);
+                let new = old.select(chunk_distance(chunk_coord, camera_chunk));
+                if new != old {
+                    changed.push(chunk_coord);
+                }
+                (chunk_coord, new)
+            })
+            .collect();
+        self.lods = lods;
+        self.camera_chunk = Some(camera_chunk);
+        changed
+    }
+}
+
+#[test]
+fn test_chunk_lods() {
+    let chunks = || (-8..=8).map(|x| Vec3::new(x, 0, 0));
+
+    let mut lods = ChunkLods::new();
+    lods.update(Vec3::zero(), chunks());
+    assert_eq!(lods.get(Vec3::new(5, 0, 0)), ChunkLod::Full);
+    assert_eq!(lods.get(Vec3::new(-6, 0, 0)), ChunkLod::Surface);
+    assert_eq!(lods.get(Vec3::new(0, 0, 6)), ChunkLod::Surface);
+
+    // Staying in the same chunk doesn't change anything.
+    assert!(lods.update(Vec3::zero(), chunks()).is_empty());
+
+    // Going one chunk further only simplifies the chunk that's now far enough away.
+    assert_eq!(
+        lods.update(Vec3::new(1, 0, 0), chunks()),
+        vec![Vec3::new(-5, 0, 0)]
+    );
+    // Stepping back doesn't switch it straight back, only coming closer than `LOD_DISTANCE` does.
+    assert!(lods.update(Vec3::zero(), chunks()).is_empty());
+    assert_eq!(
+        lods.update(Vec3::new(-1, 0, 0), chunks()),
+        vec![Vec3::new(-5, 0, 0), Vec3::new(5, 0, 0)]
+    );
+    assert_eq!(lods.get(Vec3::new(-5, 0, 0)), ChunkLod::Full);
+    assert_eq!(lods.get(Vec3::new(5, 0, 0)), ChunkLod::Surface);
+}
@@ -2,10 +2,12 @@ use std::mem;
 
 use bytemuck::offset_of;
 use glow::HasContext;
-use ndarray::ArrayView3;
+use ndarray::Array2;
 use rmc_common::{
-    world::{face_neighbors, World},
-    Block,
+    ao::AoCache,
+    game::TerrainSampler,
+    world::{face_neighbors, Chunk, World, CHUNK_SIZE},
+    Block, BlockShape, BlockType,
 };
 use vek::{Vec2, Vec3};
 
@@ -58,6 +60,52 @@ pub struct Instance {
     pub position: Vec3<f32>,
     pub texture: u8,
     pub light: [u8; 6],
+    pub shape: u8,
+    /// Multiplied with the top face.
+    pub tint: [u8; 3],
+    /// How many quarter turns the top face's texture is rotated by, see `texture_variant`.
+    pub variant: u8,
+    /// Ambient occlusion of each face, its four corners packed by `pack_ao`.
+    pub ao: [u8; 6],
+    /// `bytemuck::Pod` can't have implicit padding.
+    pub padding: [u8; 2],
+}
+
+pub const NO_TINT: [u8; 3] = [255; 3];
+/// Ambient occlusion of an instance that nothing occludes, see `pack_ao`.
+pub const NO_AO: [u8; 6] = [0xff; 6];
+
+/// Packs the corners of `ao::face_ao` into a byte, two bits per corner with the first corner in the lowest bits.
+pub fn pack_ao(corners: [u8; 4]) -> u8 {
+    corners
+        .into_iter()
+        .enumerate()
+        .fold(0, |packed, (corner, ao)| packed | ao << (corner * 2))
+}
+
+/// Grass is tinted by the biome it's in, everything else keeps the colors of its texture.
+pub fn block_tint(block: Block, position: Vec3<i32>, terrain: &TerrainSampler) -> [u8; 3] {
+    match block.ty {
+        BlockType::Grass => terrain.biome(position.xz()).grass_tint(),
+        _ => NO_TINT,
+    }
+}
+
+/// Which of `variants` texture variants the block at `position` uses.
+/// Only depends on the position, so a block looks the same every time its chunk is meshed.
+pub fn texture_variant(position: Vec3<i32>, variants: u8) -> u8 {
+    if variants <= 1 {
+        return 0;
+    }
+
+    // FNV-1a, folded so the high bits affect the low bits the modulo keeps.
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in position.into_iter().flat_map(i32::to_le_bytes) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash ^= hash >> 32;
+    (hash % variants as u64) as u8
 }
 
 unsafe impl bytemuck::Pod for Instance {}
@@ -73,7 +121,26 @@ pub struct ChunkRenderer {
     pub has_data: bool,
 }
 
-fn generate_face(normal: Vec3<f32>, texture_origin: Vec2<f32>, face: u8) -> [Vertex; 4] {
+/// Quarter turns of each face's texture in the atlas, by face index, none keeps the atlas orientation.
+/// Directional textures like the rings of a log can be turned here to line up with the other faces.
+pub const FACE_UV_ROTATIONS: [u8; 6] = [0; 6];
+
+/// Turns `uv`, a position within a face's texture from 0 to 1, by `quarter_turns` around its center.
+/// It turns the same way `cube.vert` turns texture variants.
+pub fn rotate_uv(uv: Vec2<f32>, quarter_turns: u8) -> Vec2<f32> {
+    let mut local = uv - 0.5;
+    for _ in 0..quarter_turns % 4 {
+        local = Vec2::new(-local.y, local.x);
+    }
+    local + 0.5
+}
+
+fn generate_face(
+    normal: Vec3<f32>,
+    texture_origin: Vec2<f32>,
+    face: u8,
+    rotation: u8,
+) -> [Vertex; 4] {
     let (card, card_cross) = if normal.x == 0.0 {
         (
             Vec3::unit_x() * normal.sum(),
@@ -125,12 +192,140 @@ fn generate_face(normal: Vec3<f32>, texture_origin: Vec2<f32>, face: u8) -> [Ver
         );
         Vertex {
             position,
-            uv: texture_origin + uv_offset / Vec2::new(3.0, 2.0),
+            uv: texture_origin + rotate_uv(uv_offset, rotation) / Vec2::new(3.0, 2.0),
             ..e
         }
     })
 }
 
+/// Torches have one shape per orientation, `TORCH_SHAPE + orientation`.
+pub const TORCH_SHAPE: u8 = 8;
+
+fn instance_shape(block: Block) -> u8 {
+    match block.ty.shape() {
+        BlockShape::Torch => TORCH_SHAPE + block.orientation,
+        shape => shape as u8,
+    }
+}
+
+/// Positions of the blocks on the outside of a chunk, everything else is hidden when it's all solid.
+fn chunk_shell() -> impl Iterator<Item = Vec3<i32>> {
+    const LAST: i32 = CHUNK_SIZE as i32 - 1;
+    (0..=LAST).flat_map(|x| {
+        (0..=LAST).flat_map(move |y| {
+            let z_step = if x == 0 || x == LAST || y == 0 || y == LAST {
+                1
+            } else {
+                LAST as usize
+            };
+            (0..=LAST).step_by(z_step).map(move |z| Vec3::new(x, y, z))
+        })
+    })
+}
+
+pub fn chunk_instances(
+    offset: Vec3<i32>,
+    chunk: &Chunk,
+    world: &World,
+    terrain: &TerrainSampler,
+    ao: &mut AoCache,
+) -> Vec<Instance> {
+    if chunk.is_all_air() {
+        return Vec::new();
+    }
+
+    let positions: Box<dyn Iterator<Item = Vec3<i32>>> = if chunk.is_all_solid() {
+        Box::new(chunk_shell())
+    } else {
+        Box::new(chunk.iter_blocks().map(|(pos, _)| pos))
+    };
+
+    positions
+        .filter_map(|pos| block_instance(offset, pos, chunk, world, terrain, ao))
+        .collect()
+}
+
+/// Simplified mesh for far away chunks, only the top block of every column.
+/// Columns taller than a neighbouring column keep the blocks down to its height, so steps don't leave holes.
+pub fn surface_instances(
+    offset: Vec3<i32>,
+    chunk: &Chunk,
+    world: &World,
+    terrain: &TerrainSampler,
+    ao: &mut AoCache,
+) -> Vec<Instance> {
+    if chunk.is_all_air() {
+        return Vec::new();
+    }
+
+    let tops = Array2::from_shape_fn((CHUNK_SIZE, CHUNK_SIZE), |(x, z)| {
+        (0..CHUNK_SIZE).rev().find(|&y| {
+            chunk
+                .get_local(Vec3::new(x, y, z).as_())
+                .is_some_and(|block| block.ty.texture_layer().is_some())
+        })
+    });
+
+    let mut instances = Vec::new();
+    for ((x, z), &top) in tops.indexed_iter() {
+        let Some(top) = top else {
+            continue;
+        };
+        // Neighbours outside of the chunk are as tall as this column, it's only a rough shape.
+        let bottom = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+            .into_iter()
+            .filter_map(|(dx, dz)| {
+                let neighbor = (x.checked_add_signed(dx)?, z.checked_add_signed(dz)?);
+                tops.get(neighbor)
+            })
+            .map(|neighbor_top| neighbor_top.map_or(0, |t| t + 1))
+            .fold(top, usize::min);
+
+        instances.extend((bottom..=top).filter_map(|y| {
+            block_instance(offset, Vec3::new(x, y, z).as_(), chunk, world, terrain, ao)
+        }));
+    }
+    instances
+}
+
+/// Instance of the block at `pos` in `chunk`, None if it's hidden or not drawn as a cube.
+fn block_instance(
+    offset: Vec3<i32>,
+    pos: Vec3<i32>,
+    chunk: &Chunk,
+    world: &World,
+    terrain: &TerrainSampler,
+    ao: &mut AoCache,
+) -> Option<Instance> {
+    let block = chunk.get_local(pos)?;
+    if block.occluded {
+        return None;
+    }
+    let texture = block.ty.texture_layer()? as u8;
+
+    let chunk_coord = world.world_to_chunk(offset);
+    Some(Instance {
+        position: offset.as_() + pos.as_(),
+        texture,
+        light: face_neighbors(offset + pos).map(|p| {
+            world
+                .get_block_cached(chunk_coord, chunk, p)
+                .map(|b| b.light)
+                .unwrap_or(0)
+        }),
+        shape: instance_shape(block),
+        tint: block_tint(block, offset + pos, terrain),
+        variant: texture_variant(offset + pos, block.ty.texture_variants()),
+        // Only full cubes have corners that line up with the blocks around them.
+        ao: if block.ty.shape() == BlockShape::Full {
+            std::array::from_fn(|face| pack_ao(ao.get_or_compute(world, offset + pos, face as u8)))
+        } else {
+            NO_AO
+        },
+        padding: [0; 2],
+    })
+}
+
 impl ChunkRenderer {
     pub unsafe fn new(gl: &glow::Context) -> Self {
         let vao = gl.create_vertex_array().unwrap();
@@ -148,31 +343,37 @@ impl ChunkRenderer {
             Vec3::new(1.0, 0.0, 0.0),
             Vec2::new(2.0 / 3.0, 0.0),
             0,
+            FACE_UV_ROTATIONS[0],
         ));
         push(generate_face(
             Vec3::new(0.0, 1.0, 0.0),
             Vec2::new(1.0 / 3.0, 0.0),
             1,
+            FACE_UV_ROTATIONS[1],
         ));
         push(generate_face(
             Vec3::new(0.0, 0.0, 1.0),
             Vec2::new(0.0, 0.0),
             2,
+            FACE_UV_ROTATIONS[2],
         ));
         push(generate_face(
             Vec3::new(-1.0, 0.0, 0.0),
             Vec2::new(2.0 / 3.0, 0.5),
             3,
+            FACE_UV_ROTATIONS[3],
         ));
         push(generate_face(
             Vec3::new(0.0, -1.0, 0.0),
             Vec2::new(1.0 / 3.0, 0.5),
             4,
+            FACE_UV_ROTATIONS[4],
         ));
         push(generate_face(
             Vec3::new(0.0, 0.0, -1.0),
             Vec2::new(0.0, 0.5),
             5,
+            FACE_UV_ROTATIONS[5],
         ));
 
         let vbo = gl.create_buffer().unwrap();
@@ -259,6 +460,52 @@ impl ChunkRenderer {
             offset_of!(Instance, light) as i32 + 4,
         );
         gl.vertex_attrib_divisor(6, 1);
+        gl.enable_vertex_attrib_array(7);
+        gl.vertex_attrib_pointer_i32(
+            7,
+            1,
+            glow::UNSIGNED_BYTE,
+            mem::size_of::<Instance>() as _,
+            offset_of!(Instance, shape) as _,
+        );
+        gl.vertex_attrib_divisor(7, 1);
+        gl.enable_vertex_attrib_array(8);
+        gl.vertex_attrib_pointer_f32(
+            8,
+            3,
+            glow::UNSIGNED_BYTE,
+            true,
+            mem::size_of::<Instance>() as _,
+            offset_of!(Instance, tint) as _,
+        );
+        gl.vertex_attrib_divisor(8, 1);
+        gl.enable_vertex_attrib_array(9);
+        gl.vertex_attrib_pointer_i32(
+            9,
+            1,
+            glow::UNSIGNED_BYTE,
+            mem::size_of::<Instance>() as _,
+            offset_of!(Instance, variant) as _,
+        );
+        gl.vertex_attrib_divisor(9, 1);
+        gl.enable_vertex_attrib_array(10);
+        gl.vertex_attrib_pointer_i32(
+            10,
+            4,
+            glow::UNSIGNED_BYTE,
+            mem::size_of::<Instance>() as _,
+            offset_of!(Instance, ao) as _,
+        );
+        gl.vertex_attrib_divisor(10, 1);
+        gl.enable_vertex_attrib_array(11);
+        gl.vertex_attrib_pointer_i32(
+            11,
+            2,
+            glow::UNSIGNED_BYTE,
+            mem::size_of::<Instance>() as _,
+            offset_of!(Instance, ao) as i32 + 4,
+        );
+        gl.vertex_attrib_divisor(11, 1);
 
         ChunkRenderer {
             vao,
@@ -270,29 +517,11 @@ impl ChunkRenderer {
         }
     }
 
-    pub unsafe fn update_data(
-        &mut self,
-        gl: &glow::Context,
-        offset: Vec3<i32>,
-        blocks: ArrayView3<Block>,
-        world: &World,
-    ) {
-        let instances = blocks
-            .indexed_iter()
-            .filter(|(_idx, block)| !block.ty.is_air() && !block.occluded)
-            .map(|(pos, block)| (Vec3::new(pos.0 as i32, pos.1 as i32, pos.2 as i32), block))
-            .map(|(pos, block)| Instance {
-                position: offset.as_() + pos.as_(),
-                texture: block.ty as u8 - 1,
-                light: face_neighbors(offset + pos)
-                    .map(|p| world.get_block(p).map(|b| b.light).unwrap_or(0)),
-            })
-            .collect::<Vec<_>>();
-
+    pub unsafe fn update_instances(&mut self, gl: &glow::Context, instances: &[Instance]) {
         gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.ib));
         gl.buffer_data_u8_slice(
             glow::ARRAY_BUFFER,
-            bytemuck::cast_slice::<_, u8>(instances.as_slice()),
+            bytemuck::cast_slice::<_, u8>(instances),
             glow::STATIC_DRAW,
         );
         self.ib_size = instances.len();
@@ -339,3 +568,297 @@ impl ChunkRenderer {
 //         }
 //     }
 // }
+
+#[test]
+fn test_chunk_instances() {
+    use ndarray::Array3;
+    use rmc_common::Block;
+
+    let world = World::new(Vec3::zero());
+    let terrain = TerrainSampler::new(rmc_common::game::DEFAULT_SEED);
+
+    assert!(chunk_instances(
+        Vec3::zero(),
+        &Chunk::new(),
+        &world,
+        &terrain,
+        &mut AoCache::new()
+    )
+    .is_empty());
+
+    let solid = Chunk::from_blocks(Array3::from_elem(
+        (CHUNK_SIZE, CHUNK_SIZE, CHUNK_SIZE),
+        Block::STONE,
+    ));
+    let instances = chunk_instances(Vec3::zero(), &solid, &world, &terrain, &mut AoCache::new());
+    assert_eq!(instances.len(), 16 * 16 * 16 - 14 * 14 * 14);
+    assert!(instances
+        .iter()
+        .all(|i| i.position.into_iter().any(|e| e == 0.0 || e == 15.0)));
+
+    // Partially filled chunks mesh every visible block.
+    let mut partial = solid.clone();
+    partial.set_block(Vec3::new(5, 5, 5), Block::AIR);
+    assert_eq!(
+        chunk_instances(
+            Vec3::zero(),
+            &partial,
+            &world,
+            &terrain,
+            &mut AoCache::new()
+        )
+        .len(),
+        16 * 16 * 16 - 1
+    );
+
+    // Only grass is tinted.
+    assert!(instances.iter().all(|i| i.tint == NO_TINT));
+    let mut grass = partial.clone();
+    grass.set_block(Vec3::new(5, 5, 5), Block::GRASS);
+    let instances = chunk_instances(Vec3::zero(), &grass, &world, &terrain, &mut AoCache::new());
+    let tint = terrain.biome(Vec2::new(5, 5)).grass_tint();
+    assert_eq!(
+        instances
+            .iter()
+            .filter(|i| i.tint != NO_TINT)
+            .map(|i| (i.position, i.tint))
+            .collect::<Vec<_>>(),
+        vec![(Vec3::new(5.0, 5.0, 5.0), tint)]
+    );
+}
+
+#[test]
+fn test_texture_variant() {
+    let positions = || (-16..16).flat_map(|x| (-16..16).map(move |z| Vec3::new(x, 40 + x % 3, z)));
+
+    // Blocks without variants always use the first one.
+    assert!(positions().all(|position| texture_variant(position, 1) == 0));
+    assert!(positions().all(|position| texture_variant(position, 0) == 0));
+
+    let mut counts = [0; 4];
+    for position in positions() {
+        let variant = texture_variant(position, 4);
+        assert_eq!(texture_variant(position, 4), variant);
+        counts[variant as usize] += 1;
+    }
+    // Roughly a quarter each.
+    let total = 32 * 32;
+    assert!(
+        counts
+            .iter()
+            .all(|&count| count > total / 8 && count < total * 3 / 8),
+        "{counts:?}"
+    );
+
+    // Remeshing a chunk picks the same variants.
+    let world = World::new(Vec3::zero());
+    let terrain = TerrainSampler::new(rmc_common::game::DEFAULT_SEED);
+    let mut chunk = Chunk::new();
+    chunk.set_block(Vec3::new(1, 2, 3), Block::GRASS);
+    chunk.set_block(Vec3::new(4, 2, 3), Block::WOOD);
+    let offset = Vec3::new(16, 32, -16);
+    let variants = || {
+        chunk_instances(offset, &chunk, &world, &terrain, &mut AoCache::new())
+            .iter()
+            .map(|i| (i.position, i.variant))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(variants(), variants());
+    assert!(variants().contains(&(Vec3::new(20.0, 34.0, -13.0), 0)));
+    assert!(variants().contains(&(
+        Vec3::new(17.0, 34.0, -13.0),
+        texture_variant(Vec3::new(17, 34, -13), 4)
+    )));
+}
+
+#[test]
+fn test_instance_world_position() {
+    use rmc_common::coords::{block_to_world_units, world_units_to_block, BLOCK_SCALE};
+
+    let world = World::new(Vec3::zero());
+    let terrain = TerrainSampler::new(rmc_common::game::DEFAULT_SEED);
+    let mut chunk = Chunk::new();
+    chunk.set_block(Vec3::new(3, 5, 7), Block::STONE);
+
+    // Instances are in blocks, `cube.vert` scales them to world units.
+    let offset = Vec3::new(-1, 2, 1) * CHUNK_SIZE as i32;
+    let instances = chunk_instances(offset, &chunk, &world, &terrain, &mut AoCache::new());
+    assert_eq!(instances.len(), 1);
+    let block = offset + Vec3::new(3, 5, 7);
+    let corner = instances[0].position * BLOCK_SCALE;
+    assert_eq!(corner, block_to_world_units(block, BLOCK_SCALE));
+    assert_eq!(
+        world_units_to_block(corner + BLOCK_SCALE / 2.0, BLOCK_SCALE),
+        block
+    );
+}
+
+#[test]
+fn test_instance_ao() {
+    use rmc_common::{ao::MAX_AO, Block};
+
+    let mut chunk = Chunk::new();
+    chunk.set_block(Vec3::new(4, 4, 4), Block::STONE);
+    chunk.set_block(Vec3::new(5, 5, 4), Block::STONE);
+    let mut world = World::new(Vec3::zero());
+    world.load(Vec3::zero(), chunk.clone());
+    let terrain = TerrainSampler::new(rmc_common::game::DEFAULT_SEED);
+
+    let mut ao = AoCache::new();
+    let instances = chunk_instances(Vec3::zero(), &chunk, &world, &terrain, &mut ao);
+    let instance = instances
+        .iter()
+        .find(|i| i.position == Vec3::new(4.0, 4.0, 4.0))
+        .unwrap();
+    // The block next to the top face darkens the two corners along it, the bottom face isn't occluded.
+    assert_eq!(instance.ao[1], pack_ao([MAX_AO, 2, MAX_AO, 2]));
+    assert_eq!(instance.ao[4], pack_ao([MAX_AO; 4]));
+    assert_eq!(pack_ao([MAX_AO; 4]), NO_AO[0]);
+    // Meshing caches every face of the full cubes.
+    assert_eq!(ao.len(), 2 * 6);
+}
+
+#[test]
+fn test_surface_instances() {
+    use ndarray::Array3;
+    use std::collections::HashMap;
+
+    let world = World::new(Vec3::zero());
+    let terrain = TerrainSampler::new(rmc_common::game::DEFAULT_SEED);
+
+    // Top of every column, in whole blocks.
+    let silhouette = |instances: &[Instance]| {
+        let mut tops = HashMap::new();
+        for instance in instances {
+            let column = (instance.position.x as i32, instance.position.z as i32);
+            let top = tops.entry(column).or_insert(instance.position.y as i32);
+            *top = (*top).max(instance.position.y as i32);
+        }
+        tops
+    };
+
+    let solid = Chunk::from_blocks(Array3::from_elem(
+        (CHUNK_SIZE, CHUNK_SIZE, CHUNK_SIZE),
+        Block::STONE,
+    ));
+    let full = chunk_instances(Vec3::zero(), &solid, &world, &terrain, &mut AoCache::new());
+    let surface = surface_instances(Vec3::zero(), &solid, &world, &terrain, &mut AoCache::new());
+    assert_eq!(surface.len(), CHUNK_SIZE * CHUNK_SIZE);
+    assert!(surface.len() * 4 < full.len());
+    assert_eq!(silhouette(&surface), silhouette(&full));
+
+    // A step keeps the blocks of the taller side down to the lower side.
+    let mut step = solid.clone();
+    for x in 0..8 {
+        for y in 10..CHUNK_SIZE as i32 {
+            for z in 0..CHUNK_SIZE as i32 {
+                step.set_block(Vec3::new(x, y, z), Block::AIR);
+            }
+        }
+    }
+    let full = chunk_instances(Vec3::zero(), &step, &world, &terrain, &mut AoCache::new());
+    let surface = surface_instances(Vec3::zero(), &step, &world, &terrain, &mut AoCache::new());
+    assert!(surface.len() < full.len());
+    assert_eq!(silhouette(&surface), silhouette(&full));
+    let mut wall = surface
+        .iter()
+        .filter(|i| i.position.x == 8.0 && i.position.z == 3.0)
+        .map(|i| i.position.y as i32)
+        .collect::<Vec<_>>();
+    wall.sort();
+    assert_eq!(wall, (10..CHUNK_SIZE as i32).collect::<Vec<_>>());
+
+    assert!(surface_instances(
+        Vec3::zero(),
+        &Chunk::new(),
+        &world,
+        &terrain,
+        &mut AoCache::new()
+    )
+    .is_empty());
+}
+
+#[test]
+fn test_generated_occlusion() {
+    use rmc_common::world::generate_chunk;
+    use std::collections::HashSet;
+
+    let world = World::new(Vec3::zero());
+    let terrain = TerrainSampler::new(rmc_common::game::DEFAULT_SEED);
+    // The chunk with the grass in it and the one below it, which is mostly buried.
+    let surface_y = (terrain.height(Vec2::zero()) as i32 - 1) / CHUNK_SIZE as i32;
+    let mut buried = 0;
+    for chunk_coord in [Vec3::new(0, surface_y, 0), Vec3::new(0, surface_y - 1, 0)] {
+        let offset = chunk_coord * CHUNK_SIZE as i32;
+        let chunk = generate_chunk(&terrain, chunk_coord);
+
+        let mut exposed = HashSet::new();
+        for (local, block) in chunk.iter_blocks() {
+            if block.ty.is_air() {
+                continue;
+            }
+            let hidden = face_neighbors(local).into_iter().all(|neighbor| {
+                chunk
+                    .get_local(neighbor)
+                    .map_or(false, |neighbor| !neighbor.ty.light_passing())
+            });
+            assert_eq!(block.occluded, hidden, "{local}");
+            if hidden {
+                buried += 1;
+            } else {
+                exposed.insert(offset + local);
+            }
+        }
+
+        // Exactly the blocks that can be seen are meshed, which includes all of the grass.
+        let meshed = chunk_instances(offset, &chunk, &world, &terrain, &mut AoCache::new())
+            .into_iter()
+            .map(|instance| instance.position.as_::<i32>())
+            .collect::<HashSet<_>>();
+        assert_eq!(meshed, exposed);
+        assert!(chunk
+            .iter_blocks()
+            .filter(|(_, block)| block.ty == BlockType::Grass)
+            .all(|(local, _)| meshed.contains(&(offset + local))));
+    }
+    assert!(buried > 0);
+}
+
+#[test]
+fn test_face_uv_rotation() {
+    let face = |rotation| {
+        generate_face(
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec2::new(2.0 / 3.0, 0.0),
+            0,
+            rotation,
+        )
+    };
+    let cell = Vec2::new(3.0, 2.0);
+    let unrotated = face(0);
+    let corner = unrotated
+        .iter()
+        .position(|vertex| vertex.uv == Vec2::new(2.0 / 3.0, 0.0))
+        .unwrap();
+
+    // A quarter turn moves each corner of the texture to the next one.
+    let expected = [
+        Vec2::new(0.0, 0.0),
+        Vec2::new(1.0, 0.0),
+        Vec2::new(1.0, 1.0),
+        Vec2::new(0.0, 1.0),
+    ];
+    for (rotation, expected) in (0..4).zip(expected) {
+        let vertices = face(rotation);
+        let uv = (vertices[corner].uv - Vec2::new(2.0 / 3.0, 0.0)) * cell;
+        assert!((uv - expected).magnitude() < 1e-5, "{rotation}: {uv}");
+
+        // Only the texture turns, the face stays in place and within its part of the atlas.
+        for (vertex, unrotated) in vertices.iter().zip(&unrotated) {
+            assert_eq!(vertex.position, unrotated.position);
+            let uv = (vertex.uv - Vec2::new(2.0 / 3.0, 0.0)) * cell;
+            assert!(uv.map(|c| (-1e-5..=1.0 + 1e-5).contains(&c)).reduce_and());
+        }
+    }
+    assert_eq!(face(4), unrotated);
+}
@@ -1,22 +1,109 @@
 use glow::HasContext;
 use ndarray::Array3;
 use rmc_common::{
-    world::{Chunk, World, CHUNK_SIZE},
-    CameraExt, Game,
+    coords::{world_units_to_block, BLOCK_SCALE},
+    entity::EntityKind,
+    game::BlockOrItem,
+    world::CHUNK_SIZE,
+    BlockType, CameraExt, Game, ViewBob,
 };
-use vek::{Mat4, Vec3};
+use vek::{FrustumPlanes, Mat4, Vec3};
 
 use crate::{
     shader::create_shader,
     texture::{load_array_texture, DataSource},
 };
 
-use super::ChunkRenderer;
+use super::{
+    chunk_renderer::{Instance, NO_AO, NO_TINT},
+    BorderRenderer, ChunkRenderer, PlayerRenderer,
+};
+
+/// `Instance::shape` of dropped items, a small cube instead of a block shape.
+const DROPPED_ITEM_SHAPE: u8 = 2;
+/// Texture of the markers drawn on reachable blocks.
+const REACHABLE_MARKER_TEXTURE: BlockType = BlockType::Test;
+/// What far away blocks fade into, the same as the sky so the fog hides where the loaded chunks end.
+pub const FOG_COLOR: [f32; 3] = [0.1, 0.2, 0.3];
+/// Where the fog starts, as a fraction of where it hides everything.
+const FOG_START: f32 = 0.6;
+
+/// Vertical field of view of the perspective projection, in degrees.
+const FOV: f32 = 120.0;
+const ASPECT_RATIO: f32 = 4. / 3.;
+/// How far the orthographic projection sees, in world units.
+const ORTHOGRAPHIC_DEPTH: f32 = 512.0;
+
+/// How `GameRenderer::projection` puts the view on the screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProjectionMode {
+    Perspective,
+    /// Without perspective, so geometry can be looked at without being distorted.
+    /// `scale` is how many world units there are from the middle of the screen to its top.
+    Orthographic {
+        scale: f32,
+    },
+}
+
+impl ProjectionMode {
+    pub fn matrix(self) -> Mat4<f32> {
+        match self {
+            ProjectionMode::Perspective => {
+                Mat4::infinite_perspective_rh(FOV.to_radians(), ASPECT_RATIO, 0.0001)
+            }
+            ProjectionMode::Orthographic { scale } => Mat4::orthographic_rh_no(FrustumPlanes {
+                left: -scale * ASPECT_RATIO,
+                right: scale * ASPECT_RATIO,
+                bottom: -scale,
+                top: scale,
+                near: 0.0,
+                far: ORTHOGRAPHIC_DEPTH,
+            }),
+        }
+    }
+}
+
+/// Distances from the camera, in world units, where the fog starts and where it hides everything,
+/// for chunks loaded `load_radius` chunks around the origin chunk.
+/// The camera can be anywhere in the origin chunk, so the first missing chunk can be a chunk closer than that.
+pub fn fog_range(load_radius: f32, block_scale: f32) -> (f32, f32) {
+    let far = (load_radius - 1.0).max(0.0) * CHUNK_SIZE as f32 * block_scale;
+    (far * FOG_START, far)
+}
+
+fn block_texture(ty: BlockType) -> &'static [u8] {
+    match ty {
+        BlockType::Air => unreachable!(),
+        BlockType::Test => include_bytes!("../../textures/test.png"),
+        BlockType::Grass => include_bytes!("../../textures/grass.png"),
+        BlockType::Lantern => include_bytes!("../../textures/lantern.png"),
+        BlockType::Mesh => include_bytes!("../../textures/mesh.png"),
+        BlockType::Wood => include_bytes!("../../textures/wood.png"),
+        BlockType::Stone => include_bytes!("../../textures/stone.png"),
+        BlockType::Water => include_bytes!("../../textures/water.png"),
+        BlockType::StoneSlab => include_bytes!("../../textures/stone_slab.png"),
+        BlockType::Torch => include_bytes!("../../textures/torch.png"),
+        BlockType::Wire => include_bytes!("../../textures/wire.png"),
+        BlockType::PowerSource => include_bytes!("../../textures/power_source.png"),
+        BlockType::Dirt => include_bytes!("../../textures/dirt.png"),
+    }
+}
 
 pub struct GameRenderer {
+    /// Only used for drawing, see `set_projection_mode`.
     pub projection: Mat4<f32>,
+    projection_mode: ProjectionMode,
 
     pub chunk_renderers: Array3<ChunkRenderer>,
+    pub entity_renderer: ChunkRenderer,
+    /// Markers on the blocks found by `Game::reachable_blocks`, for debugging.
+    pub reachable_renderer: ChunkRenderer,
+    pub border_renderer: BorderRenderer,
+    pub player_renderer: PlayerRenderer,
+    /// Transform of the player's model, see `player_renderer::model_matrix`. None hides it, like in first person.
+    pub player_model: Option<Mat4<f32>>,
+    /// Whether the view bobs while walking, see `Game::view_bob_offset`.
+    pub view_bob: bool,
 
     pub block_array_texture: glow::Texture,
     pub program: glow::Program,
@@ -24,16 +111,18 @@ pub struct GameRenderer {
 
 impl GameRenderer {
     pub unsafe fn new(gl: &glow::Context, chunk_shape: (usize, usize, usize)) -> Self {
+        let mut block_textures = BlockType::ALL
+            .into_iter()
+            .filter_map(|ty| Some((ty.texture_layer()?, ty)))
+            .collect::<Vec<_>>();
+        block_textures.sort_by_key(|&(layer, _)| layer);
+
         let block_array_texture = load_array_texture(
             &gl,
-            &[
-                DataSource::Inline(include_bytes!("../../textures/test.png")),
-                DataSource::Inline(include_bytes!("../../textures/grass.png")),
-                DataSource::Inline(include_bytes!("../../textures/lantern.png")),
-                DataSource::Inline(include_bytes!("../../textures/mesh.png")),
-                DataSource::Inline(include_bytes!("../../textures/wood.png")),
-                DataSource::Inline(include_bytes!("../../textures/stone.png")),
-            ],
+            &block_textures
+                .into_iter()
+                .map(|(_, ty)| DataSource::Inline(block_texture(ty)))
+                .collect::<Vec<_>>(),
         );
 
         let program = create_shader(
@@ -43,39 +132,103 @@ impl GameRenderer {
         );
 
         GameRenderer {
-            projection: Mat4::<f32>::infinite_perspective_rh(120_f32.to_radians(), 4. / 3., 0.0001),
+            projection: ProjectionMode::Perspective.matrix(),
+            projection_mode: ProjectionMode::Perspective,
 
             chunk_renderers: Array3::from_shape_simple_fn(chunk_shape, || ChunkRenderer::new(gl)),
+            entity_renderer: ChunkRenderer::new(gl),
+            reachable_renderer: ChunkRenderer::new(gl),
+            border_renderer: BorderRenderer::new(gl),
+            player_renderer: PlayerRenderer::new(gl),
+            player_model: None,
+            view_bob: true,
 
             block_array_texture,
             program,
         }
     }
 
-    pub unsafe fn update_chunk(
-        &mut self,
-        gl: &glow::Context,
-        idx: (usize, usize, usize),
-        chunk_coord: Vec3<i32>,
-        chunk: &Chunk,
-        world: &World,
-    ) {
-        self.chunk_renderers[idx].update_data(
-            gl,
-            chunk_coord * CHUNK_SIZE as i32,
-            chunk.blocks.view(),
-            &world,
-        );
+    pub fn projection_mode(&self) -> ProjectionMode {
+        self.projection_mode
+    }
+
+    pub fn set_projection_mode(&mut self, mode: ProjectionMode) {
+        self.projection_mode = mode;
+        self.projection = mode.matrix();
     }
 
     pub unsafe fn clear_chunk(&mut self, gl: &glow::Context, idx: (usize, usize, usize)) {
         self.chunk_renderers[idx].clear_data(gl);
     }
 
-    pub unsafe fn draw(&self, gl: &glow::Context, game: &Game) {
+    pub unsafe fn update_entities(&mut self, gl: &glow::Context, game: &Game) {
+        let instances = game
+            .entities
+            .iter()
+            .filter_map(|entity| match entity.kind {
+                EntityKind::DroppedItem(BlockOrItem::Block(ty)) => Some((entity, ty)),
+                EntityKind::DroppedItem(BlockOrItem::Item(_)) => None,
+                EntityKind::Particle { ty, .. } => Some((entity, ty)),
+            })
+            .filter_map(|(entity, ty)| {
+                let light = game
+                    .world
+                    .get_block(world_units_to_block(entity.position, BLOCK_SCALE))
+                    .map(|b| b.light)
+                    .unwrap_or(0);
+                Some(Instance {
+                    // Instances are positioned in blocks.
+                    position: entity.position / BLOCK_SCALE,
+                    texture: ty.texture_layer()? as u8,
+                    light: [light; 6],
+                    shape: DROPPED_ITEM_SHAPE,
+                    tint: NO_TINT,
+                    variant: 0,
+                    ao: NO_AO,
+                    ..Default::default()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        self.entity_renderer.update_instances(gl, &instances);
+    }
+
+    /// Marks every block in `positions` with a small cube in its center, an empty slice hides the markers.
+    pub unsafe fn update_reachable(&mut self, gl: &glow::Context, positions: &[Vec3<i32>]) {
+        if positions.is_empty() {
+            self.reachable_renderer.clear_data(gl);
+            return;
+        }
+
+        let instances = positions
+            .iter()
+            .map(|position| Instance {
+                position: position.as_::<f32>() + 0.5,
+                texture: REACHABLE_MARKER_TEXTURE.texture_layer().unwrap() as u8,
+                light: [255; 6],
+                shape: DROPPED_ITEM_SHAPE,
+                tint: NO_TINT,
+                variant: 0,
+                ao: NO_AO,
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        self.reachable_renderer.update_instances(gl, &instances);
+    }
+
+    /// Draws the chunks, entities, player model and world border, as lines instead of filled triangles if `wireframe` is set.
+    pub unsafe fn draw(&self, gl: &glow::Context, game: &Game, wireframe: bool) {
         gl.enable(glow::DEPTH_TEST);
+        if wireframe {
+            gl.polygon_mode(glow::FRONT_AND_BACK, glow::LINE);
+        }
 
-        let mvp = self.projection * game.camera.to_matrix();
+        let bob = if self.view_bob {
+            game.view_bob_offset()
+        } else {
+            ViewBob::default()
+        };
+        let mvp = self.projection * game.camera.to_matrix_with_bob(bob);
 
         gl.use_program(Some(self.program));
         gl.uniform_matrix_4_f32_slice(
@@ -87,8 +240,8 @@ impl GameRenderer {
             mvp.as_col_slice(),
         );
         let uniform_highlighted = game
-            .look_at_raycast
-            .map(|v| v.position.map(|e| e as f32))
+            .highlighted_position()
+            .map(|position| position.as_::<f32>())
             .unwrap_or(Vec3::new(f32::NAN, f32::NAN, f32::NAN));
         gl.uniform_3_f32(
             Some(
@@ -100,6 +253,44 @@ impl GameRenderer {
             uniform_highlighted.z,
         );
 
+        gl.uniform_1_f32(
+            Some(
+                &gl.get_uniform_location(self.program, "uniform_BlockScale")
+                    .unwrap(),
+            ),
+            BLOCK_SCALE,
+        );
+
+        let (fog_near, fog_far) = fog_range(game.load_radius(), BLOCK_SCALE);
+        gl.uniform_1_f32(
+            Some(
+                &gl.get_uniform_location(self.program, "uniform_FogNear")
+                    .unwrap(),
+            ),
+            fog_near,
+        );
+        gl.uniform_1_f32(
+            Some(
+                &gl.get_uniform_location(self.program, "uniform_FogFar")
+                    .unwrap(),
+            ),
+            fog_far,
+        );
+        gl.uniform_3_f32_slice(
+            Some(
+                &gl.get_uniform_location(self.program, "uniform_FogColor")
+                    .unwrap(),
+            ),
+            &FOG_COLOR,
+        );
+        gl.uniform_3_f32_slice(
+            Some(
+                &gl.get_uniform_location(self.program, "uniform_CameraPosition")
+                    .unwrap(),
+            ),
+            game.camera.position.as_slice(),
+        );
+
         gl.bind_texture(glow::TEXTURE_2D_ARRAY, Some(self.block_array_texture));
         for (index, chunk_renderer) in self.chunk_renderers.indexed_iter() {
             if game
@@ -109,7 +300,18 @@ impl GameRenderer {
                 chunk_renderer.draw(&gl);
             }
         }
+        self.entity_renderer.draw(gl);
+        self.reachable_renderer.draw(gl);
+        if let Some(model) = self.player_model {
+            self.player_renderer.draw(gl, mvp, model);
+        }
+        // Translucent, so it has to come after everything it can be seen through.
+        if let Some(border) = game.world_border {
+            self.border_renderer.draw(gl, mvp, border);
+        }
 
+        // Everything drawn after this, like the UI, is always filled.
+        gl.polygon_mode(glow::FRONT_AND_BACK, glow::FILL);
         gl.disable(glow::DEPTH_TEST);
     }
 
@@ -129,3 +331,47 @@ impl GameRenderer {
             .sum()
     }
 }
+
+#[test]
+fn test_draw_signature() {
+    // Makes sure callers have to say whether to draw a wireframe.
+    let _: unsafe fn(&GameRenderer, &glow::Context, &Game, bool) = GameRenderer::draw;
+}
+
+#[test]
+fn test_fog_range() {
+    let (near, far) = fog_range(4.0, BLOCK_SCALE);
+    assert_eq!(far, 3.0 * CHUNK_SIZE as f32);
+    assert!(near > 0.0 && near < far);
+
+    // Further render distances push the fog out with them.
+    let (further_near, further_far) = fog_range(6.0, BLOCK_SCALE);
+    assert!(further_near > near && further_far > far);
+    assert_eq!(fog_range(4.0, 0.5).1, far * 0.5);
+    assert_eq!(fog_range(0.5, BLOCK_SCALE), (0.0, 0.0));
+}
+
+#[test]
+fn test_orthographic_projection() {
+    use vek::Vec4;
+
+    let projection = ProjectionMode::Orthographic { scale: 10.0 }.matrix();
+    let ndc = |view: Vec3<f32>| {
+        let clip = projection * Vec4::from_point(view);
+        clip.xyz() / clip.w
+    };
+
+    // The camera looks down -z, things don't shrink with distance.
+    for depth in [1.0, 50.0, 400.0] {
+        let top_right = ndc(Vec3::new(10.0 * ASPECT_RATIO, 10.0, -depth));
+        assert!((top_right.x - 1.0).abs() < 1e-5 && (top_right.y - 1.0).abs() < 1e-5);
+        let bottom_left = ndc(Vec3::new(-10.0 * ASPECT_RATIO, -10.0, -depth));
+        assert!((bottom_left.x + 1.0).abs() < 1e-5 && (bottom_left.y + 1.0).abs() < 1e-5);
+        assert_eq!(ndc(Vec3::new(0.0, 0.0, -depth)).xy(), vek::Vec2::zero());
+    }
+    assert!((ndc(Vec3::zero()).z + 1.0).abs() < 1e-5);
+    assert!((ndc(Vec3::new(0.0, 0.0, -ORTHOGRAPHIC_DEPTH)).z - 1.0).abs() < 1e-5);
+
+    let perspective = ProjectionMode::Perspective.matrix();
+    assert_ne!(projection, perspective);
+}
@@ -3,10 +3,71 @@ use crate::shader::create_shader;
 use super::{screen_quad_renderer::ScreenVertex, DrawParams};
 use bytemuck::offset_of;
 use glow::HasContext;
-use rmc_common::BlockType;
+use rmc_common::{light::light_to_shade, BlockType};
 use std::mem;
 use vek::{Mat3, Vec2};
 
+/// Light the icons are drawn in, as bright as a block in full daylight.
+pub const ICON_LIGHT: u8 = 255;
+
+/// Default angle the icons are looked down at, gives the top face the usual 2:1 pixel art diamond.
+pub const DEFAULT_ELEVATION: f32 = std::f32::consts::FRAC_PI_6;
+
+/// Outline of a unit cube turned 45° around the vertical axis and looked down at from `elevation`
+/// radians above the horizon, fit into the unit square with y pointing down.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IsometricGeometry {
+    /// Clockwise from the top corner.
+    pub points: [Vec2<f32>; 6],
+    /// The corner shared by the three visible faces.
+    pub center: Vec2<f32>,
+}
+
+impl IsometricGeometry {
+    pub fn new(elevation: f32) -> Self {
+        // Turned 45°, the cube is as wide as the diagonal of a face.
+        let half_width = std::f32::consts::FRAC_1_SQRT_2;
+        // Looking down squashes the top face and foreshortens the vertical edges.
+        let half_top_height = half_width * elevation.sin();
+        let edge_height = elevation.cos();
+
+        let size = Vec2::new(2.0 * half_width, 2.0 * half_top_height + edge_height);
+        let scale = 1.0 / size.reduce_partial_max();
+        let w = half_width * scale;
+        let h = half_top_height * scale;
+        let v = edge_height * scale;
+        // Centered vertically when the width is what limits the size.
+        let top = (1.0 - size.y * scale) / 2.0;
+
+        IsometricGeometry {
+            points: [
+                Vec2::new(0.5, top),
+                Vec2::new(0.5 + w, top + h),
+                Vec2::new(0.5 + w, top + h + v),
+                Vec2::new(0.5, top + 2.0 * h + v),
+                Vec2::new(0.5 - w, top + h + v),
+                Vec2::new(0.5 - w, top + h),
+            ],
+            center: Vec2::new(0.5, top + 2.0 * h),
+        }
+    }
+
+    /// The visible faces as their top left, bottom left, bottom right and top right corners,
+    /// with the column of the block texture's first row they're textured with.
+    pub fn faces(&self) -> [([Vec2<f32>; 4], u32); 3] {
+        let [p0, p1, p2, p3, p4, p5] = self.points;
+        let center = self.center;
+        [
+            // +z
+            ([p5, p4, p3, center], 0),
+            // +y
+            ([p0, p5, center, p1], 1),
+            // +x
+            ([center, p3, p2, p1], 2),
+        ]
+    }
+}
+
 pub struct IsometricBlockRenderer {
     pub vao: glow::VertexArray,
     #[allow(dead_code)]
@@ -19,6 +80,10 @@ pub struct IsometricBlockRenderer {
 
 impl IsometricBlockRenderer {
     pub unsafe fn new(gl: &glow::Context) -> Self {
+        Self::with_elevation(gl, DEFAULT_ELEVATION)
+    }
+
+    pub unsafe fn with_elevation(gl: &glow::Context, elevation: f32) -> Self {
         let mut indices = Vec::new();
         let mut vertices = Vec::new();
 
@@ -27,71 +92,19 @@ impl IsometricBlockRenderer {
             vertices.extend_from_slice(&vs);
         };
 
-        // TODO this is still scuffed...
-
-        let angle = 30_f32.to_radians().sin().atan();
-        let h = angle.sin() * std::f32::consts::SQRT_2 / 2.0;
-        let w = angle.cos() * std::f32::consts::SQRT_2 / 2.0;
-
-        let full_height = 0.5 * std::f32::consts::SQRT_2 + h * 2.0;
-        let full_width = 2.0 * w;
-
-        let scale = if full_width > full_height {
-            1.0 / full_width
-        } else {
-            1.0 / full_height
-        };
-
-        let w = w * scale;
-        let h = h * scale;
-        let full_height = full_height * scale;
-
-        let points = [
-            Vec2::new(0.5, 0.0),
-            Vec2::new(0.5 + w, h),
-            Vec2::new(0.5 + w, full_height - h),
-            Vec2::new(0.5, full_height),
-            Vec2::new(0.5 - w, full_height - h),
-            Vec2::new(0.5 - w, h),
-        ];
-
-        let center = Vec2::new(0.5, h * 2.0);
-
-        // Front
-        push([
-            ScreenVertex::new(points[5], Vec2::new(0.0 / 3.0, 0.0 / 2.0)),
-            ScreenVertex::new(points[4], Vec2::new(0.0 / 3.0, 1.0 / 2.0)),
-            ScreenVertex::new(center, Vec2::new(1.0 / 3.0, 0.0 / 2.0)),
-        ]);
-        push([
-            ScreenVertex::new(points[4], Vec2::new(0.0 / 3.0, 1.0 / 2.0)),
-            ScreenVertex::new(points[3], Vec2::new(1.0 / 3.0, 1.0 / 2.0)),
-            ScreenVertex::new(center, Vec2::new(1.0 / 3.0, 0.0 / 2.0)),
-        ]);
-
-        // Right
-        push([
-            ScreenVertex::new(points[3], Vec2::new(2.0 / 3.0, 1.0 / 2.0)),
-            ScreenVertex::new(points[2], Vec2::new(3.0 / 3.0, 1.0 / 2.0)),
-            ScreenVertex::new(center, Vec2::new(2.0 / 3.0, 0.0 / 2.0)),
-        ]);
-        push([
-            ScreenVertex::new(points[2], Vec2::new(3.0 / 3.0, 1.0 / 2.0)),
-            ScreenVertex::new(points[1], Vec2::new(3.0 / 3.0, 0.0 / 2.0)),
-            ScreenVertex::new(center, Vec2::new(2.0 / 3.0, 0.0 / 2.0)),
-        ]);
-
-        // Top
-        push([
-            ScreenVertex::new(points[1], Vec2::new(2.0 / 3.0, 0.0 / 2.0)),
-            ScreenVertex::new(points[0], Vec2::new(1.0 / 3.0, 0.0 / 2.0)),
-            ScreenVertex::new(center, Vec2::new(2.0 / 3.0, 1.0 / 2.0)),
-        ]);
-        push([
-            ScreenVertex::new(points[0], Vec2::new(1.0 / 3.0, 0.0 / 2.0)),
-            ScreenVertex::new(points[5], Vec2::new(1.0 / 3.0, 1.0 / 2.0)),
-            ScreenVertex::new(center, Vec2::new(2.0 / 3.0, 1.0 / 2.0)),
-        ]);
+        for ([top_left, bottom_left, bottom_right, top_right], column) in
+            IsometricGeometry::new(elevation).faces()
+        {
+            let uv_min = Vec2::new(column as f32 / 3.0, 0.0);
+            let uv_max = Vec2::new((column + 1) as f32 / 3.0, 0.5);
+            let top_left = ScreenVertex::new(top_left, uv_min);
+            let bottom_left = ScreenVertex::new(bottom_left, Vec2::new(uv_min.x, uv_max.y));
+            let bottom_right = ScreenVertex::new(bottom_right, uv_max);
+            let top_right = ScreenVertex::new(top_right, Vec2::new(uv_max.x, uv_min.y));
+
+            push([top_left, bottom_left, top_right]);
+            push([bottom_left, bottom_right, top_right]);
+        }
 
         let vao = gl.create_vertex_array().unwrap();
         gl.bind_vertex_array(Some(vao));
@@ -147,7 +160,7 @@ impl IsometricBlockRenderer {
 
     // TODO Instancing
     pub unsafe fn draw(&self, gl: &glow::Context, block_ty: BlockType, params: DrawParams) {
-        if block_ty == BlockType::Air {
+        let Some(texture_layer) = block_ty.texture_layer() else {
             return;
         };
 
@@ -174,10 +187,42 @@ impl IsometricBlockRenderer {
                 &gl.get_uniform_location(self.program, "uniform_TextureLayer")
                     .unwrap(),
             ),
-            block_ty as u32 - 1,
+            texture_layer,
+        );
+        gl.uniform_1_f32(
+            Some(
+                &gl.get_uniform_location(self.program, "uniform_Shade")
+                    .unwrap(),
+            ),
+            light_to_shade(ICON_LIGHT),
         );
 
         gl.bind_vertex_array(Some(self.vao));
         gl.draw_elements(glow::TRIANGLES, 18, glow::UNSIGNED_BYTE, 0);
     }
 }
+
+#[test]
+fn test_default_geometry() {
+    let geometry = IsometricGeometry::new(DEFAULT_ELEVATION);
+    let expected = [
+        Vec2::new(0.5, 0.0),
+        Vec2::new(0.949_49, 0.224_745),
+        Vec2::new(0.949_49, 0.775_255),
+        Vec2::new(0.5, 1.0),
+        Vec2::new(0.050_51, 0.775_255),
+        Vec2::new(0.050_51, 0.224_745),
+    ];
+    for (point, expected) in geometry.points.into_iter().zip(expected) {
+        assert!(point.distance(expected) < 1e-4, "{point} != {expected}");
+    }
+    assert!(geometry.center.distance(Vec2::new(0.5, 0.449_49)) < 1e-4);
+
+    // The top face is twice as wide as it's tall.
+    let [top, right, _, _, _, left] = geometry.points;
+    assert!(((right.x - left.x) - 2.0 * 2.0 * (right.y - top.y)).abs() < 1e-4);
+    // Looking at it from the side, it fits the square by width instead of height.
+    let side = IsometricGeometry::new(0.0);
+    assert!(side.points.iter().all(|p| (0.0..=1.0).contains(&p.y)));
+    assert_eq!(side.points[1].x - side.points[5].x, 1.0);
+}
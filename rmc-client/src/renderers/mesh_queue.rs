@@ -0,0 +1,84 @@
+use std::{collections::HashMap, mem};
+
+use vek::Vec3;
+
+use super::chunk_renderer::Instance;
+
+/// Chunk meshes built during the ticks, waiting to be uploaded when the frame is drawn.
+/// A chunk only has its latest mesh queued, in the order it was first queued.
+#[derive(Debug, Default)]
+pub struct MeshQueue {
+    order: Vec<Vec3<i32>>,
+    meshes: HashMap<Vec3<i32>, Vec<Instance>>,
+}
+
+impl MeshQueue {
+    pub fn new() -> Self {
+        MeshQueue::default()
+    }
+
+    /// Queues `instances` for the chunk at `chunk_coord`, replacing what was queued for it before.
+    pub fn push(&mut self, chunk_coord: Vec3<i32>, instances: Vec<Instance>) {
+        if self.meshes.insert(chunk_coord, instances).is_none() {
+            self.order.push(chunk_coord);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// Takes every queued mesh, they should all be uploaded before the next draw
+    /// so a frame never shows some of the chunks changed in a tick but not the others.
+    pub fn drain(&mut self) -> Vec<(Vec3<i32>, Vec<Instance>)> {
+        let mut meshes = mem::take(&mut self.meshes);
+        self.order
+            .drain(..)
+            .map(|chunk_coord| (chunk_coord, meshes.remove(&chunk_coord).unwrap()))
+            .collect()
+    }
+}
+
+#[test]
+fn test_mesh_queue() {
+    let mesh = |x: f32| {
+        vec![Instance {
+            position: Vec3::new(x, 0.0, 0.0),
+            ..Default::default()
+        }]
+    };
+
+    let mut queue = MeshQueue::new();
+    assert!(queue.is_empty());
+    queue.push(Vec3::new(1, 0, 0), mesh(1.0));
+    queue.push(Vec3::new(0, 0, 0), mesh(2.0));
+    queue.push(Vec3::new(1, 0, 0), mesh(3.0));
+    queue.push(Vec3::new(0, 1, 0), Vec::new());
+    assert_eq!(queue.len(), 3);
+
+    // Chunks keep the place they were first queued in, with only their latest mesh.
+    let drained = queue
+        .drain()
+        .into_iter()
+        .map(|(chunk_coord, instances)| {
+            (
+                chunk_coord,
+                instances.iter().map(|i| i.position.x).collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        drained,
+        vec![
+            (Vec3::new(1, 0, 0), vec![3.0]),
+            (Vec3::new(0, 0, 0), vec![2.0]),
+            (Vec3::new(0, 1, 0), vec![]),
+        ]
+    );
+    assert!(queue.is_empty());
+    assert!(queue.drain().is_empty());
+}
@@ -0,0 +1,140 @@
+use std::cmp::Ordering;
+
+use glow::HasContext;
+use image::{imageops, Rgba, RgbaImage};
+use rmc_common::{
+    world::{World, CHUNK_SIZE},
+    BlockType, Camera,
+};
+use vek::Vec2;
+
+use crate::texture::{create_image, Image};
+
+use super::{DrawParams, ScreenQuadRenderer};
+
+/// Pointing up, i.e towards -z.
+#[rustfmt::skip]
+const MARKER: [&str; 7] = [
+    "...#...",
+    "..###..",
+    ".#####.",
+    "#######",
+    "..###..",
+    "..###..",
+    "..###..",
+];
+
+pub fn block_color(ty: BlockType) -> [u8; 3] {
+    match ty {
+        BlockType::Air => [0, 0, 0],
+        BlockType::Test => [255, 0, 255],
+        BlockType::Grass => [86, 156, 58],
+        BlockType::Lantern => [250, 210, 110],
+        BlockType::Mesh => [160, 160, 170],
+        BlockType::Wood => [130, 94, 54],
+        BlockType::Stone => [125, 125, 125],
+        BlockType::Water => [50, 90, 200],
+        BlockType::StoneSlab => [150, 150, 150],
+        BlockType::Torch => [255, 190, 60],
+        BlockType::Wire => [170, 30, 30],
+        BlockType::PowerSource => [220, 40, 40],
+        BlockType::Dirt => [134, 96, 67],
+    }
+}
+
+/// Top-down view of the surface around the world origin, one pixel per column.
+pub struct MinimapRenderer {
+    pub image: Image,
+    pub marker: Image,
+    /// Chunks shown to each side of the origin chunk.
+    pub radius: i32,
+
+    /// World x and z of the top left pixel as of the last update.
+    min: Vec2<i32>,
+}
+
+impl MinimapRenderer {
+    pub unsafe fn new(gl: &glow::Context, radius: i32) -> Self {
+        let size = ((radius * 2 + 1) * CHUNK_SIZE as i32) as u32;
+        let mut marker = RgbaImage::from_fn(7, 7, |x, y| {
+            match MARKER[y as usize].as_bytes()[x as usize] {
+                b'#' => Rgba([255, 255, 255, 255]),
+                _ => Rgba([0, 0, 0, 0]),
+            }
+        });
+        // The screen quad samples textures bottom row first.
+        imageops::flip_vertical_in_place(&mut marker);
+
+        MinimapRenderer {
+            image: create_image(gl, RgbaImage::new(size, size)),
+            marker: create_image(gl, marker),
+            radius,
+            min: Vec2::zero(),
+        }
+    }
+
+    /// Resamples the surface of `world`, only needed when the loaded chunks have changed.
+    pub unsafe fn update(&mut self, gl: &glow::Context, world: &World) {
+        let chunk_size = CHUNK_SIZE as i32;
+        let origin = Vec2::new(world.origin().x, world.origin().z);
+        self.min = (origin - self.radius) * chunk_size;
+        let max = (origin + self.radius + 1) * chunk_size - 1;
+        let heightmap = world.surface_heightmap(self.min, max);
+
+        let mut image = RgbaImage::from_fn(self.image.size.x, self.image.size.y, |x, y| {
+            let (height, ty) = heightmap[(x as usize, y as usize)];
+            if ty == BlockType::Air {
+                return Rgba([0, 0, 0, 255]);
+            }
+
+            // Slopes facing north are lit, the ones facing south are shaded.
+            let north_height = match y {
+                0 => height,
+                _ => heightmap[(x as usize, y as usize - 1)].0,
+            };
+            let shade = match height.cmp(&north_height) {
+                Ordering::Greater => 1.0,
+                Ordering::Equal => 0.86,
+                Ordering::Less => 0.71,
+            };
+            let [r, g, b] = block_color(ty).map(|c| (c as f32 * shade) as u8);
+            Rgba([r, g, b, 255])
+        });
+        imageops::flip_vertical_in_place(&mut image);
+
+        gl.bind_texture(glow::TEXTURE_2D, Some(self.image.raw));
+        gl.tex_sub_image_2d(
+            glow::TEXTURE_2D,
+            0,
+            0,
+            0,
+            image.width() as _,
+            image.height() as _,
+            glow::RGBA,
+            glow::UNSIGNED_BYTE,
+            glow::PixelUnpackData::Slice(image.as_raw()),
+        );
+    }
+
+    /// Draws the map with a marker on top showing where the camera is and which way it faces.
+    pub unsafe fn draw(
+        &self,
+        gl: &glow::Context,
+        screen_quad_renderer: &ScreenQuadRenderer,
+        camera: &Camera,
+        params: DrawParams,
+    ) {
+        screen_quad_renderer.draw(gl, &self.image, params);
+
+        let top_left = params.position - params.origin * self.image.size.as_() * params.scale;
+        let player = Vec2::new(camera.position.x, camera.position.z) - self.min.as_();
+        screen_quad_renderer.draw(
+            gl,
+            &self.marker,
+            DrawParams::default()
+                .position(top_left + player * params.scale)
+                .origin(Vec2::one() / 2.0)
+                .rotation(camera.yaw.0),
+        );
+    }
+}
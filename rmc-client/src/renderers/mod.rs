@@ -3,18 +3,33 @@ use vek::Vec2;
 pub mod chunk_renderer;
 pub use chunk_renderer::ChunkRenderer;
 
+pub mod chunk_lod;
+pub use chunk_lod::ChunkLods;
+
 pub mod screen_quad_renderer;
 pub use screen_quad_renderer::ScreenQuadRenderer;
 
 pub mod isometric_block_renderer;
 pub use isometric_block_renderer::IsometricBlockRenderer;
 
+pub mod border_renderer;
+pub use border_renderer::BorderRenderer;
+
+pub mod player_renderer;
+pub use player_renderer::PlayerRenderer;
+
+pub mod mesh_queue;
+pub use mesh_queue::MeshQueue;
+
 pub mod game_renderer;
 pub use game_renderer::GameRenderer;
 
 pub mod text_renderer;
 pub use text_renderer::TextRenderer;
 
+pub mod minimap_renderer;
+pub use minimap_renderer::MinimapRenderer;
+
 fn face_to_tri(v: &[u8; 4]) -> [u8; 6] {
     [v[0], v[1], v[3], v[3], v[2], v[0]]
 }
@@ -29,6 +44,8 @@ pub struct DrawParams {
     pub position: Vec2<f32>,
     pub origin: Vec2<f32>,
     pub scale: Vec2<f32>,
+    /// Clockwise, in radians, around `origin`.
+    pub rotation: f32,
 }
 
 impl DrawParams {
@@ -46,6 +63,11 @@ impl DrawParams {
         self.origin = origin;
         self
     }
+
+    pub fn rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
 }
 
 impl Default for DrawParams {
@@ -54,6 +76,7 @@ impl Default for DrawParams {
             position: Vec2::zero(),
             origin: Vec2::zero(),
             scale: Vec2::one(),
+            rotation: 0.0,
         }
     }
 }
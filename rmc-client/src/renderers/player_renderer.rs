@@ -0,0 +1,154 @@
+use glow::HasContext;
+use rmc_common::Angle;
+use vek::{Mat4, Vec3, Vec4};
+
+use crate::shader::create_shader;
+
+use super::{
+    border_renderer::{CORNERS, FACES},
+    face_to_tri,
+};
+
+/// Boxes making up the player's model as (min, max, color), in world units with the feet at the origin
+/// and facing -z like a camera with no yaw.
+const PARTS: [(Vec3<f32>, Vec3<f32>, Vec4<f32>); 3] = [
+    // Legs
+    (
+        Vec3::new(-0.15, 0.0, -0.1),
+        Vec3::new(0.15, 0.75, 0.1),
+        Vec4::new(0.2, 0.25, 0.6, 1.0),
+    ),
+    // Body
+    (
+        Vec3::new(-0.2, 0.75, -0.12),
+        Vec3::new(0.2, 1.4, 0.12),
+        Vec4::new(0.2, 0.6, 0.6, 1.0),
+    ),
+    // Head
+    (
+        Vec3::new(-0.15, 1.4, -0.15),
+        Vec3::new(0.15, 1.75, 0.15),
+        Vec4::new(0.9, 0.7, 0.55, 1.0),
+    ),
+];
+
+/// Transform of the player's model standing at `feet` and facing along `yaw`, see `Game::feet_position`.
+pub fn model_matrix(feet: Vec3<f32>, yaw: Angle) -> Mat4<f32> {
+    Mat4::translation_3d(feet) * Mat4::rotation_y(-yaw.0)
+}
+
+/// Draws the player as a few colored boxes, for when the camera isn't in the player's head.
+pub struct PlayerRenderer {
+    pub vao: glow::VertexArray,
+    #[allow(dead_code)]
+    pub vbo: glow::Buffer,
+    #[allow(dead_code)]
+    pub ebo: glow::Buffer,
+
+    pub program: glow::Program,
+}
+
+impl PlayerRenderer {
+    pub unsafe fn new(gl: &glow::Context) -> Self {
+        let vao = gl.create_vertex_array().unwrap();
+        gl.bind_vertex_array(Some(vao));
+
+        let vbo = gl.create_buffer().unwrap();
+        gl.bind_buffer(glow::ARRAY_BUFFER, Some(vbo));
+        gl.buffer_data_u8_slice(
+            glow::ARRAY_BUFFER,
+            bytemuck::cast_slice(&CORNERS),
+            glow::STATIC_DRAW,
+        );
+
+        gl.enable_vertex_attrib_array(0);
+        gl.vertex_attrib_pointer_f32(0, 3, glow::FLOAT, false, 3 * 4, 0);
+
+        let ebo = gl.create_buffer().unwrap();
+        gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(ebo));
+        gl.buffer_data_u8_slice(
+            glow::ELEMENT_ARRAY_BUFFER,
+            bytemuck::cast_slice::<[u8; 6], u8>(&FACES.map(|face| face_to_tri(&face))),
+            glow::STATIC_DRAW,
+        );
+
+        // The border's shader already stretches the unit cube between two corners in a single color.
+        let program = create_shader(
+            gl,
+            include_str!("../../shaders/border.vert"),
+            include_str!("../../shaders/border.frag"),
+        );
+
+        PlayerRenderer {
+            vao,
+            vbo,
+            ebo,
+            program,
+        }
+    }
+
+    /// Draws the model transformed by `model`, see `model_matrix`.
+    pub unsafe fn draw(&self, gl: &glow::Context, view_projection: Mat4<f32>, model: Mat4<f32>) {
+        // The faces of the cube aren't all wound the same way.
+        gl.disable(glow::CULL_FACE);
+
+        gl.use_program(Some(self.program));
+        gl.uniform_matrix_4_f32_slice(
+            Some(
+                &gl.get_uniform_location(self.program, "uniform_Mvp")
+                    .unwrap(),
+            ),
+            false,
+            (view_projection * model).as_col_slice(),
+        );
+
+        gl.bind_vertex_array(Some(self.vao));
+        for (min, max, color) in PARTS {
+            set_vec3(gl, self.program, "uniform_Min", min);
+            set_vec3(gl, self.program, "uniform_Max", max);
+            gl.uniform_4_f32_slice(
+                Some(
+                    &gl.get_uniform_location(self.program, "uniform_Color")
+                        .unwrap(),
+                ),
+                color.as_slice(),
+            );
+            gl.draw_elements(glow::TRIANGLES, 36, glow::UNSIGNED_BYTE, 0);
+        }
+
+        gl.enable(glow::CULL_FACE);
+    }
+}
+
+unsafe fn set_vec3(gl: &glow::Context, program: glow::Program, name: &str, v: Vec3<f32>) {
+    gl.uniform_3_f32(
+        Some(&gl.get_uniform_location(program, name).unwrap()),
+        v.x,
+        v.y,
+        v.z,
+    );
+}
+
+#[test]
+fn test_model_matrix() {
+    use rmc_common::Camera;
+
+    let feet = Vec3::new(3.5, 40.0, -2.5);
+    for yaw in [0.0, 1.0, std::f32::consts::PI, 4.5] {
+        let model = model_matrix(feet, Angle(yaw));
+        assert!((Vec3::from(model * Vec4::unit_w()) - feet).magnitude() < 1e-5);
+
+        // The front of the model faces where a camera with the same yaw walks.
+        let camera = Camera {
+            position: feet,
+            pitch: Angle(0.0),
+            yaw: Angle(yaw),
+        };
+        let front = Vec3::from(model * Vec4::new(0.0, 0.0, -1.0, 1.0));
+        assert!((front - (feet + camera.forward())).magnitude() < 1e-5);
+
+        // It stands upright.
+        let head = Vec3::from(model * Vec4::new(0.0, 1.75, 0.0, 1.0));
+        assert!((head - (feet + Vec3::unit_y() * 1.75)).magnitude() < 1e-5);
+    }
+}
@@ -30,6 +30,8 @@ pub struct ScreenQuadRenderer {
     pub ebo: glow::Buffer,
 
     pub program: glow::Program,
+    /// Samples a layer of an array texture instead.
+    pub array_program: glow::Program,
 }
 
 impl ScreenQuadRenderer {
@@ -98,38 +100,74 @@ impl ScreenQuadRenderer {
             include_str!("../../shaders/screen.vert"),
             include_str!("../../shaders/screen.frag"),
         );
+        let array_program = create_shader(
+            gl,
+            include_str!("../../shaders/screen.vert"),
+            include_str!("../../shaders/screen_array.frag"),
+        );
 
         ScreenQuadRenderer {
             vao,
             vbo,
             ebo,
             program,
+            array_program,
         }
     }
 
     // TODO Instancing
     pub unsafe fn draw(&self, gl: &glow::Context, image: &Image, params: DrawParams) {
-        let screen_to_view_scale = Vec2::one() / Vec2::new(1024.0, 768.0);
-        // TODO improve
-        let screen_mat = Mat3::<f32>::identity()
-            * Mat3::translation_2d(params.position * screen_to_view_scale)
-            * Mat3::scaling_3d(
-                (screen_to_view_scale * image.size.as_::<f32>() * params.scale).with_z(1.0),
-            )
-            * Mat3::translation_2d(-params.origin);
-
         gl.use_program(Some(self.program));
-        gl.uniform_matrix_3_f32_slice(
+        set_screen_mat(gl, self.program, image.size, params);
+
+        gl.bind_texture(glow::TEXTURE_2D, Some(image.raw));
+        gl.bind_vertex_array(Some(self.vao));
+        gl.draw_elements(glow::TRIANGLES, 6, glow::UNSIGNED_BYTE, 0);
+    }
+
+    /// Draws `layer` of the array `texture`, whose layers are `size` pixels large.
+    pub unsafe fn draw_layer(
+        &self,
+        gl: &glow::Context,
+        texture: glow::Texture,
+        size: Vec2<u32>,
+        layer: u32,
+        params: DrawParams,
+    ) {
+        gl.use_program(Some(self.array_program));
+        set_screen_mat(gl, self.array_program, size, params);
+        gl.uniform_1_u32(
             Some(
-                &gl.get_uniform_location(self.program, "uniform_Mat")
+                &gl.get_uniform_location(self.array_program, "uniform_TextureLayer")
                     .unwrap(),
             ),
-            false,
-            screen_mat.as_col_slice(),
+            layer,
         );
 
-        gl.bind_texture(glow::TEXTURE_2D, Some(image.raw));
+        gl.bind_texture(glow::TEXTURE_2D_ARRAY, Some(texture));
         gl.bind_vertex_array(Some(self.vao));
         gl.draw_elements(glow::TRIANGLES, 6, glow::UNSIGNED_BYTE, 0);
     }
 }
+
+unsafe fn set_screen_mat(
+    gl: &glow::Context,
+    program: glow::Program,
+    size: Vec2<u32>,
+    params: DrawParams,
+) {
+    let screen_to_view_scale = Vec2::one() / Vec2::new(1024.0, 768.0);
+    // TODO improve
+    let screen_mat = Mat3::<f32>::identity()
+        * Mat3::translation_2d(params.position * screen_to_view_scale)
+        * Mat3::scaling_3d(screen_to_view_scale.with_z(1.0))
+        * Mat3::rotation_z(params.rotation)
+        * Mat3::scaling_3d((size.as_::<f32>() * params.scale).with_z(1.0))
+        * Mat3::translation_2d(-params.origin);
+
+    gl.uniform_matrix_3_f32_slice(
+        Some(&gl.get_uniform_location(program, "uniform_Mat").unwrap()),
+        false,
+        screen_mat.as_col_slice(),
+    );
+}
@@ -1,8 +1,10 @@
-use std::mem;
+use std::{mem, path::Path};
 
-use ab_glyph::FontVec;
+use ab_glyph::{FontVec, InvalidFont};
 use glow::HasContext;
-use glyph_brush::{BrushAction, BrushError, Extra, GlyphBrush, GlyphBrushBuilder, GlyphCruncher};
+use glyph_brush::{
+    BrushAction, BrushError, Extra, GlyphBrush, GlyphBrushBuilder, GlyphCruncher, OwnedSection,
+};
 use vek::{Aabr, Mat3, Vec2, Vec4};
 
 use crate::shader::create_shader;
@@ -22,6 +24,80 @@ pub struct TextVertex {
 unsafe impl bytemuck::Pod for TextVertex {}
 unsafe impl bytemuck::Zeroable for TextVertex {}
 
+/// Font used by `TextRenderer::new`.
+pub const DEFAULT_FONT: &[u8] = include_bytes!("../../fonts/Cute Dino.otf");
+
+#[derive(Debug)]
+pub enum FontError {
+    Io(std::io::Error),
+    Invalid(InvalidFont),
+}
+
+impl From<std::io::Error> for FontError {
+    fn from(e: std::io::Error) -> Self {
+        FontError::Io(e)
+    }
+}
+
+impl From<InvalidFont> for FontError {
+    fn from(e: InvalidFont) -> Self {
+        FontError::Invalid(e)
+    }
+}
+
+pub fn load_font(font_bytes: &[u8]) -> Result<FontVec, FontError> {
+    Ok(FontVec::try_from_vec(font_bytes.to_vec())?)
+}
+
+/// Builds a section out of runs of text that each have their own color and scale.
+/// `color` and `scale` apply to every run pushed after them.
+#[derive(Debug, Clone)]
+pub struct RichText {
+    color: Vec4<f32>,
+    scale: f32,
+    runs: Vec<(String, Vec4<f32>, f32)>,
+}
+
+impl Default for RichText {
+    fn default() -> Self {
+        RichText {
+            color: Vec4::one(),
+            scale: 48.0,
+            runs: Vec::new(),
+        }
+    }
+}
+
+impl RichText {
+    pub fn color(mut self, color: Vec4<f32>) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn push(mut self, text: impl Into<String>) -> Self {
+        self.runs.push((text.into(), self.color, self.scale));
+        self
+    }
+
+    pub fn section(&self) -> glyph_brush::Section<'_> {
+        glyph_brush::Section::default().with_text(
+            self.runs
+                .iter()
+                .map(|(text, color, scale)| {
+                    glyph_brush::Text::new(text)
+                        .with_color(color.into_array())
+                        .with_scale(*scale)
+                })
+                .collect(),
+        )
+    }
+}
+
 pub struct TextRenderer {
     pub vao: glow::VertexArray,
     pub ib: glow::Buffer,
@@ -32,11 +108,30 @@ pub struct TextRenderer {
     pub glyph_brush: GlyphBrush<TextVertex, Extra, FontVec>,
     pub glyph_count: usize,
 
-    pub section: glyph_brush::Section<'static>,
+    pub section: OwnedSection,
 }
 
 impl TextRenderer {
-    pub unsafe fn new(gl: &glow::Context, section: glyph_brush::Section<'static>) -> Self {
+    pub unsafe fn new(gl: &glow::Context, section: glyph_brush::Section<'_>) -> Self {
+        Self::with_font(gl, DEFAULT_FONT, section).expect("the default font is invalid")
+    }
+
+    pub unsafe fn with_font_path(
+        gl: &glow::Context,
+        path: impl AsRef<Path>,
+        section: glyph_brush::Section<'_>,
+    ) -> Result<Self, FontError> {
+        Self::with_font(gl, &std::fs::read(path)?, section)
+    }
+
+    // Accepts any font format ab_glyph supports.
+    pub unsafe fn with_font(
+        gl: &glow::Context,
+        font_bytes: &[u8],
+        section: glyph_brush::Section<'_>,
+    ) -> Result<Self, FontError> {
+        let font = load_font(font_bytes)?;
+
         let vao = gl.create_vertex_array().unwrap();
         gl.bind_vertex_array(Some(vao));
         let vbo = gl.create_buffer().unwrap();
@@ -99,9 +194,8 @@ impl TextRenderer {
             include_str!("../../shaders/text.frag"),
         );
 
-        let font =
-            FontVec::try_from_vec(Vec::from(include_bytes!("../../fonts/Cute Dino.otf"))).unwrap();
         let mut glyph_brush = GlyphBrushBuilder::using_font(font).build();
+        let section = section.to_owned();
         glyph_brush.queue(&section);
 
         let texture = gl.create_texture().unwrap();
@@ -151,11 +245,11 @@ impl TextRenderer {
 
         r.flush(gl);
 
-        r
+        Ok(r)
     }
 
-    pub fn set_section(&mut self, section: glyph_brush::Section<'static>) {
-        self.section = section;
+    pub fn set_section(&mut self, section: glyph_brush::Section<'_>) {
+        self.section = section.to_owned();
         self.glyph_brush.queue(&self.section)
     }
 
@@ -273,3 +367,44 @@ fn to_vertex(
         color: extra.color.into(),
     }
 }
+
+#[test]
+fn test_alternate_font() {
+    let section = glyph_brush::Section::default().add_text(glyph_brush::Text::new("Hello, world!"));
+    let bounds = |font_bytes: &[u8]| {
+        let mut brush: GlyphBrush<TextVertex, Extra, FontVec> =
+            GlyphBrushBuilder::using_font(load_font(font_bytes).unwrap()).build();
+        brush.glyph_bounds(&section).unwrap()
+    };
+
+    let default = bounds(DEFAULT_FONT);
+    let mono = bounds(include_bytes!("../../fonts/DejaVuSansMono.ttf"));
+    assert!(default.width() > 0.0 && mono.width() > 0.0);
+    assert_ne!(default, mono);
+
+    assert!(matches!(
+        load_font(b"definitely not a font"),
+        Err(FontError::Invalid(_))
+    ));
+}
+
+#[test]
+fn test_rich_text_colors() {
+    let white = Vec4::one();
+    let red = Vec4::new(1.0, 0.0, 0.0, 1.0);
+    let text = RichText::default()
+        .push("XYZ")
+        .color(red)
+        .scale(24.0)
+        .push("HP");
+
+    let mut brush: GlyphBrush<TextVertex, Extra, FontVec> =
+        GlyphBrushBuilder::using_font(load_font(DEFAULT_FONT).unwrap()).build();
+    brush.queue(text.section());
+    let Ok(BrushAction::Draw(vertices)) = brush.process_queued(|_, _| {}, to_vertex) else {
+        panic!("expected the queued section to be drawn");
+    };
+
+    let colors = vertices.iter().map(|v| v.color).collect::<Vec<_>>();
+    assert_eq!(colors, [white, white, white, red, red]);
+}
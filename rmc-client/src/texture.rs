@@ -27,6 +27,15 @@ pub unsafe fn load_image(gl: &glow::Context, data_source: DataSource<'_, [u8]>)
     }
 }
 
+pub unsafe fn create_image(gl: &glow::Context, image: image::RgbaImage) -> Image {
+    let size = Vec2::from(image.dimensions());
+
+    Image {
+        raw: load_texture_image(gl, image),
+        size,
+    }
+}
+
 pub unsafe fn load_texture(gl: &glow::Context, data_source: DataSource<'_, [u8]>) -> glow::Texture {
     load_texture_image(
         gl,
@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+
+use itertools::iproduct;
+use vek::Vec3;
+
+use crate::{
+    coords,
+    world::{face_to_normal, World},
+};
+
+/// Ambient occlusion of a corner with no occluders around it.
+pub const MAX_AO: u8 = 3;
+
+/// Ambient occlusion of a corner from whether the two blocks along its edges and the block in its corner occlude.
+/// A corner with both edges occluded is fully dark, the corner block can't be seen past them.
+pub fn vertex_ao(side1: bool, side2: bool, corner: bool) -> u8 {
+    if side1 && side2 {
+        0
+    } else {
+        MAX_AO - (side1 as u8 + side2 as u8 + corner as u8)
+    }
+}
+
+/// The two axes along face `face`, in increasing order.
+fn tangent_axes(face: u8) -> (usize, usize) {
+    match face % 3 {
+        0 => (1, 2),
+        1 => (0, 2),
+        _ => (0, 1),
+    }
+}
+
+/// Positions whose blocks `face_ao` looks at for face `face` of the block at `position`,
+/// the layer of 3x3 blocks in front of the face.
+pub fn ao_samples(position: Vec3<i32>, face: u8) -> impl Iterator<Item = Vec3<i32>> {
+    let front = position + face_to_normal(face);
+    let (u, v) = tangent_axes(face);
+    iproduct!(-1..=1, -1..=1).map(move |(du, dv)| {
+        let mut sample = front;
+        sample[u] += du;
+        sample[v] += dv;
+        sample
+    })
+}
+
+/// Ambient occlusion of each corner of face `face` of the block at `position`, from `MAX_AO` for no occlusion to 0.
+/// Corners are ordered (-, -), (+, -), (-, +), (+, +) along the face's two axes, x before y before z.
+/// Blocks that let light through and unloaded blocks don't occlude.
+pub fn face_ao(world: &World, position: Vec3<i32>, face: u8) -> [u8; 4] {
+    let front = position + face_to_normal(face);
+    let (u, v) = tangent_axes(face);
+    let occludes = |du: i32, dv: i32| {
+        let mut sample = front;
+        sample[u] += du;
+        sample[v] += dv;
+        world
+            .get_block(sample)
+            .is_some_and(|block| !block.ty.light_passing())
+    };
+
+    [(-1, -1), (1, -1), (-1, 1), (1, 1)]
+        .map(|(du, dv)| vertex_ao(occludes(du, 0), occludes(0, dv), occludes(du, dv)))
+}
+
+/// Every face whose `face_ao` can change when the block at `position` changes, as (block position, face).
+pub fn affected_faces(position: Vec3<i32>) -> impl Iterator<Item = (Vec3<i32>, u8)> {
+    (0..6).flat_map(move |face| {
+        let normal = face_to_normal(face);
+        // The faces whose samples include `position` are the ones found by sampling back from it.
+        ao_samples(position - normal * 2, face).map(move |sample| (sample, face))
+    })
+}
+
+/// `face_ao` of the faces of a chunk, by block position and face.
+type ChunkAo = HashMap<(Vec3<i32>, u8), [u8; 4]>;
+
+/// `face_ao` of the faces meshed so far, kept per chunk so remeshing doesn't recompute them.
+/// The cache doesn't see edits itself, `invalidate` has to be called for every block that changes
+/// and `invalidate_chunk` for every chunk that's loaded or unloaded.
+#[derive(Debug, Default, Clone)]
+pub struct AoCache {
+    chunks: HashMap<Vec3<i32>, ChunkAo>,
+}
+
+impl AoCache {
+    pub fn new() -> Self {
+        AoCache::default()
+    }
+
+    pub fn get(&self, position: Vec3<i32>, face: u8) -> Option<[u8; 4]> {
+        self.chunks
+            .get(&coords::world_to_chunk(position))?
+            .get(&(position, face))
+            .copied()
+    }
+
+    pub fn get_or_compute(&mut self, world: &World, position: Vec3<i32>, face: u8) -> [u8; 4] {
+        *self
+            .chunks
+            .entry(coords::world_to_chunk(position))
+            .or_default()
+            .entry((position, face))
+            .or_insert_with(|| face_ao(world, position, face))
+    }
+
+    /// Forgets the faces affected by the block at `position`,
+    /// including the faces of blocks in the neighboring chunks when it's on the edge of its chunk.
+    pub fn invalidate(&mut self, position: Vec3<i32>) {
+        for (block, face) in affected_faces(position) {
+            if let Some(chunk) = self.chunks.get_mut(&coords::world_to_chunk(block)) {
+                chunk.remove(&(block, face));
+            }
+        }
+    }
+
+    /// Forgets the faces of the chunk at `chunk_coord`, and the faces of the neighboring chunks that look into it.
+    pub fn invalidate_chunk(&mut self, chunk_coord: Vec3<i32>) {
+        self.chunks.remove(&chunk_coord);
+        for (dx, dy, dz) in iproduct!(-1..=1, -1..=1, -1..=1) {
+            let Some(chunk) = self.chunks.get_mut(&(chunk_coord + Vec3::new(dx, dy, dz))) else {
+                continue;
+            };
+            chunk.retain(|&(position, face), _| {
+                ao_samples(position, face)
+                    .all(|sample| coords::world_to_chunk(sample) != chunk_coord)
+            });
+        }
+    }
+
+    /// Number of faces cached over all chunks.
+    pub fn len(&self) -> usize {
+        self.chunks.values().map(HashMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[test]
+fn test_face_ao() {
+    use crate::{world::Chunk, Block};
+
+    let mut world = World::new(Vec3::zero());
+    world.load(Vec3::zero(), Chunk::new());
+    let position = Vec3::new(4, 4, 4);
+    assert_eq!(face_ao(&world, position, 1), [MAX_AO; 4]);
+
+    // A block next to the top face darkens the two corners along it, a block diagonal to it only one corner.
+    world.set_block(Vec3::new(5, 5, 4), Block::STONE).unwrap();
+    assert_eq!(face_ao(&world, position, 1), [3, 2, 3, 2]);
+    world.set_block(Vec3::new(3, 5, 3), Block::STONE).unwrap();
+    assert_eq!(face_ao(&world, position, 1), [2, 2, 3, 2]);
+    world.set_block(Vec3::new(4, 5, 5), Block::STONE).unwrap();
+    assert_eq!(face_ao(&world, position, 1), [2, 2, 2, 0]);
+
+    // See-through blocks don't occlude.
+    world.set_block(Vec3::new(4, 5, 5), Block::WATER).unwrap();
+    assert_eq!(face_ao(&world, position, 1), [2, 2, 3, 2]);
+}
+
+#[test]
+fn test_ao_cache_invalidation() {
+    use crate::{game::TerrainSampler, world::generate_chunk, Block};
+    use itertools::Itertools;
+
+    let terrain = TerrainSampler::new(42);
+    let mut world = World::new(Vec3::zero());
+    for chunk_coord in [Vec3::zero(), Vec3::unit_x()] {
+        world.load(chunk_coord, generate_chunk(&terrain, chunk_coord));
+    }
+
+    // Every face of the blocks around the edge between the two chunks.
+    let mut cache = AoCache::new();
+    let faces = iproduct!(12..20, 0..8, 0..8, 0..6)
+        .map(|(x, y, z, face)| (Vec3::new(x, y, z), face))
+        .collect_vec();
+    for &(position, face) in &faces {
+        cache.get_or_compute(&world, position, face);
+    }
+    assert_eq!(cache.len(), faces.len());
+
+    // On the edge of chunk 0, so faces in chunk 1 are affected too.
+    let edited = Vec3::new(15, 3, 3);
+    let affected = affected_faces(edited).collect_vec();
+    assert!(affected
+        .iter()
+        .any(|&(p, _)| coords::world_to_chunk(p) == Vec3::unit_x()));
+    let before = world.get_block(edited).unwrap();
+    world
+        .set_block(
+            edited,
+            if before.ty.light_passing() {
+                Block::STONE
+            } else {
+                Block::AIR
+            },
+        )
+        .unwrap();
+    cache.invalidate(edited);
+
+    for &(position, face) in &faces {
+        let cached = cache.get(position, face);
+        if affected.contains(&(position, face)) {
+            assert_eq!(cached, None);
+        } else {
+            // Untouched faces are still right without recomputing them.
+            assert_eq!(cached, Some(face_ao(&world, position, face)));
+        }
+    }
+    // The invalidated faces were all in the region, so exactly those are gone.
+    assert_eq!(cache.len(), faces.len() - affected.len());
+
+    // Unloading chunk 1 forgets its faces and the faces of chunk 0 that look into it.
+    cache.invalidate_chunk(Vec3::unit_x());
+    for &(position, face) in &faces {
+        let kept = coords::world_to_chunk(position) == Vec3::zero()
+            && ao_samples(position, face)
+                .all(|sample| coords::world_to_chunk(sample) != Vec3::unit_x())
+            && !affected.contains(&(position, face));
+        assert_eq!(cache.get(position, face).is_some(), kept);
+    }
+}
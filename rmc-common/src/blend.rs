@@ -11,7 +11,9 @@ pub trait Blend {
 }
 
 impl Blend for f32 {
+    /// `alpha` is clamped to `[0, 1]`, so the result never overshoots past either value.
     fn blend(&self, other: &Self, alpha: f32) -> Self {
+        let alpha = alpha.clamp(0.0, 1.0);
         *self * (1.0 - alpha) + *other * alpha
     }
 }
@@ -22,6 +24,12 @@ impl Blend for i32 {
     }
 }
 
+impl Blend for u32 {
+    fn blend(&self, other: &Self, alpha: f32) -> Self {
+        (*self as f32).blend(&(*other as f32), alpha) as _
+    }
+}
+
 impl Blend for usize {
     fn blend(&self, other: &Self, alpha: f32) -> Self {
         (*self as f32).blend(&(*other as f32), alpha) as _
@@ -46,9 +54,13 @@ impl<T: Blend + PartialEq> Blend for Rc<T> {
     }
 }
 
-impl<T: Blend + Default + Clone> Blend for Array3<T> {
+impl<T: Blend + Default + Clone + PartialEq> Blend for Array3<T> {
+    /// Identical arrays are cloned without blending every element, most of them don't change between ticks.
     fn blend(&self, other: &Self, alpha: f32) -> Self {
         assert_eq!(self.shape(), other.shape());
+        if self.as_ptr() == other.as_ptr() || self == other {
+            return self.clone();
+        }
 
         let shape = self.shape();
         let mut result = Array3::default((shape[0], shape[1], shape[2]));
@@ -88,6 +100,46 @@ impl<T: DiscreteBlend + Clone> Blend for T {
 pub trait DiscreteBlend {}
 
 impl DiscreteBlend for bool {}
+impl DiscreteBlend for u64 {}
 impl<T> DiscreteBlend for Vec<T> {}
 impl<T> DiscreteBlend for VecDeque<T> {}
 impl<T> DiscreteBlend for HashSet<T> {}
+
+#[test]
+fn test_blend_alpha_clamped() {
+    assert_eq!(2.0.blend(&4.0, 0.5), 3.0);
+    assert_eq!(2.0.blend(&4.0, 1.3), 4.0);
+    assert_eq!(2.0.blend(&4.0, -0.3), 2.0);
+    assert_eq!(
+        Vec3::new(0.0, 1.0, 2.0).blend(&Vec3::new(1.0, 3.0, 2.0), 1.3),
+        Vec3::new(1.0, 3.0, 2.0)
+    );
+}
+
+#[test]
+fn test_array3_blend_fast_path() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static BLENDS: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug, Default, Clone, PartialEq)]
+    struct Counted(f32);
+
+    impl Blend for Counted {
+        fn blend(&self, other: &Self, alpha: f32) -> Self {
+            BLENDS.fetch_add(1, Ordering::Relaxed);
+            Counted(self.0.blend(&other.0, alpha))
+        }
+    }
+
+    let a = Array3::from_elem((4, 4, 4), Counted(1.0));
+    assert_eq!(a.blend(&a.clone(), 0.5), a);
+    assert_eq!(BLENDS.load(Ordering::Relaxed), 0);
+
+    let mut b = a.clone();
+    b[(1, 2, 3)] = Counted(3.0);
+    let blended = a.blend(&b, 0.5);
+    assert_eq!(BLENDS.load(Ordering::Relaxed), 4 * 4 * 4);
+    assert_eq!(blended[(1, 2, 3)], Counted(2.0));
+    assert_eq!(blended[(0, 0, 0)], Counted(1.0));
+}
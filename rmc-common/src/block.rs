@@ -1,38 +1,186 @@
-use crate::DiscreteBlend;
+use crate::{
+    world::{face_to_normal, World},
+    DiscreteBlend,
+};
 use enum_assoc::Assoc;
 use std::fmt::{Display, Formatter};
+use vek::{Aabb, Vec3};
 
-#[derive(Debug, Default, PartialEq, Eq, Copy, Clone, Assoc)]
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+#[repr(u8)]
+pub enum BlockShape {
+    #[default]
+    Full,
+    BottomSlab,
+    /// Thin stick standing on the floor, see `Block::aabb` for torches attached to walls.
+    Torch,
+}
+
+impl BlockShape {
+    /// The space taken up by a block of this shape placed at `position`.
+    pub fn aabb(self, position: Vec3<i32>) -> Aabb<f32> {
+        let min = position.as_::<f32>();
+        let size = match self {
+            BlockShape::Full => Vec3::one(),
+            BlockShape::BottomSlab => Vec3::new(1.0, 0.5, 1.0),
+            BlockShape::Torch => {
+                return Aabb {
+                    min: min + Vec3::new(0.4375, 0.0, 0.4375),
+                    max: min + Vec3::new(0.5625, 0.625, 0.5625),
+                }
+            }
+        };
+
+        Aabb {
+            min,
+            max: min + size,
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone, Hash, Assoc)]
 #[func(pub fn light_emission(&self) -> Option<u8>)]
+#[func(pub fn power_emission(&self) -> Option<u8>)]
 #[func(pub fn light_passing(&self) -> bool { false })]
 #[func(pub fn is_air(&self) -> bool { false })]
+#[func(pub fn is_replaceable(&self) -> bool { false })]
+#[func(pub fn is_fluid(&self) -> bool { false })]
+#[func(pub fn is_solid_collision(&self) -> bool { true })]
 #[func(pub fn name(&self) -> &'static str { "??" })]
+#[func(pub fn texture_layer(&self) -> Option<u32>)]
+#[func(pub fn shape(&self) -> BlockShape { BlockShape::Full })]
+#[func(pub fn hardness(&self) -> f32 { 1.0 })]
+#[func(pub fn texture_variants(&self) -> u8 { 1 })]
 #[repr(u8)]
 pub enum BlockType {
     #[default]
     #[assoc(light_passing = true)]
     #[assoc(is_air = true)]
+    #[assoc(is_replaceable = true)]
+    #[assoc(is_solid_collision = false)]
     Air,
 
     #[assoc(name = "Test")]
+    #[assoc(texture_layer = 0)]
     Test,
 
+    /// The top is turned a random way, see `texture_variants`.
     #[assoc(name = "Grass")]
+    #[assoc(texture_layer = 1)]
+    #[assoc(hardness = 0.6)]
+    #[assoc(texture_variants = 4)]
     Grass,
 
     #[assoc(light_emission = 224)]
     #[assoc(name = "Lantern")]
+    #[assoc(texture_layer = 2)]
+    #[assoc(hardness = 0.5)]
     Lantern,
 
+    /// Drawn like a cube, but it can be walked through.
     #[assoc(light_passing = true)]
+    #[assoc(is_solid_collision = false)]
     #[assoc(name = "Mesh")]
+    #[assoc(texture_layer = 3)]
     Mesh,
 
     #[assoc(name = "Wood")]
+    #[assoc(texture_layer = 4)]
+    #[assoc(hardness = 2.0)]
     Wood,
 
     #[assoc(name = "Stone")]
+    #[assoc(texture_layer = 5)]
+    #[assoc(hardness = 3.0)]
+    #[assoc(texture_variants = 4)]
     Stone,
+
+    #[assoc(light_passing = true)]
+    #[assoc(is_replaceable = true)]
+    #[assoc(is_fluid = true)]
+    #[assoc(is_solid_collision = false)]
+    #[assoc(name = "Water")]
+    #[assoc(texture_layer = 6)]
+    Water,
+
+    #[assoc(light_passing = true)]
+    #[assoc(name = "Stone Slab")]
+    #[assoc(texture_layer = 7)]
+    #[assoc(hardness = 3.0)]
+    #[assoc(shape = BlockShape::BottomSlab)]
+    StoneSlab,
+
+    #[assoc(light_emission = 160)]
+    #[assoc(light_passing = true)]
+    #[assoc(is_solid_collision = false)]
+    #[assoc(name = "Torch")]
+    #[assoc(texture_layer = 8)]
+    #[assoc(hardness = 0.0)]
+    #[assoc(shape = BlockShape::Torch)]
+    Torch,
+
+    /// Carries power from neighboring sources and wires, see `power::calculate_power`.
+    #[assoc(light_passing = true)]
+    #[assoc(name = "Wire")]
+    #[assoc(texture_layer = 9)]
+    #[assoc(hardness = 0.0)]
+    Wire,
+
+    #[assoc(power_emission = 15)]
+    #[assoc(name = "Power Source")]
+    #[assoc(texture_layer = 10)]
+    PowerSource,
+
+    /// Turns into grass next to grass, see `random_tick`.
+    #[assoc(name = "Dirt")]
+    #[assoc(texture_layer = 11)]
+    #[assoc(hardness = 0.5)]
+    Dirt,
+}
+
+impl BlockType {
+    pub const ALL: [BlockType; 13] = [
+        BlockType::Air,
+        BlockType::Test,
+        BlockType::Grass,
+        BlockType::Lantern,
+        BlockType::Mesh,
+        BlockType::Wood,
+        BlockType::Stone,
+        BlockType::Water,
+        BlockType::StoneSlab,
+        BlockType::Torch,
+        BlockType::Wire,
+        BlockType::PowerSource,
+        BlockType::Dirt,
+    ];
+
+    pub fn from_id(id: u8) -> Option<BlockType> {
+        BlockType::ALL.into_iter().find(|&ty| ty as u8 == id)
+    }
+
+    /// The edit a random tick of a block of this type at `position` makes, None if it doesn't do anything.
+    /// `roll` is a random number that picks between what could happen, see `Game::update_random_ticks`.
+    pub fn random_tick(
+        &self,
+        world: &World,
+        position: Vec3<i32>,
+        roll: u64,
+    ) -> Option<(Vec3<i32>, Block)> {
+        match self {
+            // Spreads to one of the 3x3x3 blocks around it, if that's dirt that isn't covered up.
+            BlockType::Grass => {
+                let offset = Vec3::new(roll % 3, roll / 3 % 3, roll / 9 % 3).as_::<i32>() - 1;
+                let target = position + offset;
+                let covered = world
+                    .get_block(target + Vec3::unit_y())
+                    .is_none_or(|above| !above.ty.light_passing());
+                (world.get_block(target)?.ty == BlockType::Dirt && !covered)
+                    .then_some((target, Block::GRASS))
+            }
+            _ => None,
+        }
+    }
 }
 
 impl Display for BlockType {
@@ -41,14 +189,25 @@ impl Display for BlockType {
     }
 }
 
-#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone, Hash)]
 pub struct Block {
     pub ty: BlockType,
     pub light: u8,
     pub open_to_sky: bool,
 
+    /// Light coming from the sky, dimmed by every solid block above this one.
+    pub sky_light: u8,
+
     /// Whether a block is fully occluded from view or not, used for rendering optimization.
+    /// Set by `generate_chunk` and kept up to date by block updates, occluded blocks aren't meshed.
     pub occluded: bool,
+
+    /// The face this block points towards, see `face_to_normal`.
+    /// Torches point away from the block they're attached to.
+    pub orientation: u8,
+
+    /// Signal carried by wires, 0 to `power::MAX_POWER`.
+    pub power: u8,
 }
 
 impl Block {
@@ -57,7 +216,10 @@ impl Block {
             ty,
             light: 0,
             open_to_sky: false,
+            sky_light: 0,
             occluded: false,
+            orientation: 0,
+            power: 0,
         }
     }
 
@@ -66,6 +228,72 @@ impl Block {
         self
     }
 
+    pub const fn with_orientation(mut self, face: u8) -> Block {
+        self.orientation = face;
+        self
+    }
+
+    pub const fn with_open_to_sky(mut self, open_to_sky: bool) -> Block {
+        self.open_to_sky = open_to_sky;
+        self
+    }
+
+    pub const fn with_sky_light(mut self, sky_light: u8) -> Block {
+        self.sky_light = sky_light;
+        self
+    }
+
+    pub const fn with_occluded(mut self, occluded: bool) -> Block {
+        self.occluded = occluded;
+        self
+    }
+
+    pub const fn with_power(mut self, power: u8) -> Block {
+        self.power = power;
+        self
+    }
+
+    /// The space taken up by this block placed at `position`.
+    pub fn aabb(&self, position: Vec3<i32>) -> Aabb<f32> {
+        let aabb = self.ty.shape().aabb(position);
+        match self.ty.shape() {
+            // Wall torches lean against the wall, slightly raised.
+            BlockShape::Torch if self.orientation != 1 => {
+                let normal = face_to_normal(self.orientation).as_::<f32>();
+                let offset = Vec3::new(-normal.x * 0.4375, 0.2, -normal.z * 0.4375);
+                Aabb {
+                    min: aabb.min + offset,
+                    max: aabb.max + offset,
+                }
+            }
+            _ => aabb,
+        }
+    }
+
+    /// `aabb` in world units, with blocks `scale` units wide.
+    pub fn aabb_scaled(&self, position: Vec3<i32>, scale: f32) -> Aabb<f32> {
+        let aabb = self.aabb(position);
+        Aabb {
+            min: aabb.min * scale,
+            max: aabb.max * scale,
+        }
+    }
+
+    /// The block this one needs to stay in place, if any.
+    pub fn attached_to(&self, position: Vec3<i32>) -> Option<Vec3<i32>> {
+        (self.ty == BlockType::Torch).then(|| position - face_to_normal(self.orientation))
+    }
+
+    /// Whether the chunk mesh would look the same with `other` in place of this block.
+    /// `light` is baked into the faces of the neighboring blocks so it counts,
+    /// `open_to_sky` and `sky_light` only feed into calculating it and are ignored.
+    pub fn same_appearance(&self, other: &Block) -> bool {
+        self.ty == other.ty
+            && self.occluded == other.occluded
+            && self.light == other.light
+            && self.orientation == other.orientation
+    }
+
     pub const AIR: Block = Block::new(BlockType::Air);
     pub const TEST: Block = Block::new(BlockType::Test);
     pub const GRASS: Block = Block::new(BlockType::Grass);
@@ -74,6 +302,101 @@ impl Block {
     pub const MESH: Block = Block::new(BlockType::Mesh);
     pub const WOOD: Block = Block::new(BlockType::Wood);
     pub const STONE: Block = Block::new(BlockType::Stone);
+    pub const WATER: Block = Block::new(BlockType::Water);
+    pub const STONE_SLAB: Block = Block::new(BlockType::StoneSlab);
+    /// Standing on the floor.
+    pub const TORCH: Block = Block::new(BlockType::Torch).with_orientation(1);
+    pub const WIRE: Block = Block::new(BlockType::Wire);
+    pub const POWER_SOURCE: Block = Block::new(BlockType::PowerSource);
+    pub const DIRT: Block = Block::new(BlockType::Dirt);
 }
 
 impl DiscreteBlend for Block {}
+
+#[test]
+fn test_same_appearance() {
+    let block = Block::STONE.with_light(100);
+
+    assert!(block.same_appearance(&block));
+    assert!(!block.same_appearance(&Block::WOOD.with_light(100)));
+    assert!(!block.same_appearance(&Block::STONE.with_light(120)));
+    assert!(!block.same_appearance(&Block {
+        occluded: true,
+        ..block
+    }));
+
+    // Changes that only matter for relighting don't need a remesh.
+    assert!(block.same_appearance(&Block {
+        sky_light: 255,
+        open_to_sky: true,
+        ..block
+    }));
+}
+
+#[test]
+fn test_block_builder() {
+    use crate::Apply;
+
+    // Everything not set is the same as a fresh block.
+    let fresh = Block::new(BlockType::Stone);
+    assert_eq!(
+        fresh,
+        Block {
+            ty: BlockType::Stone,
+            ..Default::default()
+        }
+    );
+    assert_eq!(Block::STONE.with_light(0).with_power(0), fresh);
+
+    let block = Block::new(BlockType::Wire)
+        .with_light(5)
+        .with_open_to_sky(true)
+        .with_sky_light(200)
+        .with_occluded(true)
+        .with_orientation(3)
+        .with_power(7);
+    assert_eq!(
+        block,
+        Block {
+            ty: BlockType::Wire,
+            light: 5,
+            open_to_sky: true,
+            sky_light: 200,
+            occluded: true,
+            orientation: 3,
+            power: 7,
+        }
+    );
+
+    // `apply` works for anything the builder doesn't cover.
+    assert_eq!(
+        Block::new(BlockType::Wire).apply(|b| b.light = 5),
+        Block::new(BlockType::Wire).with_light(5)
+    );
+    assert_eq!(
+        block.apply(|b| b.ty = BlockType::Wood),
+        Block {
+            ty: BlockType::Wood,
+            ..block
+        }
+    );
+}
+
+#[test]
+fn test_texture_layers() {
+    let layers = BlockType::ALL
+        .into_iter()
+        .filter(|ty| !ty.is_air())
+        .map(|ty| ty.texture_layer().expect("non-air block without a texture"))
+        .collect::<Vec<_>>();
+
+    assert_eq!(BlockType::Air.texture_layer(), None);
+    for (i, layer) in layers.iter().enumerate() {
+        assert!(
+            (*layer as usize) < layers.len(),
+            "layer {} out of range",
+            layer
+        );
+        assert!(!layers[..i].contains(layer), "layer {} used twice", layer);
+    }
+}
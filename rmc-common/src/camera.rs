@@ -1,6 +1,10 @@
 use crate::Blend;
-use std::f32::consts::TAU;
-use vek::{Mat4, Quaternion, Vec3, Wrap};
+use std::f32::consts::{PI, TAU};
+use vek::{Mat4, Quaternion, Vec3, Vec4, Wrap};
+
+/// Distance the far plane gets clamped to in `Camera::frustum_corners`,
+/// needed for projections with an infinite far plane.
+pub const FRUSTUM_MAX_DISTANCE: f32 = 128.0;
 
 #[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
 /// 0 to tau
@@ -40,6 +44,22 @@ impl Angle {
     pub fn cos(self) -> f32 {
         self.0.cos()
     }
+
+    /// Signed shortest rotation from `self` to `other`, in (-pi, pi].
+    /// Angles exactly opposite of each other are always pi apart, never -pi.
+    pub fn delta(self, other: Angle) -> f32 {
+        let delta = (other.0 - self.0).wrapped_2pi();
+        if delta > PI {
+            delta - TAU
+        } else {
+            delta
+        }
+    }
+
+    /// Rotates `t` of the way from `self` to `target`, going the shortest way around.
+    pub fn lerp_shortest(self, target: Angle, t: f32) -> Angle {
+        Angle((self.0 + self.delta(target) * t).wrapped_2pi())
+    }
 }
 
 impl std::ops::Neg for Angle {
@@ -81,6 +101,50 @@ impl Camera {
             .rotated_x(self.pitch.0)
     }
 
+    /// `to_matrix` with `bob` applied, it only moves what's rendered and not the camera itself.
+    pub fn to_matrix_with_bob(&self, bob: ViewBob) -> Mat4<f32> {
+        Mat4::<f32>::identity()
+            .translated_3d(-(self.position + Vec3::unit_y() * bob.vertical))
+            .rotated_y(self.yaw.0)
+            .rotated_x(self.pitch.0)
+            .rotated_z(bob.roll)
+    }
+
+    /// World space corners of the view volume of `projection`, the near plane first and then the far plane.
+    /// Each plane is ordered (-x, -y), (+x, -y), (-x, +y), (+x, +y) in NDC.
+    /// The far plane is at most `FRUSTUM_MAX_DISTANCE` away.
+    pub fn frustum_corners(&self, projection: &Mat4<f32>) -> [Vec3<f32>; 8] {
+        let inverse = (*projection * self.to_matrix()).inverted();
+        let unproject = |ndc: Vec3<f32>| {
+            let point = inverse * Vec4::from_point(ndc);
+            Vec3::from(point) / point.w
+        };
+        let depth = |point: Vec3<f32>| (point - self.position).dot(self.look_at());
+
+        let mut corners = [Vec3::zero(); 8];
+        for i in 0..4 {
+            let x = if i & 1 == 0 { -1.0 } else { 1.0 };
+            let y = if i & 2 == 0 { -1.0 } else { 1.0 };
+
+            let near = unproject(Vec3::new(x, y, -1.0));
+            let far = unproject(Vec3::new(x, y, 1.0));
+            // An infinite far plane unprojects to w = 0,
+            // so the far corner is found along the ray through a point that's always finite.
+            let middle = unproject(Vec3::new(x, y, 0.0));
+
+            let far_depth = if far.into_iter().all(f32::is_finite) && depth(far) > 0.0 {
+                depth(far).min(FRUSTUM_MAX_DISTANCE)
+            } else {
+                FRUSTUM_MAX_DISTANCE
+            };
+            let t = (far_depth - depth(near)) / (depth(middle) - depth(near));
+
+            corners[i] = near;
+            corners[i + 4] = near + (middle - near) * t;
+        }
+        corners
+    }
+
     pub fn rotate_horizontal(&mut self, v: f32) {
         self.yaw.0 = (self.yaw.0 + v).wrapped_2pi();
     }
@@ -112,3 +176,150 @@ impl Blend for Camera {
         }
     }
 }
+
+/// Offset of the view while walking, see `Game::view_bob_offset`.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct ViewBob {
+    /// Up, in world units.
+    pub vertical: f32,
+    /// Clockwise, in radians.
+    pub roll: f32,
+}
+
+/// Keyframes of a camera flying through the world, spread out evenly over `duration` seconds.
+#[derive(Debug, Clone, Default)]
+pub struct CameraPath {
+    pub keyframes: Vec<Camera>,
+    pub duration: f32,
+}
+
+impl CameraPath {
+    pub fn new(duration: f32) -> Self {
+        CameraPath {
+            keyframes: Vec::new(),
+            duration,
+        }
+    }
+
+    pub fn push(&mut self, camera: Camera) {
+        self.keyframes.push(camera);
+    }
+
+    /// The camera `time` seconds into the path, blended between the keyframes around it.
+    /// Times outside of the path are clamped to the first and last keyframe, None if there are no keyframes.
+    pub fn sample(&self, time: f32) -> Option<Camera> {
+        let segments = self.keyframes.len().checked_sub(1)?;
+        if segments == 0 || self.duration <= 0.0 {
+            return self.keyframes.last().copied();
+        }
+
+        let progress = (time / self.duration).clamp(0.0, 1.0) * segments as f32;
+        let index = (progress as usize).min(segments - 1);
+        Some(self.keyframes[index].blend(&self.keyframes[index + 1], progress - index as f32))
+    }
+
+    pub fn is_finished(&self, time: f32) -> bool {
+        time >= self.duration
+    }
+}
+
+#[test]
+fn test_frustum_corners() {
+    let camera = Camera {
+        position: Vec3::new(1.0, 2.0, 3.0),
+        pitch: Angle(0.3),
+        yaw: Angle(1.2),
+    };
+    let ndc_corner = |i: usize| {
+        Vec3::new(
+            if i & 1 == 0 { -1.0 } else { 1.0 },
+            if i & 2 == 0 { -1.0 } else { 1.0 },
+            if i & 4 == 0 { -1.0 } else { 1.0 },
+        )
+    };
+    let project = |projection: Mat4<f32>, corner: Vec3<f32>| {
+        let clip = projection * camera.to_matrix() * Vec4::from_point(corner);
+        Vec3::from(clip) / clip.w
+    };
+
+    let projection = Mat4::perspective_rh_no(90_f32.to_radians(), 4. / 3., 0.1, 50.0);
+    for (i, corner) in camera.frustum_corners(&projection).into_iter().enumerate() {
+        let ndc = project(projection, corner);
+        assert!(ndc.distance(ndc_corner(i)) < 1e-3, "{i}: {ndc}");
+    }
+
+    // An infinite projection has its far plane clamped, but still lines up on x and y.
+    let projection = Mat4::infinite_perspective_rh(90_f32.to_radians(), 4. / 3., 0.1);
+    for (i, corner) in camera.frustum_corners(&projection).into_iter().enumerate() {
+        let ndc = project(projection, corner);
+        let expected = ndc_corner(i);
+        assert!(ndc.xy().distance(expected.xy()) < 1e-3, "{i}: {ndc}");
+        if i < 4 {
+            assert!((ndc.z - expected.z).abs() < 1e-3, "{i}: {ndc}");
+        } else {
+            let depth = (corner - camera.position).dot(camera.look_at());
+            assert!((depth - FRUSTUM_MAX_DISTANCE).abs() < 1e-2, "{i}: {depth}");
+        }
+    }
+}
+
+#[test]
+fn test_angle_delta() {
+    let eq = |a: f32, b: f32| (a - b).abs() < 1e-5;
+
+    // Across 0/tau in both directions.
+    assert!(eq(Angle(0.1).delta(Angle(TAU - 0.1)), -0.2));
+    assert!(eq(Angle(TAU - 0.1).delta(Angle(0.1)), 0.2));
+    assert!(eq(Angle(1.0).delta(Angle(2.0)), 1.0));
+    assert!(eq(Angle(2.0).delta(Angle(1.0)), -1.0));
+    assert_eq!(Angle(1.0).delta(Angle(1.0)), 0.0);
+
+    // Exactly opposite is pi, no matter which side it's approached from.
+    assert_eq!(Angle(0.0).delta(Angle(PI)), PI);
+    assert_eq!(Angle(PI).delta(Angle(0.0)), PI);
+    // Rounding can put these just past either side of pi.
+    assert!(eq(Angle(PI / 2.0).delta(Angle(PI * 1.5)).abs(), PI));
+    assert!(eq(Angle(PI * 1.5).delta(Angle(PI / 2.0)).abs(), PI));
+
+    let lerp = Angle(TAU - 0.1).lerp_shortest(Angle(0.3), 0.5);
+    assert!(eq(lerp.0, 0.1));
+    let lerp = Angle(0.1).lerp_shortest(Angle(TAU - 0.3), 0.5);
+    assert!(eq(lerp.0, TAU - 0.1));
+    assert!(eq(
+        Angle(0.1).lerp_shortest(Angle(TAU - 0.3), 1.0).0,
+        TAU - 0.3
+    ));
+    assert!(eq(Angle(0.1).lerp_shortest(Angle(TAU - 0.3), 0.0).0, 0.1));
+}
+
+#[test]
+fn test_camera_path() {
+    let mut path = CameraPath::new(4.0);
+    assert!(path.sample(0.0).is_none());
+
+    let a = Camera {
+        position: Vec3::new(0.0, 10.0, 0.0),
+        pitch: Angle(0.2),
+        yaw: Angle(TAU - 0.2),
+    };
+    let b = Camera {
+        position: Vec3::new(8.0, 14.0, -4.0),
+        pitch: Angle(0.4),
+        yaw: Angle(0.2),
+    };
+    path.push(a);
+    assert_eq!(path.sample(3.0).unwrap().position, a.position);
+    path.push(b);
+
+    let expected = a.blend(&b, 0.5);
+    let middle = path.sample(2.0).unwrap();
+    assert_eq!(middle.position, expected.position);
+    assert_eq!(middle.pitch, expected.pitch);
+    // Crosses 0 instead of turning all the way around.
+    assert!(middle.yaw.0 < 1e-5 || middle.yaw.0 > TAU - 1e-5);
+
+    assert_eq!(path.sample(-1.0).unwrap().position, a.position);
+    assert_eq!(path.sample(10.0).unwrap().position, b.position);
+    assert!(!path.is_finished(3.9));
+    assert!(path.is_finished(4.0));
+}
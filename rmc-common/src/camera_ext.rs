@@ -1,6 +1,6 @@
 use vek::Vec3;
 
-use crate::{world::CHUNK_SIZE, Camera};
+use crate::{coords::BLOCK_SCALE, world::CHUNK_SIZE, Camera};
 
 pub trait CameraExt {
     fn is_chunk_in_view(&self, chunk_coord: Vec3<i32>) -> bool;
@@ -9,7 +9,7 @@ pub trait CameraExt {
 impl CameraExt for Camera {
     fn is_chunk_in_view(&self, chunk_coord: Vec3<i32>) -> bool {
         let chunk_corner = chunk_coord * CHUNK_SIZE as i32;
-        let chunk_corner_distance = chunk_corner.as_::<f32>() - self.position;
+        let chunk_corner_distance = chunk_corner.as_::<f32>() * BLOCK_SCALE - self.position;
         let view_plane_normal = self.look_at();
 
         let corners = [
@@ -24,7 +24,9 @@ impl CameraExt for Camera {
         ];
 
         corners.into_iter().any(|c| {
-            view_plane_normal.dot(chunk_corner_distance + c.as_::<f32>() * CHUNK_SIZE as f32) >= 0.0
+            view_plane_normal
+                .dot(chunk_corner_distance + c.as_::<f32>() * CHUNK_SIZE as f32 * BLOCK_SCALE)
+                >= 0.0
         })
     }
 }
@@ -1,5 +1,13 @@
+use itertools::{iproduct, Itertools};
 use vek::{Aabb, Vec3};
 
+use crate::{
+    coords::{block_to_world_units, world_units_to_block, BLOCK_SCALE},
+    physics::broad_phase_box,
+    world::{World, CHUNK_SIZE},
+    DiscreteBlend,
+};
+
 // https://www.gamedev.net/tutorials/programming/general-and-gameplay-programming/swept-aabb-collision-detection-and-response-r3084/
 // https://www.gamedev.net/tutorials/_/technical/game-programming/swept-aabb-collision-detection-and-response-r3084/
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -108,8 +116,213 @@ pub fn sweep_test(a: SweepBox, b: Aabb<f32>) -> Option<SweepTestResult> {
     })
 }
 
+/// How many collisions `collide_with_world` resolves by default before giving up.
+pub const DEFAULT_COLLISION_ITERATIONS: u32 = 4;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct WorldCollision {
+    /// How far the collider actually moved, after sliding along whatever it hit.
+    pub displacement: Vec3<f32>,
+    /// Whether the collider landed on top of a block, relative to `up`.
+    pub on_ground: bool,
+    /// Whether the collider bumped into the bottom of a block, relative to `up`.
+    pub hit_ceiling: bool,
+
+    /// How many collisions were resolved.
+    pub iterations: u32,
+    /// False if it gave up after `max_iterations` without checking that the final movement is free,
+    /// which can leave the collider inside a block.
+    pub converged: bool,
+}
+
+impl DiscreteBlend for WorldCollision {}
+
+/// Moves `collider` by `displacement`, stopping and sliding along the blocks of `world` in the way.
+/// At most `max_iterations` collisions are resolved, `up` is the unit vector pointing away from gravity.
+pub fn collide_with_world(
+    world: &World,
+    collider: Aabb<f32>,
+    displacement: Vec3<f32>,
+    max_iterations: u32,
+    up: Vec3<f32>,
+) -> WorldCollision {
+    collide_with_world_scaled(
+        world,
+        collider,
+        displacement,
+        max_iterations,
+        up,
+        BLOCK_SCALE,
+    )
+}
+
+/// `collide_with_world` with blocks `scale` world units wide.
+pub fn collide_with_world_scaled(
+    world: &World,
+    collider: Aabb<f32>,
+    displacement: Vec3<f32>,
+    max_iterations: u32,
+    up: Vec3<f32>,
+    scale: f32,
+) -> WorldCollision {
+    let mut output = WorldCollision {
+        displacement,
+        on_ground: false,
+        hit_ceiling: false,
+        iterations: 0,
+        converged: false,
+    };
+
+    'iteration_loop: for _ in 0..max_iterations {
+        let velocity = output.displacement;
+
+        let sweep = SweepBox {
+            collider: Aabb {
+                min: collider.min,
+                max: collider.min + collider.size(),
+            },
+            velocity,
+        };
+
+        let broad_box = broad_phase_box(collider, velocity);
+
+        let mut collisions = Vec::new();
+
+        for (pos, block) in world
+            .chunks_iter()
+            .filter(|(chunk_coord, _chunk)| {
+                broad_box.collides_with_aabb(Aabb {
+                    min: block_to_world_units(*chunk_coord * CHUNK_SIZE as i32, scale),
+                    max: block_to_world_units(
+                        (*chunk_coord + Vec3::one()) * CHUNK_SIZE as i32,
+                        scale,
+                    ),
+                })
+            })
+            .flat_map(|(chunk_coord, chunk)| {
+                chunk
+                    .iter_world_blocks(chunk_coord)
+                    .collect_vec()
+                    .into_iter()
+            })
+        {
+            let block_box = block.aabb_scaled(pos, scale);
+
+            if block.ty.is_solid_collision() && broad_box.collides_with_aabb(block_box) {
+                if let Some(result) = sweep_test(sweep, block_box) {
+                    collisions.push(result);
+                }
+            }
+        }
+
+        // WTF How does this improve the collision detection???
+        collisions.reverse();
+
+        let Some(SweepTestResult { normal, time }) = collisions
+            .into_iter()
+            .min_by(|a, b| a.time.partial_cmp(&b.time).unwrap())
+        else {
+            output.converged = true;
+            break 'iteration_loop;
+        };
+        output.iterations += 1;
+
+        // Sliding
+        let remaining_time = 1.0 - time;
+        let remaining_velocity = velocity * remaining_time;
+        let projected_velocity = remaining_velocity - remaining_velocity.dot(normal) * normal;
+        output.displacement = velocity * time + projected_velocity;
+
+        if normal.dot(up) < 0.0 {
+            output.hit_ceiling = true;
+        }
+
+        if normal.dot(up) > 0.0 {
+            output.on_ground = true;
+        }
+    }
+
+    output
+}
+
+/// How far `collider` has to move to stop overlapping the blocks of `world`.
+/// It's pushed out along the axis with the least penetration, one block at a time.
+/// Touching a block, like standing on it, doesn't count as overlapping.
+pub fn resolve_penetration(world: &World, collider: Aabb<f32>) -> Vec3<f32> {
+    resolve_penetration_scaled(world, collider, BLOCK_SCALE)
+}
+
+/// `resolve_penetration` with blocks `scale` world units wide.
+pub fn resolve_penetration_scaled(world: &World, collider: Aabb<f32>, scale: f32) -> Vec3<f32> {
+    const MAX_ITERATIONS: usize = 8;
+    const EPSILON: f32 = 1e-4;
+
+    let mut offset = Vec3::<f32>::zero();
+    for _ in 0..MAX_ITERATIONS {
+        let collider = Aabb {
+            min: collider.min + offset,
+            max: collider.max + offset,
+        };
+
+        let min = world_units_to_block(collider.min, scale);
+        let max = world_units_to_block(collider.max, scale);
+        let push = iproduct!(min.x..=max.x, min.y..=max.y, min.z..=max.z)
+            .map(|(x, y, z)| Vec3::new(x, y, z))
+            .filter_map(|pos| {
+                let block = world.get_block(pos)?;
+                block
+                    .ty
+                    .is_solid_collision()
+                    .then(|| block.aabb_scaled(pos, scale))
+            })
+            .filter(|block_box| {
+                (0..3).all(|axis| {
+                    collider.max[axis].min(block_box.max[axis])
+                        - collider.min[axis].max(block_box.min[axis])
+                        > EPSILON
+                })
+            })
+            .flat_map(|block_box| {
+                (0..3).flat_map(move |axis| {
+                    let mut unit = Vec3::zero();
+                    unit[axis] = 1.0;
+                    [
+                        unit * (block_box.max[axis] - collider.min[axis]),
+                        -unit * (collider.max[axis] - block_box.min[axis]),
+                    ]
+                })
+            })
+            .min_by(|a, b| a.magnitude().partial_cmp(&b.magnitude()).unwrap());
+
+        match push {
+            Some(push) => offset += push,
+            None => break,
+        }
+    }
+
+    offset
+}
+
 // TODO use vek::Rect3 here
 
+/// Blocks colliders from moving past its walls, the corners are in blocks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WorldBorder(pub Aabb<i32>);
+
+impl DiscreteBlend for WorldBorder {}
+
+impl WorldBorder {
+    /// `displacement` cut short so `collider` doesn't move any further outside of the border.
+    /// A collider that is already outside can still move back in.
+    pub fn clamp(&self, collider: Aabb<f32>, displacement: Vec3<f32>) -> Vec3<f32> {
+        let min = block_to_world_units(self.0.min, BLOCK_SCALE);
+        let max = block_to_world_units(self.0.max, BLOCK_SCALE);
+        let lowest = (min - collider.min).map(|e| e.min(0.0));
+        let highest = (max - collider.max).map(|e| e.max(0.0));
+        Vec3::partial_min(Vec3::partial_max(displacement, lowest), highest)
+    }
+}
+
 #[test]
 pub fn test_sweep_test() {
     use vek::Extent3;
@@ -284,3 +497,46 @@ pub fn test_sweep_test() {
         })
     );
 }
+
+#[test]
+pub fn test_collision_block_scale() {
+    use crate::{world::Chunk, Block};
+
+    let mut world = World::new(Vec3::zero());
+    world.load(Vec3::zero(), Chunk::new());
+    for (x, z) in iproduct!(0..16, 0..16) {
+        world.set_block(Vec3::new(x, 10, z), Block::STONE).unwrap();
+    }
+
+    // A player sized box falling onto the floor comes to rest on top of it, wherever that is at the scale.
+    let rest_height = |scale: f32| {
+        let collider = Aabb {
+            min: Vec3::new(7.7, 14.0, 7.7) * scale,
+            max: Vec3::new(8.3, 15.8, 8.3) * scale,
+        };
+        let collision = collide_with_world_scaled(
+            &world,
+            collider,
+            Vec3::new(0.0, -10.0, 0.0) * scale,
+            DEFAULT_COLLISION_ITERATIONS,
+            Vec3::unit_y(),
+            scale,
+        );
+        assert!(collision.on_ground);
+        collider.min.y + collision.displacement.y
+    };
+
+    assert!((rest_height(BLOCK_SCALE) - 11.0).abs() < 1e-3);
+    assert!((rest_height(0.5) - 5.5).abs() < 1e-3);
+    assert!((rest_height(2.0) - 22.0).abs() < 1e-3);
+
+    // Sunk into the floor, it's pushed back out to the same height.
+    let sunk = |scale: f32| Aabb {
+        min: Vec3::new(7.7, 10.9, 7.7) * scale,
+        max: Vec3::new(8.3, 12.7, 8.3) * scale,
+    };
+    let push = resolve_penetration_scaled(&world, sunk(0.5), 0.5);
+    assert!(push.distance(Vec3::new(0.0, 0.05, 0.0)) < 1e-3, "{push}");
+    let push = resolve_penetration(&world, sunk(BLOCK_SCALE));
+    assert!(push.distance(Vec3::new(0.0, 0.1, 0.0)) < 1e-3, "{push}");
+}
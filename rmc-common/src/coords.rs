@@ -0,0 +1,117 @@
+use vek::Vec3;
+
+use crate::world::CHUNK_SIZE;
+
+/// Size of a block in world units, positions of the camera and colliders are in world units.
+pub const BLOCK_SCALE: f32 = 1.0;
+
+/// Chunk containing the block at `position`.
+/// Rounds towards negative infinity, so -1 is in chunk -1 and not chunk 0.
+pub fn world_to_chunk(position: Vec3<i32>) -> Vec3<i32> {
+    position.map(|e| e.div_euclid(CHUNK_SIZE as i32))
+}
+
+/// Offset of the block at `position` inside of its chunk, always in `0..CHUNK_SIZE`.
+pub fn world_to_local(position: Vec3<i32>) -> Vec3<i32> {
+    position.map(|e| e.rem_euclid(CHUNK_SIZE as i32))
+}
+
+/// Position of the block at `local` inside of the chunk at `chunk_coord`.
+pub fn chunk_to_world(chunk_coord: Vec3<i32>, local: Vec3<i32>) -> Vec3<i32> {
+    chunk_coord * CHUNK_SIZE as i32 + local
+}
+
+/// World space position of the min corner of the block at `position`, with blocks `scale` units wide.
+pub fn block_to_world_units(position: Vec3<i32>, scale: f32) -> Vec3<f32> {
+    position.as_::<f32>() * scale
+}
+
+/// Block containing the world space `point`, with blocks `scale` units wide.
+pub fn world_units_to_block(point: Vec3<f32>, scale: f32) -> Vec3<i32> {
+    (point / scale).map(|e| e.floor() as i32)
+}
+
+/// Index into an array of chunks centered on `origin` reaching `extents` chunks out on each side,
+/// or None if `chunk_coord` is outside of it.
+pub fn chunk_to_index(
+    chunk_coord: Vec3<i32>,
+    origin: Vec3<i32>,
+    extents: Vec3<i32>,
+) -> Option<Vec3<usize>> {
+    let offset = chunk_coord - origin;
+    if offset.zip(extents).into_iter().any(|(o, e)| o.abs() > e) {
+        return None;
+    }
+
+    Some((offset + extents).as_())
+}
+
+/// Inverse of `chunk_to_index`.
+pub fn index_to_chunk(index: Vec3<usize>, origin: Vec3<i32>, extents: Vec3<i32>) -> Vec3<i32> {
+    index.as_::<i32>() - extents + origin
+}
+
+#[test]
+fn test_world_to_chunk() {
+    assert_eq!(world_to_chunk(Vec3::new(0, 15, 16)), Vec3::new(0, 0, 1));
+    assert_eq!(
+        world_to_chunk(Vec3::new(-1, -16, -17)),
+        Vec3::new(-1, -1, -2)
+    );
+    assert_eq!(world_to_local(Vec3::new(0, 15, 16)), Vec3::new(0, 15, 0));
+    assert_eq!(
+        world_to_local(Vec3::new(-1, -16, -17)),
+        Vec3::new(15, 0, 15)
+    );
+
+    for e in -40..40 {
+        let position = Vec3::new(e, -e, e * 3);
+        let local = world_to_local(position);
+        assert!(local
+            .into_iter()
+            .all(|e| (0..CHUNK_SIZE as i32).contains(&e)));
+        assert_eq!(chunk_to_world(world_to_chunk(position), local), position);
+    }
+}
+
+#[test]
+fn test_chunk_to_index() {
+    let origin = Vec3::new(-3, 0, 2);
+    let extents = Vec3::new(2, 1, 2);
+
+    assert_eq!(
+        chunk_to_index(origin, origin, extents),
+        Some(Vec3::new(2, 1, 2))
+    );
+    assert_eq!(
+        chunk_to_index(Vec3::new(-5, -1, 0), origin, extents),
+        Some(Vec3::zero())
+    );
+    assert_eq!(chunk_to_index(Vec3::new(-6, 0, 2), origin, extents), None);
+    assert_eq!(chunk_to_index(Vec3::new(-3, 2, 2), origin, extents), None);
+
+    for index in [Vec3::zero(), Vec3::new(4, 2, 4), Vec3::new(1, 0, 3)] {
+        let chunk_coord = index_to_chunk(index, origin, extents);
+        assert_eq!(chunk_to_index(chunk_coord, origin, extents), Some(index));
+    }
+}
+
+#[test]
+fn test_world_units() {
+    assert_eq!(
+        block_to_world_units(Vec3::new(-2, 0, 3), BLOCK_SCALE),
+        Vec3::new(-2.0, 0.0, 3.0)
+    );
+    assert_eq!(
+        block_to_world_units(Vec3::new(-2, 0, 3), 0.5),
+        Vec3::new(-1.0, 0.0, 1.5)
+    );
+    assert_eq!(
+        world_units_to_block(Vec3::new(-0.25, 0.75, 1.5), 0.5),
+        Vec3::new(-1, 1, 3)
+    );
+    assert_eq!(
+        world_units_to_block(Vec3::new(-0.25, 0.75, 1.5), BLOCK_SCALE),
+        Vec3::new(-1, 0, 1)
+    );
+}
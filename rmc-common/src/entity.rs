@@ -0,0 +1,179 @@
+use itertools::{EitherOrBoth, Itertools};
+use vek::{Aabb, Vec3};
+
+use crate::{
+    collision::{collide_with_world, DEFAULT_COLLISION_ITERATIONS},
+    game::{BlockOrItem, GRAVITY, TICK_DELTA},
+    world::World,
+    Blend, BlockType,
+};
+
+const DROPPED_ITEM_SIZE: f32 = 0.25;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntityKind {
+    DroppedItem(BlockOrItem),
+    /// A bit of a broken block, it's removed once `ticks_left` runs out.
+    Particle {
+        ty: BlockType,
+        ticks_left: u32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entity {
+    /// Unique for the lifetime of the game, never reused.
+    pub id: u32,
+    pub kind: EntityKind,
+
+    /// Center of the entity's bounding box.
+    pub position: Vec3<f32>,
+    pub velocity: Vec3<f32>,
+    pub on_ground: bool,
+}
+
+impl Entity {
+    pub fn aabb(&self) -> Aabb<f32> {
+        let half_size = Vec3::broadcast(DROPPED_ITEM_SIZE / 2.0);
+        Aabb {
+            min: self.position - half_size,
+            max: self.position + half_size,
+        }
+    }
+
+    /// Applies gravity and moves the entity, colliding with the blocks of `world`.
+    pub fn update(&mut self, world: &World) {
+        self.velocity.y -= GRAVITY * TICK_DELTA;
+
+        let collision = collide_with_world(
+            world,
+            self.aabb(),
+            self.velocity * TICK_DELTA,
+            DEFAULT_COLLISION_ITERATIONS,
+            Vec3::unit_y(),
+        );
+        self.position += collision.displacement;
+
+        if collision.hit_ceiling || collision.on_ground {
+            self.velocity.y = 0.0;
+        }
+        self.on_ground = collision.on_ground;
+    }
+}
+
+impl Blend for Entity {
+    fn blend(&self, other: &Self, alpha: f32) -> Self {
+        Entity {
+            position: self.position.blend(&other.position, alpha),
+            velocity: self.velocity.blend(&other.velocity, alpha),
+            ..if alpha < 0.5 { self } else { other }.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Entities {
+    list: Vec<Entity>,
+    next_id: u32,
+}
+
+impl Entities {
+    pub fn spawn(&mut self, kind: EntityKind, position: Vec3<f32>) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.list.push(Entity {
+            id,
+            kind,
+            position,
+            velocity: Vec3::zero(),
+            on_ground: false,
+        });
+        id
+    }
+
+    pub fn get(&self, id: u32) -> Option<&Entity> {
+        self.list.iter().find(|entity| entity.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: u32) -> Option<&mut Entity> {
+        self.list.iter_mut().find(|entity| entity.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Entity> {
+        self.list.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Entity> {
+        self.list.iter_mut()
+    }
+
+    /// Keeps only the entities for which `f` returns true, in the same order.
+    pub fn retain(&mut self, f: impl FnMut(&Entity) -> bool) {
+        self.list.retain(f);
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+}
+
+impl Blend for Entities {
+    fn blend(&self, other: &Self, alpha: f32) -> Self {
+        // Ids only ever increase and removing entities keeps the order,
+        // so both lists are sorted by id and can be matched in a single pass.
+        let list = self
+            .list
+            .iter()
+            .merge_join_by(other.list.iter(), |a, b| a.id.cmp(&b.id))
+            .filter_map(|entities| match entities {
+                EitherOrBoth::Both(a, b) => Some(a.blend(b, alpha)),
+                EitherOrBoth::Left(a) => Some(a.clone()).blend(&None, alpha),
+                EitherOrBoth::Right(b) => None.blend(&Some(b.clone()), alpha),
+            })
+            .collect();
+
+        Entities {
+            list,
+            next_id: if alpha < 0.5 {
+                self.next_id
+            } else {
+                other.next_id
+            },
+        }
+    }
+}
+
+#[test]
+fn test_entity_blend() {
+    use crate::game::Item;
+
+    let item = EntityKind::DroppedItem(BlockOrItem::Item(Item::Bucket));
+
+    let mut prev = Entities::default();
+    let moved = prev.spawn(item, Vec3::zero());
+    let removed = prev.spawn(item, Vec3::one());
+
+    let mut curr = prev.clone();
+    curr.list
+        .iter_mut()
+        .find(|e| e.id == moved)
+        .unwrap()
+        .position = Vec3::new(2.0, 0.0, 0.0);
+    curr.retain(|e| e.id != removed);
+    let added = curr.spawn(item, Vec3::new(0.0, 4.0, 0.0));
+
+    let early = prev.blend(&curr, 0.25);
+    assert_eq!(early.iter().map(|e| e.id).collect_vec(), [moved, removed]);
+    assert_eq!(early.get(moved).unwrap().position, Vec3::new(0.5, 0.0, 0.0));
+    assert_eq!(early.get(removed).unwrap().position, Vec3::one());
+
+    let late = prev.blend(&curr, 0.75);
+    assert_eq!(late.iter().map(|e| e.id).collect_vec(), [moved, added]);
+    assert_eq!(late.get(moved).unwrap().position, Vec3::new(1.5, 0.0, 0.0));
+    assert_eq!(late.get(added).unwrap().position, Vec3::new(0.0, 4.0, 0.0));
+    assert_eq!(late.next_id, curr.next_id);
+}
@@ -0,0 +1,19 @@
+//! Simulation events, like chunks loading or slow ticks, logged through the `log` crate with the `log` feature.
+//! Without the feature `event!` compiles to nothing, its arguments aren't even evaluated.
+
+/// Logs an event at `level`, one of the `log` crate's macros like `debug` or `warn`.
+/// `event!(warn if condition, ...)` only logs when `condition` holds, which isn't evaluated without the feature either.
+macro_rules! event {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "log")]
+        log::$level!($($arg)+);
+    };
+    ($level:ident if $condition:expr, $($arg:tt)+) => {
+        #[cfg(feature = "log")]
+        if $condition {
+            log::$level!($($arg)+);
+        }
+    };
+}
+
+pub(crate) use event;
@@ -1,11 +1,22 @@
 use crate::{
     camera::Angle,
-    collision::{sweep_test, SweepBox, SweepTestResult},
-    input::InputState,
-    light::calculate_block_light,
-    raycast::{raycast, RaycastOutput},
-    world::{face_neighbors, generate_chunk, Chunk, World, CHUNK_SIZE},
-    Blend, Block, BlockType, Camera, DiscreteBlend,
+    collision::{
+        collide_with_world, resolve_penetration, WorldBorder, WorldCollision,
+        DEFAULT_COLLISION_ITERATIONS,
+    },
+    coords,
+    entity::{Entities, EntityKind},
+    events::event,
+    input::{ButtonState, InputState, MoveIntent},
+    light::{calculate_block_light, calculate_sky_light, LightBits, DEFAULT_LIGHT_FALLOFF},
+    physics::{FlyParams, PhysicsParams},
+    power::calculate_power,
+    raycast::RaycastOutput,
+    world::{
+        face_neighbors, generate_chunk, normal_to_face, BlockState, Chunk, ChunkHasher, World,
+        CHUNK_SIZE,
+    },
+    Blend, Block, BlockType, Camera, DiscreteBlend, ViewBob,
 };
 use crossbeam_queue::SegQueue;
 use enum_assoc::Assoc;
@@ -13,14 +24,23 @@ use itertools::Itertools;
 use lazy_static::lazy_static;
 use noise::NoiseFn;
 use sdl2::{keyboard::Keycode, mouse::MouseButton};
-use std::{collections::HashMap, ops::Deref, rc::Rc, thread::JoinHandle};
-use vek::{Aabb, Extent3, Vec2, Vec3};
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Deref,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+};
+use vek::{Aabb, Vec2, Vec3, Wrap};
 
 pub const TICK_RATE: u32 = 16;
 pub const TICK_SPEED: f32 = 1.0;
 pub const TICK_DELTA: f32 = 1.0 / TICK_RATE as f32;
 
-const GRAVITY: f32 = 16.0;
+pub const GRAVITY: f32 = 16.0;
 const JUMP_HEIGHT: f32 = 1.0;
 lazy_static! {
     // sqrt isn't const fn :/
@@ -31,6 +51,30 @@ const SPEED: f32 = 6.0;
 
 const PLAYER_SIZE: Vec3<f32> = Vec3::new(0.2, 1.8, 0.2);
 const PLAYER_ORIGIN: Vec3<f32> = Vec3::new(0.1, 1.5, 0.1);
+/// Height of the player's box while crouching, the feet stay in place.
+const PLAYER_CROUCH_HEIGHT: f32 = 1.5;
+/// How close dropped items need to be to the player to get picked up.
+const PICKUP_DISTANCE: f32 = 1.5;
+/// How many ticks the particles of a broken block last.
+const PARTICLE_TICKS: u32 = TICK_RATE;
+
+/// How far the view moves up at the top of every step, in world units.
+const BOB_HEIGHT: f32 = 0.05;
+/// How far the view rolls to each side over a walk cycle, in radians.
+const BOB_ROLL: f32 = 0.01;
+/// How much the bob phase advances per block walked, a walk cycle of two steps is `TAU` long.
+const BOB_PHASE_PER_BLOCK: f32 = std::f32::consts::PI;
+
+/// Offset of the view at `phase` into a walk cycle while walking at `speed` blocks per second.
+/// It grows with the speed up to the walking speed, and is zero when standing still.
+pub fn bob_offset(phase: Angle, speed: f32) -> ViewBob {
+    let amount = (speed / SPEED).clamp(0.0, 1.0);
+    ViewBob {
+        // One bob per step, two per cycle.
+        vertical: BOB_HEIGHT * amount * phase.sin().powi(2),
+        roll: BOB_ROLL * amount * phase.sin(),
+    }
+}
 
 #[derive(Clone)]
 pub struct BlockUpdate {
@@ -46,12 +90,110 @@ pub struct BlockUpdate {
     pub state_changed: bool,
 }
 
+/// Block updates waiting to be processed, oldest first.
+/// Cloning copies the updates, so a cloned game runs its own updates without touching the original's.
+#[derive(Default)]
+pub struct BlockUpdateQueue(Rc<SegQueue<BlockUpdate>>);
+
+impl Deref for BlockUpdateQueue {
+    type Target = SegQueue<BlockUpdate>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Clone for BlockUpdateQueue {
+    fn clone(&self) -> Self {
+        // A `SegQueue` can't be iterated, so the updates are taken out and put back in the same order.
+        let updates = std::iter::from_fn(|| self.0.pop()).collect_vec();
+        let copy = SegQueue::new();
+        for update in updates {
+            self.0.push(update.clone());
+            copy.push(update);
+        }
+        BlockUpdateQueue(Rc::new(copy))
+    }
+}
+
+impl Blend for BlockUpdateQueue {
+    /// Shares the updates instead of copying them, a blended game is only drawn and never updated.
+    fn blend(&self, other: &Self, alpha: f32) -> Self {
+        BlockUpdateQueue(if alpha < 0.5 {
+            self.0.clone()
+        } else {
+            other.0.clone()
+        })
+    }
+}
+
 #[derive(Debug, Default, Clone, Copy, PartialEq, Assoc)]
 #[func(pub fn name(&self) -> &'static str { "??" })]
+#[func(pub fn texture_layer(&self) -> Option<u32>)]
 pub enum Item {
     #[default]
     #[assoc(name = "Empty")]
     Empty,
+
+    #[assoc(name = "Bucket")]
+    #[assoc(texture_layer = 0)]
+    Bucket,
+
+    #[assoc(name = "Water Bucket")]
+    #[assoc(texture_layer = 1)]
+    WaterBucket,
+
+    #[assoc(name = "Pickaxe")]
+    #[assoc(texture_layer = 2)]
+    Pickaxe,
+}
+
+impl Item {
+    pub const ALL: [Item; 4] = [Item::Empty, Item::Bucket, Item::WaterBucket, Item::Pickaxe];
+
+    /// How much faster than a bare hand this item breaks blocks of type `ty`.
+    pub fn mining_multiplier(self, ty: BlockType) -> f32 {
+        match (self, ty) {
+            (Item::Pickaxe, BlockType::Stone | BlockType::StoneSlab) => 4.0,
+            _ => 1.0,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Item> {
+        Item::ALL.into_iter().find(|&item| item as u8 == id)
+    }
+
+    /// Use this item on the `target` block, which was hit on the face with `normal`.
+    /// Returns the item that should be left in the slot afterwards.
+    pub fn use_on_block(self, game: &mut Game, target: Vec3<i32>, normal: Vec3<i8>) -> Item {
+        match self {
+            Item::Bucket => {
+                if game.world.get_block(target).map(|b| b.ty) == Some(BlockType::Water) {
+                    game.set_block(target, Block::AIR);
+                    return Item::WaterBucket;
+                }
+            }
+            Item::WaterBucket => {
+                // Placed like a water block would be, pouring it into water doesn't empty the bucket.
+                let target = RaycastOutput {
+                    position: target,
+                    normal,
+                };
+                if let Some((position, block)) = game
+                    .placement_against(target, BlockType::Water)
+                    .filter(|&(position, _)| {
+                        game.world.get_block(position).map(|b| b.ty) != Some(BlockType::Water)
+                    })
+                {
+                    game.set_block(position, block);
+                    return Item::Bucket;
+                }
+            }
+            Item::Empty | Item::Pickaxe => {}
+        }
+
+        self
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -82,18 +224,128 @@ impl Hotbar {
             active: 0,
         }
     }
+
+    /// Puts `item` in the first empty slot unless it's already in the hotbar.
+    /// Returns false if there was no room for it.
+    pub fn add(&mut self, item: BlockOrItem) -> bool {
+        if self.slots.contains(&Some(item)) {
+            return true;
+        }
+
+        match self.slots.iter_mut().find(|slot| slot.is_none()) {
+            Some(slot) => {
+                *slot = Some(item);
+                true
+            }
+            None => false,
+        }
+    }
 }
 
 impl DiscreteBlend for Hotbar {}
 
+/// Tracks how long a mouse button has been held to auto-repeat block actions.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct HoldRepeat {
+    ticks_held: u32,
+    ticks_since_action: u32,
+    /// What was targeted after the last action, None until the next tick sees it.
+    last_target: Option<Option<Vec3<i32>>>,
+}
+
+impl HoldRepeat {
+    /// Returns whether the action should happen this tick.
+    /// It happens once when the button is pressed, and after `delay` ticks of holding it repeats
+    /// every `interval` ticks or as soon as the target changes.
+    pub fn tick(
+        &mut self,
+        state: ButtonState,
+        target: Option<Vec3<i32>>,
+        delay: u32,
+        interval: u32,
+    ) -> bool {
+        if state.just_pressed() {
+            *self = HoldRepeat::default();
+            return true;
+        }
+        if !state.pressed() {
+            *self = HoldRepeat::default();
+            return false;
+        }
+
+        self.ticks_held += 1;
+        self.ticks_since_action += 1;
+
+        // The action itself changes what's targeted, so compare against what's targeted after it.
+        let last_target = *self.last_target.get_or_insert(target);
+        if self.ticks_held >= delay
+            && (target != last_target || self.ticks_since_action >= interval)
+        {
+            self.ticks_since_action = 0;
+            self.last_target = None;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl DiscreteBlend for HoldRepeat {}
+
+/// Ticks it takes to break a block with a hardness of 1 with a bare hand.
+pub const TICKS_PER_HARDNESS: f32 = 10.0;
+/// Lower bound on tool multipliers, so even the worst tool breaks blocks eventually.
+pub const MIN_MINING_MULTIPLIER: f32 = 0.1;
+
+/// Progress towards breaking the targeted block outside of creative.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Mining {
+    pub target: Option<Vec3<i32>>,
+    /// The block breaks once this reaches 1.
+    pub progress: f32,
+}
+
+impl DiscreteBlend for Mining {}
+
+/// Hotbar of the game created by `Game::new`.
+pub const DEFAULT_LOADOUT: [Option<BlockOrItem>; 9] = [
+    Some(BlockOrItem::Block(BlockType::Wood)),
+    Some(BlockOrItem::Block(BlockType::Lantern)),
+    Some(BlockOrItem::Block(BlockType::Test)),
+    Some(BlockOrItem::Block(BlockType::Stone)),
+    Some(BlockOrItem::Block(BlockType::Mesh)),
+    Some(BlockOrItem::Block(BlockType::Water)),
+    Some(BlockOrItem::Item(Item::Bucket)),
+    Some(BlockOrItem::Block(BlockType::StoneSlab)),
+    Some(BlockOrItem::Block(BlockType::Torch)),
+];
+
+/// Switches between breaking blocks instantly and mining them, see `Game::creative`.
+pub const TOGGLE_CREATIVE_KEY: Keycode = Keycode::G;
+
+/// Seed of the world created by `Game::new`.
+pub const DEFAULT_SEED: u32 = 54327;
+
+/// Chunks within this many chunks of spawn stay loaded when the player roams away from them,
+/// see `Game::pin_chunks_around`. None doesn't pin any.
+pub const SPAWN_PIN_RADIUS: Option<i32> = Some(1);
+
 #[derive(Clone)]
 pub struct TerrainSampler {
     seed: u32,
+    temperature: noise::OpenSimplex,
 }
 
 impl TerrainSampler {
     pub fn new(seed: u32) -> Self {
-        TerrainSampler { seed }
+        TerrainSampler {
+            seed,
+            temperature: noise::OpenSimplex::new(seed.wrapping_add(1)),
+        }
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
     }
 
     pub fn height(&self, position: Vec2<i32>) -> u32 {
@@ -118,53 +370,144 @@ impl TerrainSampler {
         ]);
         v > 0.3
     }
+
+    /// Varies slowly over hundreds of blocks, independently of the height.
+    pub fn biome(&self, position: Vec2<i32>) -> Biome {
+        const SCALE: f64 = 0.004;
+        let temperature = self
+            .temperature
+            .get([position.x as f64 * SCALE, position.y as f64 * SCALE]);
+        if temperature < -0.3 {
+            Biome::Cold
+        } else if temperature > 0.3 {
+            Biome::Plains
+        } else {
+            Biome::Forest
+        }
+    }
 }
 
 impl DiscreteBlend for TerrainSampler {}
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Assoc)]
+#[func(pub fn grass_tint(&self) -> [u8; 3] { [255, 255, 255] })]
+pub enum Biome {
+    #[assoc(grass_tint = [230, 220, 140])]
+    Plains,
+    Forest,
+    #[assoc(grass_tint = [170, 200, 235])]
+    Cold,
+}
+
+/// Why the chunk loader couldn't reach its workers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoaderError {
+    /// Every worker thread has stopped, e.g because they're shutting down or panicked while generating.
+    WorkersStopped,
+}
+
+struct ChunkLoaderWorkers {
+    terrain: TerrainSampler,
+    tx: Option<crossbeam_channel::Sender<Vec3<i32>>>,
+    rx: crossbeam_channel::Receiver<(Vec3<i32>, Chunk)>,
+    stop: Arc<AtomicBool>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl Drop for ChunkLoaderWorkers {
+    fn drop(&mut self) {
+        // Workers finish the chunk they're currently generating and skip the rest of the queue.
+        self.stop.store(true, Ordering::Relaxed);
+        self.tx.take();
+        for handle in self.handles.drain(..) {
+            let _ = handle.join();
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct ChunkLoader {
-    #[allow(dead_code)]
-    handle: Rc<Vec<JoinHandle<()>>>,
-    tx: crossbeam_channel::Sender<Vec3<i32>>,
-    rx: crossbeam_channel::Receiver<(Vec3<i32>, Chunk)>,
+    /// Shared between all clones, the workers are shut down once the last clone is dropped.
+    workers: Rc<ChunkLoaderWorkers>,
 }
 
 impl ChunkLoader {
+    /// Creates a loader with one worker thread per available core.
     pub fn new(terrain: TerrainSampler) -> Self {
+        let threads = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        Self::with_threads(terrain, threads)
+    }
+
+    /// Creates a loader with `threads` worker threads (at least one).
+    pub fn with_threads(terrain: TerrainSampler, threads: usize) -> Self {
         let (tx, thread_rx) = crossbeam_channel::unbounded::<Vec3<i32>>();
         let (thread_tx, rx) = crossbeam_channel::unbounded::<(Vec3<i32>, Chunk)>();
-        let handle = (0..std::thread::available_parallelism().unwrap().get())
+        let stop = Arc::new(AtomicBool::new(false));
+        let handles = (0..threads.max(1))
             .map(|_| {
                 let thread_rx = thread_rx.clone();
                 let thread_tx = thread_tx.clone();
                 let terrain = terrain.clone();
+                let stop = stop.clone();
                 std::thread::spawn(move || {
                     while let Ok(chunk_coord) = thread_rx.recv() {
+                        if stop.load(Ordering::Relaxed) {
+                            break;
+                        }
+
                         // println!("({}) Handling {}", i, chunk_coord);
-                        thread_tx
-                            .send((chunk_coord, generate_chunk(&terrain, chunk_coord)))
-                            .unwrap();
+                        let chunk = generate_chunk(&terrain, chunk_coord);
+                        if thread_tx.send((chunk_coord, chunk)).is_err() {
+                            break;
+                        }
                     }
                 })
             })
             .collect_vec();
         ChunkLoader {
-            handle: Rc::new(handle),
-            tx,
-            rx,
+            workers: Rc::new(ChunkLoaderWorkers {
+                terrain,
+                tx: Some(tx),
+                rx,
+                stop,
+                handles,
+            }),
         }
     }
 
-    pub fn request(&self, chunk_coord: Vec3<i32>) {
-        self.tx.send(chunk_coord).unwrap();
+    /// Stops and joins the worker threads if this is the last clone of the loader.
+    /// Returns whether the workers were shut down.
+    pub fn shutdown(self) -> bool {
+        Rc::try_unwrap(self.workers).is_ok()
+    }
+
+    /// The terrain the chunks are generated from.
+    pub fn terrain(&self) -> &TerrainSampler {
+        &self.workers.terrain
+    }
+
+    pub fn worker_count(&self) -> usize {
+        self.workers.handles.len()
+    }
+
+    pub fn request(&self, chunk_coord: Vec3<i32>) -> Result<(), LoaderError> {
+        let tx = self
+            .workers
+            .tx
+            .as_ref()
+            .ok_or(LoaderError::WorkersStopped)?;
+        tx.send(chunk_coord)
+            .map_err(|_| LoaderError::WorkersStopped)
     }
 
-    pub fn receive(&self) -> Option<(Vec3<i32>, Chunk)> {
-        match self.rx.try_recv() {
-            Ok((chunk_coord, chunk)) => Some((chunk_coord, chunk)),
-            Err(crossbeam_channel::TryRecvError::Empty) => None,
-            Err(e) => Err(e).unwrap(),
+    /// A generated chunk if one is ready, None if the workers are still busy.
+    pub fn receive(&self) -> Result<Option<(Vec3<i32>, Chunk)>, LoaderError> {
+        match self.workers.rx.try_recv() {
+            Ok((chunk_coord, chunk)) => Ok(Some((chunk_coord, chunk))),
+            Err(crossbeam_channel::TryRecvError::Empty) => Ok(None),
+            Err(crossbeam_channel::TryRecvError::Disconnected) => Err(LoaderError::WorkersStopped),
         }
     }
 }
@@ -184,6 +527,91 @@ impl<T: Deref> Deref for Discrete<T> {
 
 impl<T> DiscreteBlend for Discrete<T> {}
 
+/// Which axes the world origin follows the player on.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OriginTracking {
+    #[default]
+    Full,
+    /// Only follow on x and z, keeping the same vertical range of chunks loaded.
+    Horizontal,
+}
+
+impl DiscreteBlend for OriginTracking {}
+
+/// What there is to know about the block the player is looking at, see `Game::highlighted_block_info`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlockInfo {
+    pub position: Vec3<i32>,
+    pub ty: BlockType,
+    /// Block light on the face being looked at, from the block in front of it.
+    pub light: u8,
+    pub open_to_sky: bool,
+}
+
+/// Blocks the player placed or broke during a tick, returned by `Game::update` so the client can react to them,
+/// e.g with sounds. Clicks that didn't change anything leave both None.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct InteractionResult {
+    pub placed: Option<(Vec3<i32>, Block)>,
+    /// The block that was there before it was broken.
+    pub broken: Option<(Vec3<i32>, Block)>,
+}
+
+/// Caps on the collections of `Game`, so nothing can grow them without bounds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GameLimits {
+    /// Entities spawned past this many are dropped, see `Game::spawn_entity`.
+    pub max_entities: u32,
+    /// Particles spawned past this many are dropped, they count towards `max_entities` too.
+    /// See `Game::spawn_particle`.
+    pub max_particles: u32,
+    /// Block updates queued past this many are dropped, see `Game::queue_block_update`.
+    pub max_pending_updates: u32,
+}
+
+impl Default for GameLimits {
+    fn default() -> Self {
+        GameLimits {
+            max_entities: 1024,
+            max_particles: 256,
+            // A few seconds of the most updates a tick processes, `Game::relight_all` queues in batches to fit.
+            max_pending_updates: 1 << 17,
+        }
+    }
+}
+
+impl DiscreteBlend for GameLimits {}
+
+/// Picks the blocks that get a random tick, from a seeded generator so a game always ticks the same blocks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RandomTicks {
+    /// Blocks picked in every loaded chunk each tick, zero turns random ticks off.
+    pub per_chunk: u32,
+    state: u64,
+}
+
+impl RandomTicks {
+    pub fn new(seed: u32) -> Self {
+        RandomTicks {
+            per_chunk: 3,
+            state: seed as u64,
+        }
+    }
+
+    /// The next number of the generator, SplitMix64.
+    pub fn next_roll(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+}
+
+impl DiscreteBlend for RandomTicks {}
+
+/// The game is cloned every tick and blended every frame, so it has to stay small, see `test_game_state_size`.
+/// Collections that grow while playing are capped by `limits`.
 #[derive(Clone)]
 pub struct Game {
     pub world: World,
@@ -193,30 +621,101 @@ pub struct Game {
     pub velocity: Vec3<f32>,
 
     pub on_ground: bool,
+    /// Ticks since the player was last on the ground.
+    pub ticks_since_grounded: u32,
+    /// How many ticks after walking off a ledge the player can still jump.
+    pub coyote_ticks: u32,
+    /// Whether the late jump is still available, it can only be used once per time in the air.
+    pub coyote_jump_available: bool,
+    /// How far into the walk cycle the player is, see `bob_offset`.
+    pub bob_phase: Angle,
+    /// Speed the player walked on the ground at last tick, zero while airborne.
+    pub bob_speed: f32,
+    /// How many collisions are resolved for the player each tick.
+    pub max_collision_iterations: u32,
+    /// Result of the player's collision in the last tick, for debugging movement.
+    pub last_collision: WorldCollision,
+    /// The player can't walk, fall or fly out of it, None lets the player go anywhere.
+    pub world_border: Option<WorldBorder>,
+    pub physics: PhysicsParams,
+    pub fly: FlyParams,
+    /// Velocity the player flies at, it eases towards what is held by `FlyParams::acceleration`.
+    fly_velocity: Vec3<f32>,
+    /// The block that would be broken, any block that isn't air.
     pub look_at_raycast: Option<RaycastOutput>,
+    /// How far away blocks can be targeted from.
+    pub reach: f32,
+    /// Whether `highlighted_block_info` describes what's behind fluids, see `info_raycast`.
+    pub info_through_fluids: bool,
 
-    pub dirty_blocks: Discrete<Rc<crossbeam_queue::SegQueue<BlockUpdate>>>,
-    pub block_update_count: usize,
+    pub dirty_blocks: BlockUpdateQueue,
+    pub block_update_count: u32,
     pub total_block_update_count: usize,
+    /// How many ticks have run, see `tick_number`.
+    tick: u64,
+    /// Chunks whose meshes were made stale by the blocks edited this tick, see `dirty_mesh_chunks`.
+    dirty_mesh_chunks: Discrete<HashSet<Vec3<i32>, ChunkHasher>>,
 
     pub hotbar: Hotbar,
     pub flying: bool,
+    pub crouching: bool,
+    /// Whether blocks can't be placed where they would overlap the player.
+    pub prevent_placing_in_player: bool,
+    /// Whether placing with nothing targeted puts the block on top of the one the player stands on,
+    /// see `support_target`.
+    pub place_on_support: bool,
+
+    pub entities: Box<Entities>,
+    pub limits: GameLimits,
+    pub random_ticks: RandomTicks,
+
+    /// Ticks a mouse button has to be held before placing/destroying starts repeating.
+    pub block_action_delay: u32,
+    /// Ticks between repeated placing/destroying while a mouse button is held.
+    pub block_action_interval: u32,
+    pub destroy_hold: HoldRepeat,
+    pub place_hold: HoldRepeat,
+    /// Blocks break instantly in creative, otherwise it depends on their hardness and the held tool.
+    pub creative: bool,
+    pub mining: Mining,
+
+    /// Chunks further away than this many chunks are kept compressed in memory.
+    pub chunk_compression_distance: Option<i32>,
+    pub origin_tracking: OriginTracking,
+    /// Chunks further away than this many chunks from the origin aren't loaded, see `set_load_radius`.
+    load_radius: f32,
+    /// Whether the player is waiting for their chunk to load after a `teleport`.
+    held_until_loaded: bool,
+    /// How much block light is lost per block away from a light source.
+    pub light_falloff: f32,
+    /// How many bits block light is kept with, see `set_light_bits`.
+    light_bits: LightBits,
 }
 
 impl Game {
     pub fn new() -> Self {
+        Self::new_with_seed(DEFAULT_SEED)
+    }
+
+    /// Creates a game whose terrain is generated from `seed`.
+    pub fn new_with_seed(seed: u32) -> Self {
         let mut world = World::new(Vec3::zero());
-        let chunk_loader = ChunkLoader::new(TerrainSampler::new(54327));
+        let chunk_loader = ChunkLoader::new(TerrainSampler::new(seed));
 
         let unloaded_chunks = world.unloaded_chunks().collect_vec();
         let _total = unloaded_chunks.len();
         for chunk_coord in unloaded_chunks {
-            chunk_loader.request(chunk_coord);
+            chunk_loader
+                .request(chunk_coord)
+                .expect("the chunk loader's workers stopped");
         }
 
         let mut _loaded = 0;
         while world.unloaded_chunks().next().is_some() {
-            while let Some((chunk_coord, chunk)) = chunk_loader.receive() {
+            while let Some((chunk_coord, chunk)) = chunk_loader
+                .receive()
+                .expect("the chunk loader's workers stopped")
+            {
                 world.load(chunk_coord, chunk);
                 // loaded += 1;
                 // println!(
@@ -238,214 +737,451 @@ impl Game {
             velocity: Vec3::zero(),
 
             on_ground: false,
+            ticks_since_grounded: 0,
+            coyote_ticks: 2,
+            coyote_jump_available: false,
+            bob_phase: Angle(0.0),
+            bob_speed: 0.0,
+            max_collision_iterations: DEFAULT_COLLISION_ITERATIONS,
+            last_collision: WorldCollision::default(),
+            world_border: None,
+            physics: PhysicsParams::default(),
+            fly: FlyParams::default(),
+            fly_velocity: Vec3::zero(),
 
             look_at_raycast: None,
-            dirty_blocks: Discrete(Rc::new(SegQueue::new())),
+            reach: 7.5,
+            dirty_blocks: BlockUpdateQueue::default(),
             block_update_count: 0,
             total_block_update_count: 0,
+            tick: 0,
+            dirty_mesh_chunks: Discrete(HashSet::default()),
 
             hotbar: Hotbar::new(),
             flying: false,
+            crouching: false,
+            prevent_placing_in_player: true,
+            place_on_support: false,
+            info_through_fluids: false,
+
+            entities: Box::default(),
+            limits: GameLimits::default(),
+            random_ticks: RandomTicks::new(seed),
+
+            block_action_delay: 5,
+            block_action_interval: 4,
+            destroy_hold: HoldRepeat::default(),
+            place_hold: HoldRepeat::default(),
+            creative: false,
+            mining: Mining::default(),
+
+            chunk_compression_distance: None,
+            origin_tracking: OriginTracking::Full,
+            load_radius: 0.0,
+            held_until_loaded: false,
+            light_falloff: DEFAULT_LIGHT_FALLOFF,
+            light_bits: LightBits::default(),
         };
+        game.load_radius = game.max_load_radius();
+        if let Some(radius) = SPAWN_PIN_RADIUS {
+            game.pin_chunks_around(Vec3::zero(), radius);
+        }
 
         game.set_block(Vec3::new(6, 14, 8), Block::LANTERN);
         game.set_block(Vec3::new(-8, 14, -8), Block::LANTERN);
-        game.hotbar.slots[0] = Some(BlockOrItem::Block(BlockType::Wood));
-        game.hotbar.slots[1] = Some(BlockOrItem::Block(BlockType::Lantern));
-        game.hotbar.slots[2] = Some(BlockOrItem::Block(BlockType::Test));
-        game.hotbar.slots[3] = Some(BlockOrItem::Block(BlockType::Stone));
-        game.hotbar.slots[4] = Some(BlockOrItem::Block(BlockType::Mesh));
+        game.hotbar.slots = DEFAULT_LOADOUT;
 
         game
     }
 
-    pub fn update(&mut self, input: &InputState) {
+    /// Creates a game that starts with `slots` in the hotbar instead of `DEFAULT_LOADOUT`.
+    pub fn with_loadout(slots: [Option<BlockOrItem>; 9]) -> Self {
+        let mut game = Self::new();
+        game.hotbar.slots = slots;
+        game
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.chunk_loader.terrain().seed()
+    }
+
+    pub fn update(&mut self, input: &InputState) -> InteractionResult {
+        self.update_with_intent(input, input.move_intent())
+    }
+
+    /// Runs a tick where the player moves according to `intent` instead of the keys,
+    /// e.g for bots that don't want to build an `InputState`.
+    pub fn apply_intent(&mut self, intent: MoveIntent) {
+        self.update_with_intent(&InputState::default(), intent);
+    }
+
+    fn update_with_intent(&mut self, input: &InputState, intent: MoveIntent) -> InteractionResult {
+        #[cfg(feature = "log")]
+        let start = std::time::Instant::now();
         let initial = self.clone();
+        self.dirty_mesh_chunks.0.clear();
+        self.tick += 1;
 
         self.handle_camera_movement(input);
-        self.handle_movement(input);
 
-        if !self.flying {
-            self.velocity.y -= GRAVITY * TICK_DELTA;
-        } else {
-            self.velocity.y = 0.0;
+        // After a teleport the player stays put until there's something to stand on.
+        if self.held_until_loaded && self.current_chunk_loaded() {
+            self.held_until_loaded = false;
         }
-        self.camera.position += self.velocity * TICK_DELTA;
+        if !self.held_until_loaded {
+            self.handle_movement(intent);
+
+            if !self.flying {
+                let submersion = self.submersion();
+                self.velocity += self.physics.acceleration(submersion) * TICK_DELTA;
+                self.velocity *= self.physics.drag(submersion, TICK_DELTA);
+            } else {
+                self.set_vertical_velocity(0.0);
+            }
+            self.camera.position += self.velocity * TICK_DELTA;
 
-        self.handle_collision(&initial);
+            self.handle_collision(&initial);
+        }
+        self.update_view_bob(&initial);
 
-        self.look_at_raycast = raycast(self.camera.position, self.camera.look_at(), 7.5, |pos| {
-            self.world.get_block(pos)
-        });
+        self.look_at_raycast = self.raycast_from_camera();
 
         self.hotbar.active = (self.hotbar.active as i32 - input.scroll_delta)
             .rem_euclid(self.hotbar.slots.len() as i32) as usize;
 
-        self.handle_place_destroy(input);
+        let interaction = self.handle_place_destroy(input);
+        self.update_entities();
+        self.update_random_ticks();
         self.update_blocks();
 
         if input.get_key(Keycode::P).just_pressed() {
             self.flying = !self.flying;
         }
+        if input.get_key(TOGGLE_CREATIVE_KEY).just_pressed() {
+            self.creative = !self.creative;
+        }
 
-        if self.chunk_coordinate() != self.world.origin() {
-            self.world.set_origin(self.chunk_coordinate());
+        let origin = self.world.origin();
+        self.follow_player();
 
-            let unloaded_chunks = self
-                .world
-                .unloaded_chunks()
-                .filter(|chunk_coord| {
-                    chunk_coord
-                        .as_::<f32>()
-                        .distance(self.world.origin().as_::<f32>())
-                        < self.world.extents.as_::<f32>().average()
-                })
-                .collect_vec();
+        // Chunks stop coming in if the workers died, the world keeps the ones it has.
+        while let Ok(Some((chunk_coord, mut chunk))) = self.chunk_loader.receive() {
+            // A pinned chunk that was put back while it was being generated again keeps its edits.
+            if self.world.is_pinned(chunk_coord) && self.world.is_chunk_loaded(chunk_coord) {
+                continue;
+            }
+            chunk.set_light_bits(self.light_bits);
+            self.world.load(chunk_coord, chunk);
+        }
 
-            for chunk_coord in unloaded_chunks {
-                self.chunk_loader.request(chunk_coord);
+        // Which chunks are distant only changes when the origin moves.
+        if self.world.origin() != origin {
+            if let Some(distance) = self.chunk_compression_distance {
+                self.world.compress_distant(distance);
             }
         }
 
-        while let Some((chunk_coord, chunk)) = self.chunk_loader.receive() {
-            self.world.load(chunk_coord, chunk);
+        event!(
+            warn if start.elapsed().as_secs_f32() > TICK_DELTA,
+            "tick took {:?}, longer than a tick lasts",
+            start.elapsed()
+        );
+
+        interaction
+    }
+
+    /// Moves the origin to the player's chunk if they left it, requesting the chunks that come into range.
+    /// The chunks closest to the player are requested first.
+    fn follow_player(&mut self) {
+        let origin = match self.origin_tracking {
+            OriginTracking::Full => self.chunk_coordinate(),
+            OriginTracking::Horizontal => self.chunk_coordinate().with_y(self.world.origin().y),
+        };
+        if origin == self.world.origin() {
+            return;
+        }
+
+        self.world.set_origin(origin);
+        let player_chunk = self.chunk_coordinate();
+        for chunk_coord in self
+            .chunks_to_load()
+            .into_iter()
+            .sorted_by_key(|chunk_coord| (chunk_coord - player_chunk).magnitude_squared())
+        {
+            if self.chunk_loader.request(chunk_coord).is_err() {
+                event!(
+                    warn,
+                    "the chunk loader's workers stopped, {chunk_coord} won't be loaded"
+                );
+                break;
+            }
+        }
+    }
+
+    /// Moves the player to `position`, e.g from the debug window.
+    /// The chunks around it are requested right away, and the player is held in place until the one they're in
+    /// is loaded instead of falling through the missing ground. If they end up inside of blocks, the next tick
+    /// pushes them out like after any other teleport.
+    pub fn teleport(&mut self, position: Vec3<f32>) {
+        self.camera.position = position;
+        self.velocity = Vec3::zero();
+        self.follow_player();
+        self.held_until_loaded = !self.current_chunk_loaded();
+    }
+
+    /// Keeps the chunks within `radius` chunks of `center` on every axis when the player roams away from them,
+    /// e.g around spawn, see `World::pin`.
+    pub fn pin_chunks_around(&mut self, center: Vec3<i32>, radius: i32) {
+        for offset in itertools::iproduct!(-radius..=radius, -radius..=radius, -radius..=radius) {
+            self.world.pin(center + Vec3::from(offset));
         }
     }
 
+    /// The unloaded chunks within `load_radius` of the origin, which get requested when the origin moves.
+    pub fn chunks_to_load(&self) -> Vec<Vec3<i32>> {
+        let origin = self.world.origin();
+        self.world
+            .unloaded_chunks()
+            .filter(|chunk_coord| {
+                let offset = (chunk_coord - origin).as_::<f32>();
+                // The whole vertical range stays loaded when only following horizontally.
+                let offset = match self.origin_tracking {
+                    OriginTracking::Full => offset,
+                    OriginTracking::Horizontal => offset.with_y(0.0),
+                };
+                offset.magnitude() < self.load_radius
+            })
+            .collect_vec()
+    }
+
+    /// How far from the origin chunks are loaded, in chunks.
+    pub fn load_radius(&self) -> f32 {
+        self.load_radius
+    }
+
+    /// The largest `load_radius`, and the default one. Chunks any further out wouldn't all fit in the world.
+    pub fn max_load_radius(&self) -> f32 {
+        self.world.extents.as_::<f32>().average()
+    }
+
+    /// A smaller radius leaves the chunks on the edge of the world unloaded,
+    /// e.g so the chunks that are loaded always have their neighbors to mesh against.
+    /// It's clamped to `max_load_radius`.
+    pub fn set_load_radius(&mut self, radius: f32) {
+        self.load_radius = radius.clamp(0.0, self.max_load_radius());
+    }
+
     fn handle_camera_movement(&mut self, input: &InputState) {
         self.camera.rotate_horizontal(input.mouse_delta.x);
         self.camera.rotate_vertical(input.mouse_delta.y);
     }
 
-    fn handle_movement(&mut self, input: &InputState) {
-        let up_down = input.get_key(Keycode::Space).pressed() as i8
-            - input.get_key(Keycode::LShift).pressed() as i8;
-        let input_vector = input.get_movement_vector();
-        let movement_vector =
-            input_vector.x * self.camera.right() + input_vector.y * self.camera.forward();
-        self.camera.position += movement_vector.try_normalized().unwrap_or_default()
-            * SPEED
-            * TICK_DELTA
-            * if self.flying { 10.0 } else { 1.0 };
+    fn handle_movement(&mut self, intent: MoveIntent) {
+        let up_down = intent.jump as i8 - intent.crouch as i8;
+        self.crouching = !self.flying && intent.crouch;
+
+        // forward() and right() are flat, so looking up or down doesn't change the walking speed.
+        let planar_vector = (intent.direction.x * self.camera.right()
+            + intent.direction.y * self.camera.forward())
+        .try_normalized()
+        .unwrap_or_default();
+
+        let movement = if self.flying {
+            let target = planar_vector * self.fly.horizontal_speed
+                + up_down as f32 * self.fly.vertical_speed * self.physics.up();
+            self.fly_velocity = self.fly.accelerate(self.fly_velocity, target, TICK_DELTA);
+            self.fly_velocity
+        } else {
+            self.fly_velocity = Vec3::zero();
+            planar_vector * SPEED
+        };
 
-        if self.flying && input.get_key(Keycode::Space).pressed() {
-            self.camera.position.y += 10.0 * TICK_DELTA;
-        }
+        self.camera.position += movement * TICK_DELTA;
 
         if self.on_ground {
-            self.velocity.y = up_down as f32 * *JUMP_STRENGTH;
+            self.ticks_since_grounded = 0;
+            self.coyote_jump_available = true;
+        } else {
+            self.ticks_since_grounded = self.ticks_since_grounded.saturating_add(1);
         }
-    }
 
-    fn handle_collision(&mut self, initial: &Game) {
-        self.on_ground = false;
+        if !self.flying {
+            if up_down > 0 && self.submersion() > 0.0 {
+                // There's nothing to jump off of in a fluid, even when standing on the bottom.
+                self.set_vertical_velocity(self.physics.swim_up_speed);
+            } else if self.on_ground {
+                self.set_vertical_velocity(up_down as f32 * *JUMP_STRENGTH);
+            } else if up_down > 0
+                && self.coyote_jump_available
+                && self.ticks_since_grounded <= self.coyote_ticks
+            {
+                self.set_vertical_velocity(*JUMP_STRENGTH);
+            }
+
+            if up_down > 0 {
+                self.coyote_jump_available = false;
+            }
+        }
+    }
 
-        const MAX_ITERATIONS: usize = 4;
+    /// Replaces the part of the velocity along `PhysicsParams::up`.
+    fn set_vertical_velocity(&mut self, speed: f32) {
+        let up = self.physics.up();
+        self.velocity = self.velocity - up * self.velocity.dot(up) + up * speed;
+    }
 
-        'iteration_loop: for _ in 0..MAX_ITERATIONS {
-            let player_box_position = initial.camera.position - PLAYER_ORIGIN;
-            let player_box = Aabb {
-                min: player_box_position,
-                max: player_box_position + PLAYER_SIZE,
-            };
+    /// How much of the player's box is in fluids, from 0 for none of it to 1 for all of it.
+    pub fn submersion(&self) -> f32 {
+        let aabb = self.player_aabb();
+        // Blocks are checked in blocks.
+        let aabb = Aabb {
+            min: aabb.min / coords::BLOCK_SCALE,
+            max: aabb.max / coords::BLOCK_SCALE,
+        };
+        let volume = |aabb: Aabb<f32>| {
+            let size = aabb.size();
+            size.w * size.h * size.d
+        };
 
-            let player_velocity = self.camera.position - initial.camera.position;
+        let in_fluids: f32 = self
+            .world
+            .blocks_in_aabb(aabb)
+            .filter(|(_, block)| block.ty.is_fluid())
+            .map(|(position, _)| {
+                let block = Aabb {
+                    min: position.as_::<f32>(),
+                    max: position.as_::<f32>() + 1.0,
+                };
+                volume(aabb.intersection(block))
+            })
+            .sum();
+        (in_fluids / volume(aabb)).min(1.0)
+    }
 
-            let player_sweep = SweepBox {
-                collider: Aabb {
-                    min: player_box.min,
-                    max: player_box.min + player_box.size(),
-                },
-                velocity: player_velocity,
-            };
+    /// The space the player takes up, shorter while crouching.
+    pub fn player_aabb(&self) -> Aabb<f32> {
+        self.player_aabb_at(self.camera.position)
+    }
 
-            let broad_box_position =
-                player_box
-                    .min
-                    .zip(player_velocity)
-                    .map(|(p, v)| if v > 0.0 { p } else { p + v });
-            let broad_box = Aabb {
-                min: broad_box_position,
-                max: broad_box_position
-                    + player_box
-                        .size()
-                        .zip(Extent3::<f32>::from(player_velocity))
-                        .map(|(s, v)| s + v.abs()),
-            };
+    fn player_aabb_at(&self, camera_position: Vec3<f32>) -> Aabb<f32> {
+        let min = camera_position - PLAYER_ORIGIN;
+        let size = if self.crouching {
+            PLAYER_SIZE.with_y(PLAYER_CROUCH_HEIGHT)
+        } else {
+            PLAYER_SIZE
+        };
+        Aabb {
+            min,
+            max: min + size,
+        }
+    }
 
-            let mut collisions = Vec::new();
+    fn handle_collision(&mut self, initial: &Game) {
+        let initial_box = self.player_aabb_at(initial.camera.position);
 
-            for (pos, block) in self
-                .world
-                .chunks_iter()
-                .filter(|(pos, _c)| {
-                    broad_box.collides_with_aabb(Aabb {
-                        min: pos.as_() * CHUNK_SIZE as f32,
-                        max: (pos.as_() + Vec3::one()) * CHUNK_SIZE as f32,
-                    })
-                })
-                .flat_map(|(chunk_coord, chunk)| {
-                    chunk
-                        .blocks
-                        .indexed_iter()
-                        .map(|(offset, block)| {
-                            (
-                                chunk_coord * CHUNK_SIZE as i32 + Vec3::<usize>::from(offset).as_(),
-                                *block,
-                            )
-                        })
-                        .collect_vec()
-                        .into_iter()
-                })
-            {
-                let block_box = Aabb {
-                    min: pos.as_(),
-                    max: pos.as_() + Vec3::one(),
-                };
+        // The sweep assumes the player starts outside of blocks, e.g after a teleport they might not.
+        let push = resolve_penetration(&self.world, initial_box);
+        let player_box = Aabb {
+            min: initial_box.min + push,
+            max: initial_box.max + push,
+        };
 
-                if block.ty != BlockType::Air && broad_box.collides_with_aabb(block_box) {
-                    if let Some(result) = sweep_test(player_sweep, block_box) {
-                        collisions.push(result);
-                    }
-                }
-            }
+        let collision = collide_with_world(
+            &self.world,
+            player_box,
+            self.camera.position - initial.camera.position,
+            self.max_collision_iterations,
+            self.physics.up(),
+        );
+
+        let mut displacement = push + collision.displacement;
+        if let Some(border) = self.world_border {
+            let clamped = border.clamp(initial_box, displacement);
+            // Stops on the axes that hit the border, like hitting a block.
+            self.velocity = self
+                .velocity
+                .zip(clamped.zip(displacement))
+                .map(|(v, (c, d))| if c != d { 0.0 } else { v });
+            displacement = clamped;
+        }
 
-            // WTF How does this improve the collision detection???
-            collisions.reverse();
+        self.camera.position = initial.camera.position + displacement;
+        if collision.hit_ceiling {
+            self.set_vertical_velocity(0.0);
+        }
+        self.on_ground = collision.on_ground;
+        self.last_collision = collision;
+    }
 
-            let Some(SweepTestResult { normal, time }) = collisions
-                .into_iter()
-                .min_by(|a, b| a.time.partial_cmp(&b.time).unwrap())
-            else {
-                break 'iteration_loop;
-            };
+    /// Advances the walk cycle by how far the player walked on the ground this tick.
+    fn update_view_bob(&mut self, initial: &Game) {
+        let up = self.physics.up();
+        let displacement = self.camera.position - initial.camera.position;
+        let horizontal = displacement - up * displacement.dot(up);
+        self.bob_speed = if self.on_ground && !self.flying {
+            horizontal.magnitude() / TICK_DELTA
+        } else {
+            0.0
+        };
+        self.bob_phase = Angle(
+            (self.bob_phase.0 + self.bob_speed * TICK_DELTA * BOB_PHASE_PER_BLOCK).wrapped_2pi(),
+        );
+    }
 
-            self.camera.position = initial.camera.position + player_velocity * time;
+    /// Offset to render the view with while walking.
+    pub fn view_bob_offset(&self) -> ViewBob {
+        bob_offset(self.bob_phase, self.bob_speed)
+    }
 
-            // Sliding
-            let remaining_time = 1.0 - time;
-            let remaining_velocity = player_velocity * remaining_time;
-            let projected_velocity = remaining_velocity - remaining_velocity.dot(normal) * normal;
-            self.camera.position += projected_velocity;
+    fn update_entities(&mut self) {
+        for entity in self.entities.iter_mut() {
+            entity.update(&self.world);
+            if let EntityKind::Particle { ticks_left, .. } = &mut entity.kind {
+                *ticks_left = ticks_left.saturating_sub(1);
+            }
+        }
 
-            if normal == -Vec3::unit_y() {
-                self.velocity.y = 0.0;
+        let player_center = self.camera.position - PLAYER_ORIGIN + PLAYER_SIZE / 2.0;
+        let hotbar = &mut self.hotbar;
+        self.entities.retain(|entity| match entity.kind {
+            EntityKind::DroppedItem(item) => {
+                !(entity.position.distance(player_center) < PICKUP_DISTANCE && hotbar.add(item))
             }
+            EntityKind::Particle { ticks_left, .. } => ticks_left > 0,
+        });
+    }
+
+    /// Gives `random_ticks.per_chunk` random blocks of every loaded chunk a random tick, see `BlockType::random_tick`.
+    /// What they change gets updated like any other edit.
+    fn update_random_ticks(&mut self) {
+        let mut edits = Vec::new();
+        for chunk_coord in self.world.loaded_chunks().collect_vec() {
+            let Some(chunk) = self.world.chunk_at(chunk_coord) else {
+                continue;
+            };
 
-            if normal.y > 0.0 {
-                self.on_ground = true;
+            for _ in 0..self.random_ticks.per_chunk {
+                let roll = self.random_ticks.next_roll();
+                let local =
+                    Vec3::new(roll, roll >> 8, roll >> 16).map(|e| (e % CHUNK_SIZE as u64) as i32);
+                let Some(block) = chunk.get_local(local) else {
+                    continue;
+                };
+                let position = coords::chunk_to_world(chunk_coord, local);
+                edits.extend(block.ty.random_tick(&self.world, position, roll >> 24));
             }
         }
+        self.set_blocks(edits);
     }
 
     fn update_blocks(&mut self) {
-        const MAX_UPDATES_COUNT: usize = 2048;
+        const MAX_UPDATES_COUNT: u32 = 2048;
 
         self.block_update_count = 0;
 
         while self.block_update_count < MAX_UPDATES_COUNT && self.dirty_blocks.len() != 0 {
-            let update_count = self.dirty_blocks.len().min(MAX_UPDATES_COUNT);
-            self.block_update_count += update_count;
+            let update_count = self.dirty_blocks.len().min(MAX_UPDATES_COUNT as usize);
+            self.block_update_count += update_count as u32;
             self.total_block_update_count += update_count;
 
             let dirty_blocks = (0..update_count)
@@ -453,6 +1189,7 @@ impl Game {
                 .collect_vec();
 
             let mut replaces = HashMap::new();
+            let mut unsupported = Vec::new();
             for BlockUpdate {
                 target: position,
                 source,
@@ -468,6 +1205,13 @@ impl Game {
                     continue;
                 }
 
+                if let Some(support) = block.attached_to(position) {
+                    if self.is_support(support) == Some(false) {
+                        unsupported.push(position);
+                        continue;
+                    }
+                }
+
                 let mut new_block = block;
 
                 new_block.open_to_sky =
@@ -478,26 +1222,40 @@ impl Game {
                         true
                     };
 
-                new_block.occluded = face_neighbors(position).into_iter().all(|position| {
-                    if let Some(block) = self.world.get_block(position) {
-                        !block.ty.light_passing()
-                    } else {
-                        false
-                    }
-                });
-
-                new_block.light = calculate_block_light(&self.world, position, new_block, source);
+                new_block.sky_light =
+                    calculate_sky_light(self.world.get_block(position + Vec3::unit_y()));
+
+                // Blocks at the edge of the loaded world stay visible.
+                new_block.occluded =
+                    face_neighbors(position).into_iter().all(|position| {
+                        match self.world.block_state(position) {
+                            BlockState::Solid(block) => !block.ty.light_passing(),
+                            BlockState::Air | BlockState::Unloaded => false,
+                        }
+                    });
+
+                new_block.light = self.light_bits.quantize(calculate_block_light(
+                    &self.world,
+                    position,
+                    new_block,
+                    source,
+                    self.light_falloff,
+                ));
+
+                // Power spreads through the same updates as light, a change in either notifies the neighbors.
+                new_block.power = calculate_power(&self.world, position, new_block);
 
                 if new_block != block {
                     replaces.insert(position, new_block);
                 }
 
-                let should_notify_neighbor =
-                    block.light != new_block.light || block.open_to_sky != new_block.open_to_sky;
+                let should_notify_neighbor = block.light != new_block.light
+                    || block.open_to_sky != new_block.open_to_sky
+                    || block.sky_light != new_block.sky_light
+                    || block.power != new_block.power;
 
                 // Hack: If the source is None (i.e placed by user).
                 // then always update the neighbors.
-                // Also, almost all updates are from `concealed`
                 if source.is_none() || should_notify_neighbor || state_changed {
                     for neighbor in face_neighbors(position)
                         .into_iter()
@@ -508,7 +1266,7 @@ impl Game {
                             state_changed: should_notify_neighbor,
                         })
                     {
-                        self.dirty_blocks.push(neighbor);
+                        self.queue_block_update(neighbor);
                     }
                 }
             }
@@ -516,49 +1274,590 @@ impl Game {
             for (position, block) in replaces {
                 self.set_block1(position, block, false);
             }
+
+            for position in unsupported.into_iter().unique() {
+                self.break_block(position);
+            }
         }
+
+        // More than a second's worth of updates left.
+        event!(
+            warn if self.dirty_blocks.len() > (MAX_UPDATES_COUNT * TICK_RATE) as usize,
+            "{} block updates are waiting",
+            self.dirty_blocks.len()
+        );
     }
 
-    pub fn set_block(&mut self, position: Vec3<i32>, block: Block) {
-        self.set_block1(position, block, true);
+    /// Whether the block at `position` can hold up blocks attached to it, None if it isn't loaded.
+    fn is_support(&self, position: Vec3<i32>) -> Option<bool> {
+        self.world
+            .get_block(position)
+            .map(|block| !block.ty.light_passing())
     }
 
-    pub fn set_block1(&mut self, position: Vec3<i32>, block: Block, update: bool) {
-        if self.world.set_block(position, block).is_ok() {
-            if update {
-                self.dirty_blocks.push(BlockUpdate {
-                    target: position,
-                    source: None,
-                    state_changed: true,
-                });
+    /// Replaces the block at `position` with air, dropping it as an item.
+    /// Returns the block that was broken, None if it was already air or isn't loaded.
+    pub fn break_block(&mut self, position: Vec3<i32>) -> Option<Block> {
+        let block = self
+            .world
+            .get_block(position)
+            .filter(|block| block.ty != BlockType::Air);
+        if let Some(block) = block {
+            let center = position.as_::<f32>() + 0.5;
+            self.spawn_entity(
+                EntityKind::DroppedItem(BlockOrItem::Block(block.ty)),
+                center,
+            );
+            // One towards each of the upper corners.
+            for (x, z) in [(1.0, 1.0), (1.0, -1.0), (-1.0, 1.0), (-1.0, -1.0)] {
+                self.spawn_particle(block.ty, center, Vec3::new(x, 4.0, z));
             }
         }
+        self.set_block(position, Block::AIR);
+        block
     }
 
-    fn handle_place_destroy(&mut self, input: &InputState) {
-        if let Some(highlighted) = self.look_at_raycast {
-            if input.get_mouse_button(MouseButton::Left).just_pressed() {
-                self.set_block(highlighted.position, Block::AIR);
-            }
+    pub fn set_block(&mut self, position: Vec3<i32>, block: Block) {
+        self.set_block1(position, block, true);
+    }
 
-            if input.get_mouse_button(MouseButton::Right).just_pressed() {
-                let position = highlighted.position + highlighted.normal.numcast().unwrap();
+    /// Spawns an entity unless there are already `limits.max_entities`, returns its id if it was spawned.
+    pub fn spawn_entity(&mut self, kind: EntityKind, position: Vec3<f32>) -> Option<u32> {
+        (self.entities.len() < self.limits.max_entities as usize)
+            .then(|| self.entities.spawn(kind, position))
+    }
 
-                if let Some(BlockOrItem::Block(block_ty)) = self.hotbar.slots[self.hotbar.active] {
-                    self.set_block(position, Block::new(block_ty));
-                }
-            }
+    /// Spawns a particle of `ty` unless there are already `limits.max_particles`, see `spawn_entity`.
+    pub fn spawn_particle(
+        &mut self,
+        ty: BlockType,
+        position: Vec3<f32>,
+        velocity: Vec3<f32>,
+    ) -> Option<u32> {
+        let particles = self
+            .entities
+            .iter()
+            .filter(|entity| matches!(entity.kind, EntityKind::Particle { .. }))
+            .count();
+        if particles >= self.limits.max_particles as usize {
+            return None;
+        }
 
-            if input.get_mouse_button(MouseButton::Middle).just_pressed() {
-                let position = highlighted.position + highlighted.normal.numcast().unwrap();
+        let kind = EntityKind::Particle {
+            ty,
+            ticks_left: PARTICLE_TICKS,
+        };
+        let id = self.spawn_entity(kind, position)?;
+        self.entities.get_mut(id)?.velocity = velocity;
+        Some(id)
+    }
 
-                self.set_block(position, Block::LANTERN);
-            }
+    /// Queues `update` unless there are already `limits.max_pending_updates`, returns whether it was queued.
+    /// A dropped update leaves its block as it was, so light that should have spread through it stops there
+    /// until something updates the block again or `relight_all` recomputes the light of the whole world.
+    pub fn queue_block_update(&self, update: BlockUpdate) -> bool {
+        if self.dirty_blocks.len() >= self.limits.max_pending_updates as usize {
+            return false;
+        }
+        self.dirty_blocks.push(update);
+        true
+    }
+
+    /// Applies all `edits` before queueing a single update for each edited position,
+    /// later edits to the same position win. Neighbors are notified once these updates are processed.
+    pub fn set_blocks(&mut self, edits: impl IntoIterator<Item = (Vec3<i32>, Block)>) {
+        let mut edited = Vec::new();
+        for (position, block) in edits {
+            if self.world.set_block(position, block).is_ok() {
+                self.mark_mesh_dirty(position);
+                edited.push(position);
+            }
+        }
+
+        for position in edited.into_iter().unique() {
+            self.queue_block_update(BlockUpdate {
+                target: position,
+                source: None,
+                state_changed: true,
+            });
+        }
+    }
+
+    /// Clears the block light of every loaded block and queues an update for every block that lights up the world,
+    /// ones that see some of the sky or emit light. The updates are spread over the following ticks like any others.
+    /// When there are too many sources to queue at once under `limits.max_pending_updates`, they're queued in batches
+    /// and every batch but the last is processed right away, leaving room for the light they spread.
+    pub fn relight_all(&mut self) {
+        let mut sources = Vec::new();
+        for chunk_coord in self.world.loaded_chunks().collect_vec() {
+            // Compressed chunks are decompressed so their light can be cleared.
+            let Some(chunk) = self
+                .world
+                .chunk_at_world_mut(coords::chunk_to_world(chunk_coord, Vec3::zero()))
+            else {
+                continue;
+            };
+
+            let chunk = Arc::make_mut(chunk);
+            chunk.clear_light();
+            for (local, block) in chunk.iter_blocks() {
+                if block.sky_light > 0 || block.ty.light_emission().is_some() {
+                    sources.push(coords::chunk_to_world(chunk_coord, local));
+                }
+            }
+        }
+
+        // Each source can queue its neighbors, so a batch only takes up part of the queue.
+        let batch_size = (self.limits.max_pending_updates as usize / 8).max(1);
+        for batch in sources.chunks(batch_size) {
+            if sources.len() > batch_size {
+                self.process_block_updates();
+            }
+            for &position in batch {
+                self.queue_block_update(BlockUpdate {
+                    target: position,
+                    source: None,
+                    state_changed: true,
+                });
+            }
+        }
+    }
+
+    pub fn light_bits(&self) -> LightBits {
+        self.light_bits
+    }
+
+    /// Switches how many bits block light is kept with, in the loaded chunks and the ones that load later.
+    /// Compressed chunks are left as they are. Light that's already there is rounded down, `relight_all` recomputes it.
+    pub fn set_light_bits(&mut self, bits: LightBits) {
+        self.light_bits = bits;
+        for chunk_coord in self.world.loaded_chunks().collect_vec() {
+            if self.world.is_compressed(chunk_coord) {
+                continue;
+            }
+            if let Some(chunk) = self
+                .world
+                .chunk_at_world_mut(coords::chunk_to_world(chunk_coord, Vec3::zero()))
+            {
+                Arc::make_mut(chunk).set_light_bits(bits);
+            }
+        }
+    }
+
+    /// Like `relight_all`, but processes all of the updates before returning.
+    pub fn relight_all_blocking(&mut self) {
+        self.relight_all();
+        self.process_block_updates();
+    }
+
+    /// Runs block updates until none are left, instead of at most a tick's worth.
+    fn process_block_updates(&mut self) {
+        while !self.dirty_blocks.is_empty() {
+            self.update_blocks();
+        }
+    }
+
+    /// How many ticks have run since the game was made, `update` and `apply_intent` each run one.
+    pub fn tick_number(&self) -> u64 {
+        self.tick
+    }
+
+    /// Chunks whose meshes have to be rebuilt because of the blocks edited since the start of the last tick,
+    /// chunks touching an edited block included since the light and ambient occlusion of their faces look at it.
+    pub fn dirty_mesh_chunks(&self) -> &HashSet<Vec3<i32>, ChunkHasher> {
+        &self.dirty_mesh_chunks.0
+    }
+
+    fn mark_mesh_dirty(&mut self, position: Vec3<i32>) {
+        let chunk_coord = coords::world_to_chunk(position);
+        let local = coords::world_to_local(position);
+        let steps = |axis: usize| {
+            let low = if local[axis] == 0 { -1 } else { 0 };
+            let high = if local[axis] == CHUNK_SIZE as i32 - 1 {
+                1
+            } else {
+                0
+            };
+            low..=high
+        };
+        for (x, y, z) in itertools::iproduct!(steps(0), steps(1), steps(2)) {
+            self.dirty_mesh_chunks
+                .0
+                .insert(chunk_coord + Vec3::new(x, y, z));
+        }
+    }
+
+    pub fn set_block1(&mut self, position: Vec3<i32>, block: Block, update: bool) {
+        if self.world.set_block(position, block).is_ok() {
+            self.mark_mesh_dirty(position);
+            if update {
+                self.queue_block_update(BlockUpdate {
+                    target: position,
+                    source: None,
+                    state_changed: true,
+                });
+            }
+        }
+    }
+
+    /// Items used on blocks, like buckets, aren't reported in the result.
+    fn handle_place_destroy(&mut self, input: &InputState) -> InteractionResult {
+        let mut result = InteractionResult::default();
+        let target = self.highlighted_position();
+        // Each mode forgets the other's state, so switching modes mid-break starts over.
+        let destroy = if self.creative {
+            self.mining = Mining::default();
+            self.destroy_hold.tick(
+                input.get_mouse_button(MouseButton::Left),
+                target,
+                self.block_action_delay,
+                self.block_action_interval,
+            )
+        } else {
+            self.destroy_hold = HoldRepeat::default();
+            self.mine(input.get_mouse_button(MouseButton::Left), target)
+        };
+        let place = self.place_hold.tick(
+            input.get_mouse_button(MouseButton::Right),
+            self.place_target().map(|raycast| raycast.position),
+            self.block_action_delay,
+            self.block_action_interval,
+        );
+
+        if let (Some(highlighted), true) = (self.look_at_raycast, destroy) {
+            result.broken = self
+                .break_block(highlighted.position)
+                .map(|block| (highlighted.position, block));
+        }
+
+        if place {
+            match self.hotbar.slots[self.hotbar.active] {
+                // Blocks can also be placed with nothing highlighted, see `support_target`.
+                Some(BlockOrItem::Block(_)) => {
+                    if let Some((position, block)) = self.placement() {
+                        self.set_block(position, block);
+                        result.placed = Some((position, block));
+                    }
+                }
+                Some(BlockOrItem::Item(item)) => {
+                    if let Some(highlighted) = self.look_at_raycast {
+                        let item =
+                            item.use_on_block(self, highlighted.position, highlighted.normal);
+                        self.hotbar.slots[self.hotbar.active] = Some(BlockOrItem::Item(item));
+                    }
+                }
+                None => {}
+            }
+        }
+
+        if input.get_mouse_button(MouseButton::Middle).just_pressed() {
+            if let Some((position, block)) = self
+                .place_target()
+                .and_then(|target| self.placement_against(target, BlockType::Lantern))
+            {
+                self.set_block(position, block);
+                result.placed = Some((position, block));
+            }
+        }
+
+        result
+    }
+
+    /// Advances breaking `target` while `state` is held, returns whether it breaks this tick.
+    /// Progress is lost when the button is released or the target changes.
+    fn mine(&mut self, state: ButtonState, target: Option<Vec3<i32>>) -> bool {
+        if !state.pressed() || target != self.mining.target {
+            self.mining = Mining {
+                target,
+                progress: 0.0,
+            };
+        }
+        if !state.pressed() {
+            return false;
+        }
+        let Some(block) = target.and_then(|position| self.world.get_block(position)) else {
+            return false;
+        };
+
+        self.mining.progress += self.mining_progress(block.ty);
+        // Allow for rounding errors building up over the ticks.
+        if self.mining.progress >= 1.0 - 1e-4 {
+            self.mining.progress = 0.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// How much closer a block of type `ty` gets to breaking each tick with what's in the active slot.
+    pub fn mining_progress(&self, ty: BlockType) -> f32 {
+        if ty.hardness() <= 0.0 {
+            return 1.0;
+        }
+
+        let multiplier = match self.hotbar.slots[self.hotbar.active] {
+            Some(BlockOrItem::Item(item)) => item.mining_multiplier(ty),
+            _ => 1.0,
+        };
+        multiplier.max(MIN_MINING_MULTIPLIER) / (ty.hardness() * TICKS_PER_HARDNESS)
+    }
+
+    /// Whether placing `block` at `position` would put it inside of the player, anywhere along their height.
+    /// Blocks that can be walked through never do.
+    fn would_trap_player(&self, position: Vec3<i32>, block: Block) -> bool {
+        if !self.prevent_placing_in_player || !block.ty.is_solid_collision() {
+            return false;
+        }
+
+        // Only touching is fine, e.g placing a block right below the player's feet.
+        const EPSILON: f32 = 1e-4;
+        let player = self.player_aabb();
+        let block = block.aabb(position);
+        (0..3).all(|axis| {
+            player.max[axis].min(block.max[axis]) - player.min[axis].max(block.min[axis]) > EPSILON
+        })
+    }
+
+    /// What placing targets when nothing is highlighted, the top of the block the player stands on
+    /// as if they were looking at it. None unless `place_on_support` is set and the player is on the ground.
+    /// The block still goes through `would_trap_player`, so it's only placed there if that's turned off.
+    pub fn support_target(&self) -> Option<RaycastOutput> {
+        if !self.place_on_support || !self.on_ground || self.place_raycast().is_some() {
+            return None;
+        }
+
+        // Blocks overlapping a thin slab right under the feet are the ones being stood on.
+        const EPSILON: f32 = 1e-3;
+        let player = self.player_aabb();
+        let under_feet = Aabb {
+            min: player.min.with_y(player.min.y - EPSILON) / coords::BLOCK_SCALE,
+            max: player.max.with_y(player.min.y) / coords::BLOCK_SCALE,
+        };
+        let below_center = coords::world_units_to_block(self.feet_position(), coords::BLOCK_SCALE)
+            - Vec3::unit_y();
+        self.world
+            .blocks_in_aabb(under_feet)
+            .filter(|(position, block)| {
+                block.ty.is_solid_collision()
+                    && block.aabb(*position).collides_with_aabb(under_feet)
+            })
+            // Standing over an edge, the block under the middle of the feet wins.
+            .min_by_key(|(position, _)| (*position - below_center).map(i32::abs).sum())
+            .map(|(position, _)| RaycastOutput {
+                position,
+                normal: Vec3::unit_y(),
+            })
+    }
+
+    /// What placing targets, `place_raycast` or `support_target` if nothing is highlighted.
+    fn place_target(&self) -> Option<RaycastOutput> {
+        self.place_raycast().or_else(|| self.support_target())
+    }
+
+    /// The block the active slot would place and where it would go, given what's highlighted or `support_target`.
+    /// None if nothing is targeted, the active slot isn't a block or the placement would be rejected.
+    fn placement(&self) -> Option<(Vec3<i32>, Block)> {
+        let highlighted = self.place_target()?;
+        let Some(BlockOrItem::Block(block_ty)) = self.hotbar.slots[self.hotbar.active] else {
+            return None;
+        };
+        self.placement_against(highlighted, block_ty)
+    }
+
+    /// The block of type `block_ty` placed against `highlighted` and where it goes, None if the placement
+    /// would be rejected. Everything the player places goes through here, so the same rules apply to all of it.
+    fn placement_against(
+        &self,
+        highlighted: RaycastOutput,
+        block_ty: BlockType,
+    ) -> Option<(Vec3<i32>, Block)> {
+        let position = self.placement_position(highlighted);
+
+        let block = match block_ty {
+            BlockType::Torch => {
+                // Torches stand on the floor or lean against walls, but don't hang from ceilings.
+                let face = normal_to_face(highlighted.normal.as_()).filter(|&face| face != 4)?;
+                if self.is_support(highlighted.position) != Some(true) {
+                    return None;
+                }
+                Block::TORCH.with_orientation(face)
+            }
+            _ if self.is_replaceable(position) => Block::new(block_ty),
+            _ => return None,
+        };
+
+        (!self.would_trap_player(position, block)).then_some((position, block))
+    }
+
+    /// Where right clicking would place the block in the active slot, e.g for previewing it.
+    pub fn placement_target(&self) -> Option<Vec3<i32>> {
+        self.placement().map(|(position, _)| position)
+    }
+
+    fn is_replaceable(&self, position: Vec3<i32>) -> bool {
+        self.world
+            .get_block(position)
+            .is_some_and(|block| block.ty.is_replaceable())
+    }
+
+    /// Where a block placed against `target` goes, replaceable blocks like water are placed into directly
+    /// while anything else gets the block placed in front of the face that was hit.
+    fn placement_position(&self, target: RaycastOutput) -> Vec3<i32> {
+        if self.is_replaceable(target.position) {
+            target.position
+        } else {
+            target.position + target.normal.as_()
+        }
+    }
+
+    /// Every block hit by `rays` rays spread out in a cone `cone_angle` radians wide around the look direction,
+    /// each reaching as far as the player can. The first ray goes straight ahead, so it hits the highlighted block.
+    pub fn reachable_blocks(&self, cone_angle: f32, rays: usize) -> Vec<Vec3<i32>> {
+        let forward = self.camera.look_at();
+        let right = self.camera.right();
+        let up = right.cross(forward);
+        // Golden angle steps spread the rays evenly over the disc the cone covers.
+        let golden_angle = std::f32::consts::PI * (3.0 - 5.0_f32.sqrt());
+
+        (0..rays)
+            .filter_map(|i| {
+                let spread = cone_angle / 2.0 * (i as f32 / rays.max(2) as f32).sqrt();
+                let around = i as f32 * golden_angle;
+                let dir = forward * spread.cos()
+                    + (right * around.cos() + up * around.sin()) * spread.sin();
+                self.world
+                    .raycast(self.camera.position / coords::BLOCK_SCALE, dir, self.reach)
+            })
+            .map(|raycast| raycast.position)
+            .unique()
+            .collect()
+    }
+
+    pub fn loaded_chunk_count(&self) -> usize {
+        self.world.loaded_chunks().count()
+    }
+
+    /// Whether the chunk the player is in is loaded.
+    pub fn current_chunk_loaded(&self) -> bool {
+        self.world.is_block_loaded(self.block_coordinate())
+    }
+
+    /// Block updates waiting to be processed in the following ticks.
+    pub fn pending_block_updates(&self) -> usize {
+        self.dirty_blocks.len()
+    }
+
+    /// What `look_at_raycast` would be from where the camera is now instead of where it was at the last tick,
+    /// e.g for a blended game every frame so the highlight moves smoothly with the camera.
+    pub fn raycast_from_camera(&self) -> Option<RaycastOutput> {
+        // Raycasts are done in blocks, the reach is too.
+        let eye = self.camera.position / coords::BLOCK_SCALE;
+        self.world.raycast(eye, self.camera.look_at(), self.reach)
+    }
+
+    /// The block that would be placed against, see-through cubes like mesh are looked through.
+    /// Cast from where the camera is now, so a blended game places against what it shows.
+    pub fn place_raycast(&self) -> Option<RaycastOutput> {
+        let eye = self.camera.position / coords::BLOCK_SCALE;
+        self.world
+            .place_raycast(eye, self.camera.look_at(), self.reach)
+    }
+
+    /// Position of the block the player is looking at.
+    pub fn highlighted_position(&self) -> Option<Vec3<i32>> {
+        self.look_at_raycast.map(|raycast| raycast.position)
+    }
+
+    /// Whether the player is looking at the block at `position`, false if they aren't looking at any.
+    pub fn is_highlighted(&self, position: Vec3<i32>) -> bool {
+        self.highlighted_position() == Some(position)
+    }
+
+    /// The block the player is looking at.
+    pub fn highlighted_block(&self) -> Option<Block> {
+        self.world.get_block(self.highlighted_position()?)
+    }
+
+    /// The block the player is looking at and the light on the face they're looking at, None if there's nothing targeted.
+    /// Looks through fluids if `info_through_fluids` is set, see `info_raycast`.
+    pub fn highlighted_block_info(&self) -> Option<BlockInfo> {
+        let raycast = self.info_raycast()?;
+        let block = self.world.get_block(raycast.position)?;
+        let face_block = self
+            .world
+            .get_block(raycast.position + raycast.normal.as_());
+        Some(BlockInfo {
+            position: raycast.position,
+            ty: block.ty,
+            light: face_block.map_or(0, |b| b.light),
+            open_to_sky: block.open_to_sky,
+        })
+    }
+
+    /// The block `highlighted_block_info` describes. It's the one that would be broken, unless `info_through_fluids`
+    /// is set and that's a fluid, then it's the first block behind the fluids.
+    pub fn info_raycast(&self) -> Option<RaycastOutput> {
+        if !self.info_through_fluids {
+            return self.look_at_raycast;
+        }
+
+        let eye = self.camera.position / coords::BLOCK_SCALE;
+        self.world
+            .raycast_filtered(eye, self.camera.look_at(), self.reach, |block| {
+                block.ty.is_fluid()
+            })
+    }
+
+    /// The block in front of the face the player is looking at, where a block would be placed.
+    pub fn highlighted_face_block(&self) -> Option<Block> {
+        let raycast = self.look_at_raycast?;
+        self.world
+            .get_block(raycast.position + raycast.normal.as_())
+    }
+
+    /// Where the player sees from, in world units.
+    pub fn eye_position(&self) -> Vec3<f32> {
+        self.camera.position
+    }
+
+    /// Unit vector the player is looking along.
+    pub fn look_direction(&self) -> Vec3<f32> {
+        self.camera.look_at()
+    }
+
+    /// Bottom center of the player's box, where a model of the player stands.
+    pub fn feet_position(&self) -> Vec3<f32> {
+        let aabb = self.player_aabb();
+        aabb.center().with_y(aabb.min.y)
+    }
+
+    /// The camera moved `distance` world units back from the eye for a third person view, looking the same way.
+    /// It stops in front of the first block it can't be inside of, so the view isn't blocked by walls.
+    pub fn third_person_camera(&self, distance: f32) -> Camera {
+        const STEP: f32 = 0.05;
+        let back = -self.look_direction();
+        let mut pulled_back = 0.0;
+        while pulled_back + STEP <= distance {
+            let point = self.eye_position() + back * (pulled_back + STEP);
+            let block = coords::world_units_to_block(point, coords::BLOCK_SCALE);
+            if !self
+                .world
+                .get_block(block)
+                .is_none_or(|b| b.ty.is_replaceable())
+            {
+                break;
+            }
+            pulled_back += STEP;
+        }
+
+        Camera {
+            position: self.eye_position() + back * pulled_back,
+            ..self.camera
         }
     }
 
     pub fn block_coordinate(&self) -> Vec3<i32> {
-        self.camera.position.map(|e| e.floor() as i32)
+        coords::world_units_to_block(self.camera.position, coords::BLOCK_SCALE)
     }
 
     pub fn chunk_coordinate(&self) -> Vec3<i32> {
@@ -576,8 +1875,26 @@ impl Blend for Game {
             velocity: self.velocity.blend(&other.velocity, alpha),
 
             on_ground: self.on_ground.blend(&other.on_ground, alpha),
+            ticks_since_grounded: self
+                .ticks_since_grounded
+                .blend(&other.ticks_since_grounded, alpha),
+            coyote_ticks: self.coyote_ticks.blend(&other.coyote_ticks, alpha),
+            coyote_jump_available: self
+                .coyote_jump_available
+                .blend(&other.coyote_jump_available, alpha),
+            bob_phase: self.bob_phase.blend(&other.bob_phase, alpha),
+            bob_speed: self.bob_speed.blend(&other.bob_speed, alpha),
+            max_collision_iterations: self
+                .max_collision_iterations
+                .blend(&other.max_collision_iterations, alpha),
+            last_collision: self.last_collision.blend(&other.last_collision, alpha),
+            world_border: self.world_border.blend(&other.world_border, alpha),
+            physics: self.physics.blend(&other.physics, alpha),
+            fly: self.fly.blend(&other.fly, alpha),
+            fly_velocity: self.fly_velocity.blend(&other.fly_velocity, alpha),
 
             look_at_raycast: self.look_at_raycast.blend(&other.look_at_raycast, alpha),
+            reach: self.reach.blend(&other.reach, alpha),
             dirty_blocks: self.dirty_blocks.blend(&other.dirty_blocks, alpha),
             block_update_count: self
                 .block_update_count
@@ -585,9 +1902,47 @@ impl Blend for Game {
             total_block_update_count: self
                 .total_block_update_count
                 .blend(&other.total_block_update_count, alpha),
+            tick: self.tick.blend(&other.tick, alpha),
+            dirty_mesh_chunks: self
+                .dirty_mesh_chunks
+                .blend(&other.dirty_mesh_chunks, alpha),
 
             hotbar: self.hotbar.blend(&other.hotbar, alpha),
             flying: self.flying.blend(&other.flying, alpha),
+            crouching: self.crouching.blend(&other.crouching, alpha),
+            prevent_placing_in_player: self
+                .prevent_placing_in_player
+                .blend(&other.prevent_placing_in_player, alpha),
+            place_on_support: self.place_on_support.blend(&other.place_on_support, alpha),
+            info_through_fluids: self
+                .info_through_fluids
+                .blend(&other.info_through_fluids, alpha),
+
+            entities: Box::new(self.entities.blend(&other.entities, alpha)),
+            limits: self.limits.blend(&other.limits, alpha),
+            random_ticks: self.random_ticks.blend(&other.random_ticks, alpha),
+
+            block_action_delay: self
+                .block_action_delay
+                .blend(&other.block_action_delay, alpha),
+            block_action_interval: self
+                .block_action_interval
+                .blend(&other.block_action_interval, alpha),
+            destroy_hold: self.destroy_hold.blend(&other.destroy_hold, alpha),
+            place_hold: self.place_hold.blend(&other.place_hold, alpha),
+            creative: self.creative.blend(&other.creative, alpha),
+            mining: self.mining.blend(&other.mining, alpha),
+
+            chunk_compression_distance: self
+                .chunk_compression_distance
+                .blend(&other.chunk_compression_distance, alpha),
+            origin_tracking: self.origin_tracking.blend(&other.origin_tracking, alpha),
+            load_radius: self.load_radius.blend(&other.load_radius, alpha),
+            held_until_loaded: self
+                .held_until_loaded
+                .blend(&other.held_until_loaded, alpha),
+            light_falloff: self.light_falloff.blend(&other.light_falloff, alpha),
+            light_bits: self.light_bits.blend(&other.light_bits, alpha),
         }
     }
 }
@@ -603,4 +1958,1999 @@ pub fn test_game_state_size() {
         std::mem::size_of::<Game>(),
         MAX_SIZE
     );
+
+    // Collections only add a pointer, no matter how much they hold.
+    assert_eq!(
+        std::mem::size_of::<Box<Entities>>(),
+        std::mem::size_of::<usize>()
+    );
+    assert_eq!(
+        std::mem::size_of::<BlockUpdateQueue>(),
+        std::mem::size_of::<usize>()
+    );
+}
+
+#[test]
+pub fn test_clone_block_updates() {
+    let game = Game::new();
+    let pending = game.pending_block_updates();
+    assert!(pending > 0);
+
+    // The clone starts with the same updates, running them leaves the original's alone.
+    let mut clone = game.clone();
+    assert_eq!(clone.pending_block_updates(), pending);
+    clone.set_block(Vec3::new(3, 40, 3), Block::STONE);
+    while clone.pending_block_updates() > 0 {
+        clone.update_blocks();
+    }
+    assert_eq!(game.pending_block_updates(), pending);
+}
+
+#[test]
+pub fn test_highlighted_block_info() {
+    let mut game = Game::new();
+    game.look_at_raycast = None;
+    assert_eq!(game.highlighted_block_info(), None);
+
+    let position = Vec3::new(3, 40, 3);
+    game.world
+        .set_block(position, Block::WOOD.with_open_to_sky(true))
+        .unwrap();
+    game.world
+        .set_block(position + Vec3::unit_y(), Block::AIR.with_light(120))
+        .unwrap();
+    game.look_at_raycast = Some(RaycastOutput {
+        position,
+        normal: Vec3::unit_y(),
+    });
+    assert_eq!(
+        game.highlighted_block_info(),
+        Some(BlockInfo {
+            position,
+            ty: BlockType::Wood,
+            light: 120,
+            open_to_sky: true,
+        })
+    );
+
+    // Targets in chunks that aren't loaded have nothing to show.
+    game.look_at_raycast = Some(RaycastOutput {
+        position: Vec3::new(0, 10_000, 0),
+        normal: Vec3::unit_y(),
+    });
+    assert_eq!(game.highlighted_block_info(), None);
+}
+
+#[test]
+pub fn test_info_raycast_through_fluids() {
+    let mut game = Game::new();
+    game.camera.position = Vec3::new(8.5, 46.5, 8.5);
+    game.camera.pitch = Angle(std::f32::consts::FRAC_PI_2);
+    for y in 40..=46 {
+        let block = match y {
+            40 | 41 => Block::STONE,
+            42 | 43 => Block::WATER,
+            _ => Block::AIR,
+        };
+        game.set_block(Vec3::new(8, y, 8), block);
+    }
+    game.look_at_raycast =
+        game.world
+            .raycast(game.eye_position(), game.look_direction(), game.reach);
+
+    // Breaking targets the water surface either way.
+    let water = Vec3::new(8, 43, 8);
+    assert_eq!(game.highlighted_position(), Some(water));
+    let info = game.highlighted_block_info().unwrap();
+    assert_eq!((info.position, info.ty), (water, BlockType::Water));
+
+    game.info_through_fluids = true;
+    assert_eq!(game.highlighted_position(), Some(water));
+    let info = game.highlighted_block_info().unwrap();
+    assert_eq!(
+        (info.position, info.ty),
+        (Vec3::new(8, 41, 8), BlockType::Stone)
+    );
+    assert_eq!(game.info_raycast().unwrap().normal, Vec3::unit_y());
+}
+
+#[test]
+pub fn test_game_limits() {
+    let mut game = Game::new();
+    game.limits.max_entities = 2;
+    game.limits.max_pending_updates = 3;
+    while game.pending_block_updates() > 0 {
+        game.update_blocks();
+    }
+
+    let item = EntityKind::DroppedItem(BlockOrItem::Block(BlockType::Stone));
+    assert!(game.spawn_entity(item, Vec3::zero()).is_some());
+    assert!(game.spawn_entity(item, Vec3::zero()).is_some());
+    assert_eq!(game.spawn_entity(item, Vec3::zero()), None);
+    assert_eq!(game.entities.len(), 2);
+
+    // Breaking a block still works, it just doesn't drop anything.
+    let position = Vec3::new(3, 40, 3);
+    game.set_block(position, Block::STONE);
+    game.break_block(position);
+    assert_eq!(game.world.get_block(position).unwrap().ty, BlockType::Air);
+    assert_eq!(game.entities.len(), 2);
+
+    game.set_blocks((0..5).map(|x| (Vec3::new(x, 41, 3), Block::STONE)));
+    assert_eq!(game.pending_block_updates(), 3);
+    assert!(!game.queue_block_update(BlockUpdate {
+        target: Vec3::new(3, 42, 3),
+        source: None,
+        state_changed: true,
+    }));
+
+    // Updates queued while processing others are capped too.
+    game.update_blocks();
+    assert!(game.pending_block_updates() <= 3);
+
+    // Particles count as entities, but are capped on their own too.
+    game.limits.max_entities = 16;
+    game.limits.max_particles = 3;
+    game.set_block(position, Block::STONE);
+    game.break_block(position);
+    let particles = game
+        .entities
+        .iter()
+        .filter(|entity| matches!(entity.kind, EntityKind::Particle { .. }))
+        .count();
+    assert_eq!(particles, 3);
+    assert_eq!(game.entities.len(), 2 + 1 + 3);
+}
+
+/// Chunks generated by `generate_benchmark_region`, a 3x3x3 block around the surface.
+#[cfg(test)]
+const BENCHMARK_REGION: (Vec3<i32>, Vec3<i32>) = (Vec3::new(-1, 1, -1), Vec3::new(1, 3, 1));
+
+/// `World::region_hash` of `BENCHMARK_REGION` generated from `DEFAULT_SEED`.
+/// Has to be updated whenever world generation intentionally changes.
+#[cfg(test)]
+const BENCHMARK_REGION_HASH: u64 = 0xe8ea8e68bb0f0426;
+
+/// Generates `BENCHMARK_REGION` with `threads` chunk loader workers and returns its hash.
+#[cfg(test)]
+fn generate_benchmark_region(threads: usize) -> u64 {
+    let (min_chunk, max_chunk) = BENCHMARK_REGION;
+    let mut world = World::new(Vec3::new(0, 2, 0));
+    let chunk_loader = ChunkLoader::with_threads(TerrainSampler::new(DEFAULT_SEED), threads);
+    let requested = itertools::iproduct!(
+        min_chunk.x..=max_chunk.x,
+        min_chunk.y..=max_chunk.y,
+        min_chunk.z..=max_chunk.z
+    )
+    .map(Vec3::from)
+    .collect_vec();
+    for &chunk_coord in &requested {
+        chunk_loader.request(chunk_coord).unwrap();
+    }
+
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(60);
+    let mut received = 0;
+    while received < requested.len() && std::time::Instant::now() < deadline {
+        if let Some((chunk_coord, chunk)) = chunk_loader.receive().unwrap() {
+            world.load(chunk_coord, chunk);
+            received += 1;
+        }
+    }
+    chunk_loader.shutdown();
+    assert_eq!(
+        received,
+        requested.len(),
+        "timed out generating the benchmark region"
+    );
+
+    world.region_hash(min_chunk, max_chunk)
+}
+
+/// Timing depends on the machine, run it with `cargo test -- --ignored`.
+#[test]
+#[ignore]
+pub fn test_worldgen_benchmark() {
+    let start = std::time::Instant::now();
+    generate_benchmark_region(1);
+    assert!(
+        start.elapsed() < std::time::Duration::from_secs(10),
+        "generating the benchmark region took {:?}",
+        start.elapsed()
+    );
+}
+
+#[test]
+pub fn test_worldgen_baseline() {
+    let hash = generate_benchmark_region(1);
+    assert_eq!(
+        hash, BENCHMARK_REGION_HASH,
+        "world generation changed, update BENCHMARK_REGION_HASH to {hash:#x} if that was intended"
+    );
+
+    // The result doesn't depend on the order the workers finish in.
+    assert_eq!(generate_benchmark_region(4), hash);
+}
+
+#[test]
+pub fn test_chunk_loader_single_thread() {
+    let chunk_loader = ChunkLoader::with_threads(TerrainSampler::new(DEFAULT_SEED), 0);
+    assert_eq!(chunk_loader.worker_count(), 1);
+
+    let requested = [
+        Vec3::new(0, 0, 0),
+        Vec3::new(1, 0, 0),
+        Vec3::new(0, 2, -1),
+        Vec3::new(-3, 1, 2),
+    ];
+    for chunk_coord in requested {
+        chunk_loader.request(chunk_coord).unwrap();
+    }
+
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    let mut received = Vec::new();
+    while received.len() < requested.len() && std::time::Instant::now() < deadline {
+        if let Some((chunk_coord, _chunk)) = chunk_loader.receive().unwrap() {
+            received.push(chunk_coord);
+        }
+    }
+
+    // A single worker handles the requests in order.
+    assert_eq!(received, requested);
+}
+
+#[test]
+pub fn test_chunk_loader_shutdown() {
+    let chunk_loader = ChunkLoader::with_threads(TerrainSampler::new(DEFAULT_SEED), 2);
+    for x in 0..64 {
+        chunk_loader.request(Vec3::new(x, 0, 0)).unwrap();
+    }
+
+    // Other clones keep the workers alive.
+    let clone = chunk_loader.clone();
+    assert!(!clone.shutdown());
+    chunk_loader.request(Vec3::zero()).unwrap();
+
+    // The last clone joins the workers without waiting for the whole queue to be generated.
+    let start = std::time::Instant::now();
+    assert!(chunk_loader.shutdown());
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+}
+
+#[test]
+pub fn test_chunk_loader_stopped_workers() {
+    let chunk_loader = ChunkLoader::with_threads(TerrainSampler::new(DEFAULT_SEED), 1);
+    // Stops the worker while the loader is still around, like it would if it panicked.
+    chunk_loader.workers.stop.store(true, Ordering::Relaxed);
+
+    // The worker only notices once it takes the next request.
+    let start = std::time::Instant::now();
+    while chunk_loader.request(Vec3::zero()).is_ok() {
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+        std::thread::yield_now();
+    }
+    assert_eq!(
+        chunk_loader.request(Vec3::zero()),
+        Err(LoaderError::WorkersStopped)
+    );
+    assert!(matches!(
+        chunk_loader.receive(),
+        Err(LoaderError::WorkersStopped)
+    ));
+}
+
+#[test]
+pub fn test_seed() {
+    let a = Game::new_with_seed(1234);
+    let b = Game::new_with_seed(1234);
+    assert_eq!(a.seed(), 1234);
+    assert_eq!(b.seed(), 1234);
+
+    let min_chunk = -a.world.extents;
+    let max_chunk = a.world.extents;
+    assert_eq!(
+        a.world.region_hash(min_chunk, max_chunk),
+        b.world.region_hash(min_chunk, max_chunk)
+    );
+
+    // The seed has to actually change the terrain.
+    let chunk_coord = Vec3::new(0, 2, 0);
+    let chunk_hash = |seed: u32| {
+        let mut world = World::new(Vec3::zero());
+        world.load(
+            chunk_coord,
+            generate_chunk(&TerrainSampler::new(seed), chunk_coord),
+        );
+        world.region_hash(chunk_coord, chunk_coord)
+    };
+    assert_eq!(chunk_hash(1234), chunk_hash(1234));
+    assert_ne!(chunk_hash(1234), chunk_hash(DEFAULT_SEED));
+}
+
+#[test]
+pub fn test_sky_light_column() {
+    let mut game = Game::new();
+
+    for y in 30..=44 {
+        game.set_block(Vec3::new(8, y, 8), Block::STONE);
+    }
+    for y in 45..=47 {
+        game.set_block(Vec3::new(8, y, 8), Block::AIR);
+    }
+
+    for _ in 0..1000 {
+        if game.dirty_blocks.is_empty() {
+            break;
+        }
+        game.update_blocks();
+    }
+
+    let sky_light = (30..=47)
+        .rev()
+        .map(|y| game.world.get_block(Vec3::new(8, y, 8)).unwrap().sky_light)
+        .collect_vec();
+
+    // Air above the column and the top of the column see the full sky.
+    assert!(sky_light[..4].iter().all(|&l| l == 255));
+    // Every solid block dims it further until it's completely dark.
+    for pair in sky_light[3..].windows(2) {
+        assert!(pair[1] < pair[0] || pair[1] == 0, "{:?}", sky_light);
+    }
+    assert_eq!(*sky_light.last().unwrap(), 0);
+}
+
+#[test]
+pub fn test_light_falloff() {
+    fn settle(game: &mut Game) {
+        for _ in 0..1000 {
+            if game.dirty_blocks.is_empty() {
+                break;
+            }
+            game.update_blocks();
+        }
+    }
+
+    // A closed off tunnel deep underground, so only the lantern lights it.
+    let mut game = Game::new();
+    let z = -30;
+    for x in -32..=32 {
+        for (y, z) in itertools::iproduct!(9..=11, z - 1..=z + 1) {
+            game.set_block(Vec3::new(x, y, z), Block::STONE);
+        }
+    }
+    for x in -31..=31 {
+        game.set_block(Vec3::new(x, 10, z), Block::AIR);
+    }
+    settle(&mut game);
+
+    let lit_length = |mut game: Game| {
+        game.set_block(Vec3::new(0, 10, z), Block::LANTERN);
+        settle(&mut game);
+        (-31..=31)
+            .filter(|&x| game.world.get_block(Vec3::new(x, 10, z)).unwrap().light > 0)
+            .count()
+    };
+
+    // 224 light losing 16 per block lasts 13 blocks in each direction.
+    assert_eq!(lit_length(game.clone()), 27);
+
+    let mut far_reaching = game.clone();
+    far_reaching.light_falloff = 8.0;
+    assert!(lit_length(far_reaching) > 27);
+
+    // 4-bit light is rounded down to whole levels, so it can't reach further.
+    let mut four_bit = game.clone();
+    four_bit.set_light_bits(LightBits::Four);
+    assert_eq!(
+        four_bit.world.chunk_at(Vec3::zero()).unwrap().light_bits(),
+        LightBits::Four
+    );
+    four_bit.set_block(Vec3::new(0, 10, z), Block::LANTERN);
+    settle(&mut four_bit);
+    let lights = (-31..=31)
+        .map(|x| four_bit.world.get_block(Vec3::new(x, 10, z)).unwrap().light)
+        .collect_vec();
+    assert!(lights.iter().all(|light| light % 17 == 0), "{lights:?}");
+    assert!(lights.iter().filter(|&&light| light > 0).count() <= 27);
+}
+
+#[test]
+pub fn test_walking_speed_independent_of_pitch() {
+    let mut game = Game::new();
+    for (x, z) in itertools::iproduct!(0..16, 0..16) {
+        game.set_block(Vec3::new(x, 40, z), Block::STONE);
+        for y in 41..=44 {
+            game.set_block(Vec3::new(x, y, z), Block::AIR);
+        }
+    }
+    game.camera.position = Vec3::new(8.5, 42.6, 2.5);
+    game.camera.yaw = Angle(std::f32::consts::PI);
+
+    // Settle on the ground first.
+    for _ in 0..TICK_RATE {
+        game.update(&InputState::pressing(&[], &[]));
+    }
+    assert!(game.on_ground);
+
+    let walk = |pitch: f32| {
+        let mut game = game.clone();
+        game.camera.pitch = Angle(pitch);
+
+        let start = game.camera.position;
+        for _ in 0..TICK_RATE {
+            game.update(&InputState::pressing(&[Keycode::W], &[]));
+        }
+        (game.camera.position - start).with_y(0.0).magnitude()
+    };
+
+    let level = walk(0.0);
+    assert!((level - SPEED).abs() < 0.01, "{}", level);
+    for pitch in [-1.5, -0.7, 0.7, 1.5] {
+        assert!((walk(pitch) - level).abs() < 0.0001);
+    }
+}
+
+#[test]
+pub fn test_hold_to_destroy() {
+    let mut game = Game::new();
+    game.flying = true;
+    // Standing on the column rather than inside of its top block.
+    game.camera.position = Vec3::new(8.5, 45.6, 8.5);
+    game.camera.pitch = Angle(std::f32::consts::FRAC_PI_2);
+    for y in 30..=43 {
+        game.set_block(Vec3::new(8, y, 8), Block::STONE);
+    }
+    for y in 44..=47 {
+        game.set_block(Vec3::new(8, y, 8), Block::AIR);
+    }
+    game.creative = true;
+    game.block_action_delay = 5;
+    game.block_action_interval = 4;
+    let top_solid = |game: &Game| {
+        (30..=43)
+            .rev()
+            .find(|&y| game.world.get_block(Vec3::new(8, y, 8)).unwrap().ty == BlockType::Stone)
+    };
+
+    // A quick click only breaks a single block.
+    {
+        let mut game = game.clone();
+        for state in [
+            ButtonState::JustPressed,
+            ButtonState::JustReleased,
+            ButtonState::KeptReleased,
+            ButtonState::KeptReleased,
+        ] {
+            game.update(&InputState::pressing(&[], &[(MouseButton::Left, state)]));
+        }
+        assert_eq!(top_solid(&game), Some(42));
+    }
+
+    let mut broken_at = Vec::new();
+    for tick in 0..14 {
+        let state = if tick == 0 {
+            ButtonState::JustPressed
+        } else {
+            ButtonState::KeptPressed
+        };
+        let before = top_solid(&game);
+        game.update(&InputState::pressing(&[], &[(MouseButton::Left, state)]));
+        if top_solid(&game) != before {
+            broken_at.push(tick);
+        }
+    }
+    assert_eq!(broken_at, [0, 5, 9, 13]);
+}
+
+#[test]
+pub fn test_interaction_result() {
+    let mut game = Game::new();
+    game.flying = true;
+    game.creative = true;
+    game.camera.position = Vec3::new(8.5, 44.5, 8.5);
+    game.camera.pitch = Angle(std::f32::consts::FRAC_PI_2);
+    for y in 40..=46 {
+        game.set_block(
+            Vec3::new(8, y, 8),
+            if y == 41 { Block::STONE } else { Block::AIR },
+        );
+    }
+    game.update(&InputState::default());
+
+    // Clicking without anything in reach does nothing.
+    {
+        let mut game = game.clone();
+        game.camera.pitch = Angle(-std::f32::consts::FRAC_PI_2);
+        let result = game.update(&InputState::pressing(
+            &[],
+            &[(MouseButton::Left, ButtonState::JustPressed)],
+        ));
+        assert_eq!(result, InteractionResult::default());
+    }
+
+    // Placing is rejected with an empty slot.
+    {
+        let mut game = game.clone();
+        game.hotbar.slots[game.hotbar.active] = None;
+        let result = game.update(&InputState::pressing(
+            &[],
+            &[(MouseButton::Right, ButtonState::JustPressed)],
+        ));
+        assert_eq!(result, InteractionResult::default());
+        assert_eq!(
+            game.world.get_block(Vec3::new(8, 42, 8)).unwrap().ty,
+            BlockType::Air
+        );
+    }
+
+    let result = game.update(&InputState::pressing(
+        &[],
+        &[(MouseButton::Left, ButtonState::JustPressed)],
+    ));
+    assert_eq!(
+        result.broken.map(|(position, block)| (position, block.ty)),
+        Some((Vec3::new(8, 41, 8), BlockType::Stone))
+    );
+    assert_eq!(result.placed, None);
+    assert_eq!(
+        game.world.get_block(Vec3::new(8, 41, 8)).unwrap().ty,
+        BlockType::Air
+    );
+}
+
+#[test]
+pub fn test_third_person_camera() {
+    let mut game = Game::new();
+    game.flying = true;
+    game.camera.position = Vec3::new(8.5, 44.5, 8.5);
+    game.camera.yaw = Angle(0.0);
+    game.camera.pitch = Angle(0.0);
+    for z in 8..=16 {
+        for y in 43..=45 {
+            game.set_block(Vec3::new(8, y, z), Block::AIR);
+        }
+    }
+    assert_eq!(game.feet_position(), Vec3::new(8.5, 43.0, 8.5));
+
+    // Looking towards -z puts the camera behind the player on +z, looking the same way.
+    let camera = game.third_person_camera(4.0);
+    assert!((camera.position - Vec3::new(8.5, 44.5, 12.5)).magnitude() < 0.06);
+    assert_eq!(camera.yaw, game.camera.yaw);
+
+    game.set_block(Vec3::new(8, 44, 11), Block::STONE);
+    let camera = game.third_person_camera(4.0);
+    assert!(camera.position.z < 11.0 && camera.position.z > 10.9);
+}
+
+#[test]
+pub fn test_torch_support() {
+    use crate::world::face_to_normal;
+
+    let mut game = Game::new();
+    for (x, y, z) in itertools::iproduct!(4..14, 40..=46, 4..13) {
+        game.set_block(
+            Vec3::new(x, y, z),
+            if y == 40 { Block::STONE } else { Block::AIR },
+        );
+    }
+    let wall = Vec3::new(8, 42, 8);
+    game.set_block(wall, Block::STONE);
+
+    game.flying = true;
+    game.camera.position = Vec3::new(11.5, 42.5, 8.5);
+    game.camera.yaw = Angle(std::f32::consts::PI * 1.5);
+    game.hotbar.slots[0] = Some(BlockOrItem::Block(BlockType::Torch));
+    game.hotbar.active = 0;
+    let place = |game: &mut Game| {
+        game.update(&InputState::pressing(
+            &[],
+            &[(MouseButton::Right, ButtonState::JustPressed)],
+        ));
+        game.update(&InputState::pressing(
+            &[],
+            &[(MouseButton::Right, ButtonState::JustReleased)],
+        ));
+    };
+
+    // On the floor.
+    game.camera.pitch = Angle(1.5_f32.atan2(1.2));
+    place(&mut game);
+    let floor_torch = Vec3::new(10, 41, 8);
+    let block = game.world.get_block(floor_torch).unwrap();
+    assert_eq!(block.ty, BlockType::Torch);
+    assert_eq!(face_to_normal(block.orientation), Vec3::unit_y());
+
+    // On the wall, pointing away from it.
+    game.camera.pitch = Angle(0.0);
+    place(&mut game);
+    let wall_torch = Vec3::new(9, 42, 8);
+    let block = game.world.get_block(wall_torch).unwrap();
+    assert_eq!(block.ty, BlockType::Torch);
+    assert_eq!(face_to_normal(block.orientation), Vec3::unit_x());
+    assert_eq!(block.attached_to(wall_torch), Some(wall));
+    assert!(block.light > 0);
+
+    game.set_block(wall, Block::AIR);
+    for _ in 0..1000 {
+        if game.dirty_blocks.is_empty() {
+            break;
+        }
+        game.update_blocks();
+    }
+
+    assert_eq!(game.world.get_block(wall_torch).unwrap().ty, BlockType::Air);
+    assert_eq!(
+        game.world.get_block(floor_torch).unwrap().ty,
+        BlockType::Torch
+    );
+    assert!(
+        game.entities
+            .iter()
+            .any(|entity| entity.kind
+                == EntityKind::DroppedItem(BlockOrItem::Block(BlockType::Torch)))
+    );
+}
+
+#[test]
+pub fn test_place_into_replaceable() {
+    let mut game = Game::new();
+    game.flying = true;
+    game.camera.position = Vec3::new(8.5, 45.5, 8.5);
+    game.camera.pitch = Angle(std::f32::consts::FRAC_PI_2);
+    game.hotbar.slots[0] = Some(BlockOrItem::Block(BlockType::Wood));
+    game.hotbar.active = 0;
+    let column = |game: &Game| {
+        (40..=44)
+            .map(|y| game.world.get_block(Vec3::new(8, y, 8)).unwrap().ty)
+            .collect_vec()
+    };
+    let place = |game: &mut Game| {
+        game.update(&InputState::pressing(
+            &[],
+            &[(MouseButton::Right, ButtonState::JustPressed)],
+        ));
+        game.update(&InputState::pressing(
+            &[],
+            &[(MouseButton::Right, ButtonState::JustReleased)],
+        ));
+    };
+
+    // Water on top of stone is filled in place.
+    game.set_block(Vec3::new(8, 40, 8), Block::STONE);
+    game.set_block(Vec3::new(8, 41, 8), Block::WATER);
+    for y in 42..=46 {
+        game.set_block(Vec3::new(8, y, 8), Block::AIR);
+    }
+    place(&mut game);
+    use BlockType::*;
+    assert_eq!(column(&game), [Stone, Wood, Air, Air, Air]);
+
+    // Against a solid block it still goes in front of the face.
+    place(&mut game);
+    assert_eq!(column(&game), [Stone, Wood, Wood, Air, Air]);
+}
+
+#[test]
+pub fn test_wire_power() {
+    fn settle(game: &mut Game) {
+        for _ in 0..1000 {
+            if game.dirty_blocks.is_empty() {
+                break;
+            }
+            game.update_blocks();
+        }
+    }
+
+    let mut game = Game::new();
+    for (x, y, z) in itertools::iproduct!(2..=24, 40..=44, 6..=10) {
+        game.set_block(
+            Vec3::new(x, y, z),
+            if y == 40 { Block::STONE } else { Block::AIR },
+        );
+    }
+    game.set_block(Vec3::new(3, 41, 8), Block::POWER_SOURCE);
+    for x in 4..=22 {
+        game.set_block(Vec3::new(x, 41, 8), Block::WIRE);
+    }
+    // Lighting goes through the same queue.
+    game.set_block(Vec3::new(10, 42, 8), Block::LANTERN);
+    settle(&mut game);
+
+    let wire = |game: &Game, x: i32| game.world.get_block(Vec3::new(x, 41, 8)).unwrap();
+    let power = |game: &Game| (4..=22).map(|x| wire(game, x).power).collect_vec();
+    let expected = (0..19).map(|i| 14_u8.saturating_sub(i)).collect_vec();
+    assert_eq!(power(&game), expected);
+    assert!(wire(&game, 10).light > 0);
+
+    game.set_block(Vec3::new(3, 41, 8), Block::AIR);
+    settle(&mut game);
+    assert!(power(&game).iter().all(|&power| power == 0));
+    assert!(wire(&game, 10).light > 0);
+}
+
+#[test]
+pub fn test_placing_in_player() {
+    let mut game = Game::new();
+    for (x, y, z) in itertools::iproduct!(4..=12, 40..=46, 4..=12) {
+        game.set_block(
+            Vec3::new(x, y, z),
+            if y == 40 { Block::STONE } else { Block::AIR },
+        );
+    }
+    for y in 41..=45 {
+        game.set_block(Vec3::new(7, y, 8), Block::STONE);
+    }
+    game.hotbar.slots[0] = Some(BlockOrItem::Block(BlockType::Wood));
+    game.hotbar.active = 0;
+    game.camera.yaw = Angle(std::f32::consts::PI * 1.5);
+
+    // Settles on the ground and then right clicks at the wall, returns the block that was placed against it.
+    let place = |mut game: Game, feet: f32, pitch: f32, keys: &[Keycode]| {
+        game.camera.position = Vec3::new(8.5, feet + PLAYER_ORIGIN.y, 8.5);
+        game.camera.pitch = Angle(pitch);
+        for _ in 0..TICK_RATE {
+            game.update(&InputState::pressing(
+                keys,
+                &[(MouseButton::Right, ButtonState::KeptReleased)],
+            ));
+        }
+        assert!(game.on_ground);
+        let target = game.look_at_raycast.unwrap();
+        assert_eq!(target.position.x, 7);
+        game.update(&InputState::pressing(
+            keys,
+            &[(MouseButton::Right, ButtonState::JustPressed)],
+        ));
+        let position = target.position + target.normal.as_();
+        game.world.get_block(position).unwrap().ty
+    };
+
+    // Standing on the floor, the head is in the block at y = 42.
+    assert_eq!(place(game.clone(), 41.0, 0.0, &[]), BlockType::Air);
+    // Above the head there's clearance.
+    assert_eq!(place(game.clone(), 41.0, -1.2, &[]), BlockType::Wood);
+
+    // Standing on a slab the head reaches into y = 43, but not while crouching.
+    let mut game = game;
+    game.set_block(Vec3::new(8, 41, 8), Block::STONE_SLAB);
+    assert_eq!(place(game.clone(), 41.5, -0.5, &[]), BlockType::Air);
+    assert_eq!(
+        place(game.clone(), 41.5, -0.5, &[Keycode::LShift]),
+        BlockType::Wood
+    );
+}
+
+#[test]
+pub fn test_break_particles() {
+    let mut game = Game::new();
+    game.flying = true;
+    let position = Vec3::new(3, 40, 3);
+    game.set_block(position, Block::STONE);
+    game.break_block(position);
+    let particles = |game: &Game| {
+        game.entities
+            .iter()
+            .filter(|entity| matches!(entity.kind, EntityKind::Particle { .. }))
+            .count()
+    };
+    assert_eq!(particles(&game), 4);
+
+    // They only last for a while.
+    for _ in 0..PARTICLE_TICKS {
+        game.update(&InputState::default());
+    }
+    assert_eq!(particles(&game), 0);
+}
+
+#[test]
+pub fn test_dropped_item() {
+    let no_input = InputState::default();
+
+    let mut game = Game::new();
+    game.flying = true;
+    game.camera.position = Vec3::new(8.5, 44.5, 8.5);
+    game.set_block(Vec3::new(12, 38, 12), Block::STONE);
+    for y in 39..=46 {
+        game.set_block(Vec3::new(12, y, 12), Block::AIR);
+    }
+    game.hotbar.slots[1] = None;
+
+    let lantern = BlockOrItem::Block(BlockType::Lantern);
+    let id = game.entities.spawn(
+        EntityKind::DroppedItem(lantern),
+        Vec3::new(12.5, 45.5, 12.5),
+    );
+
+    // It falls until it lands on the stone.
+    game.update(&no_input);
+    assert!(game.entities.get(id).unwrap().position.y < 45.5);
+    for _ in 0..TICK_RATE * 2 {
+        game.update(&no_input);
+    }
+    let entity = game.entities.get(id).unwrap();
+    assert!(entity.on_ground);
+    assert!((entity.aabb().min.y - 39.0).abs() < 0.01);
+    assert!(!game.hotbar.slots.contains(&Some(lantern)));
+
+    // And gets picked up once the player gets close.
+    game.camera.position = Vec3::new(12.5, 41.0, 12.5);
+    game.update(&no_input);
+    assert!(game.entities.is_empty());
+    assert_eq!(game.hotbar.slots[1], Some(lantern));
+}
+
+#[test]
+pub fn test_debug_getters() {
+    let mut game = Game::new();
+    let (x, y, z) = game.world.shape();
+    assert_eq!(game.loaded_chunk_count(), x * y * z);
+    game.camera.position = Vec3::new(8.5, 20.0, 8.5);
+    assert!(game.current_chunk_loaded());
+
+    game.look_at_raycast = None;
+    assert_eq!(game.highlighted_block(), None);
+    assert_eq!(game.highlighted_face_block(), None);
+    assert!(!game.is_highlighted(Vec3::new(3, 40, 3)));
+    assert!(!game.is_highlighted(Vec3::zero()));
+
+    game.world
+        .set_block(Vec3::new(3, 40, 3), Block::WOOD)
+        .unwrap();
+    game.world
+        .set_block(Vec3::new(3, 41, 3), Block::AIR)
+        .unwrap();
+    game.look_at_raycast = Some(RaycastOutput {
+        position: Vec3::new(3, 40, 3),
+        normal: Vec3::unit_y(),
+    });
+    assert_eq!(game.highlighted_block(), Some(Block::WOOD));
+    assert_eq!(game.highlighted_face_block(), Some(Block::AIR));
+    assert!(game.is_highlighted(Vec3::new(3, 40, 3)));
+    for neighbor in face_neighbors(Vec3::new(3, 40, 3)) {
+        assert!(!game.is_highlighted(neighbor));
+    }
+
+    while game.pending_block_updates() > 0 {
+        game.update_blocks();
+    }
+    game.set_block(Vec3::new(3, 41, 3), Block::STONE);
+    assert_eq!(game.pending_block_updates(), 1);
+
+    game.world.unload(game.chunk_coordinate()).unwrap();
+    assert!(!game.current_chunk_loaded());
+    assert_eq!(game.loaded_chunk_count(), x * y * z - 1);
+}
+
+#[test]
+pub fn test_horizontal_origin_tracking() {
+    let no_input = InputState::default();
+
+    let mut game = Game::new();
+    game.flying = true;
+    game.origin_tracking = OriginTracking::Horizontal;
+    game.camera.position = Vec3::new(8.5, 20.0, 8.5);
+    game.update(&no_input);
+    assert_eq!(game.world.origin(), Vec3::zero());
+
+    // Going up and over to the next chunk only moves the origin horizontally.
+    game.camera.position = Vec3::new(8.5 + 48.0, 20.0 + 48.0, 8.5);
+    game.update(&no_input);
+    assert_eq!(game.world.origin(), Vec3::new(3, 0, 0));
+
+    // Loading the chunks in front of the player, top to bottom, doesn't move the origin vertically either.
+    let column = (-game.world.extents.y..=game.world.extents.y)
+        .map(|y| Vec3::new(7, y, 0))
+        .collect_vec();
+    let terrain = TerrainSampler::new(game.seed());
+    for &chunk_coord in &column {
+        if !game.world.is_chunk_loaded(chunk_coord) {
+            game.world
+                .load(chunk_coord, generate_chunk(&terrain, chunk_coord));
+        }
+    }
+    game.update(&no_input);
+    assert!(column.iter().all(|&c| game.world.is_chunk_loaded(c)));
+    assert_eq!(game.world.origin().y, 0);
+}
+
+#[test]
+pub fn test_set_blocks() {
+    let mut sequential = Game::new();
+    while sequential.pending_block_updates() > 0 {
+        sequential.update_blocks();
+    }
+    let mut batch = sequential.clone();
+
+    let mut edits = Vec::new();
+    for x in 0..8 {
+        for y in 36..44 {
+            edits.push((Vec3::new(x, y, 4), Block::STONE));
+        }
+    }
+    edits.push((Vec3::new(3, 40, 5), Block::LANTERN));
+    edits.push((Vec3::new(6, 40, 5), Block::LANTERN));
+    edits.push((Vec3::new(2, 39, 4), Block::AIR));
+    edits.push((Vec3::new(6, 40, 5), Block::AIR));
+    edits.push((Vec3::new(2, 39, 4), Block::LANTERN));
+
+    for &(position, block) in &edits {
+        sequential.set_block(position, block);
+    }
+    batch.set_blocks(edits);
+    assert!(batch.pending_block_updates() < sequential.pending_block_updates());
+
+    for game in [&mut sequential, &mut batch] {
+        while game.pending_block_updates() > 0 {
+            game.update_blocks();
+        }
+    }
+
+    let extents = sequential.world.extents;
+    let origin = sequential.world.origin();
+    assert_eq!(
+        batch.world.region_hash(origin - extents, origin + extents),
+        sequential
+            .world
+            .region_hash(origin - extents, origin + extents)
+    );
+}
+
+#[test]
+pub fn test_collision_iterations() {
+    use std::f32::consts::PI;
+
+    // A corner with walls on +x and +z.
+    let mut game = Game::new();
+    for x in 0..16 {
+        for z in 0..16 {
+            game.set_block(Vec3::new(x, 40, z), Block::STONE);
+            for y in 41..=44 {
+                let wall = x == 10 || z == 10;
+                game.set_block(
+                    Vec3::new(x, y, z),
+                    if wall { Block::STONE } else { Block::AIR },
+                );
+            }
+        }
+    }
+    game.camera.position = Vec3::new(9.5, 42.6, 9.5);
+    game.camera.yaw = Angle(PI * 0.75);
+    for _ in 0..TICK_RATE {
+        game.update(&InputState::pressing(&[], &[]));
+    }
+    assert!(game.on_ground);
+
+    // Walking into the corner hits the floor and both walls.
+    let mut corner = game.clone();
+    for _ in 0..TICK_RATE {
+        corner.update(&InputState::pressing(&[Keycode::W], &[]));
+        assert!(corner.last_collision.converged);
+        assert!(corner.last_collision.iterations <= DEFAULT_COLLISION_ITERATIONS);
+    }
+    // Pressed right up against both walls.
+    assert!((corner.camera.position.x - 9.9).abs() < 1e-3);
+    assert!((corner.camera.position.z - 9.9).abs() < 1e-3);
+
+    // Which is more than a single iteration can resolve.
+    let mut capped = game.clone();
+    capped.max_collision_iterations = 1;
+    capped.update(&InputState::pressing(&[Keycode::W], &[]));
+    capped.update(&InputState::pressing(&[Keycode::W], &[]));
+    assert!(!capped.last_collision.converged);
+    assert_eq!(capped.last_collision.iterations, 1);
+}
+
+#[test]
+pub fn test_apply_intent() {
+    use std::f32::consts::PI;
+
+    let mut game = Game::new();
+    for x in -8..24 {
+        for z in -8..24 {
+            game.set_block(Vec3::new(x, 40, z), Block::STONE);
+            for y in 41..=44 {
+                game.set_block(Vec3::new(x, y, z), Block::AIR);
+            }
+        }
+    }
+    game.camera.position = Vec3::new(8.5, 42.6, 8.5);
+    game.camera.yaw = Angle(PI * 0.25);
+    for _ in 0..TICK_RATE {
+        game.apply_intent(MoveIntent::default());
+    }
+    assert!(game.on_ground);
+
+    let mut keys = game.clone();
+    let mut bot = game.clone();
+    let w = InputState::pressing(&[Keycode::W], &[]);
+    for _ in 0..TICK_RATE {
+        keys.update(&w);
+        bot.apply_intent(MoveIntent {
+            direction: Vec2::new(0.0, 1.0),
+            ..Default::default()
+        });
+        assert_eq!(bot.eye_position(), keys.eye_position());
+    }
+
+    // Forward along where the player is looking.
+    let moved = bot.eye_position() - game.eye_position();
+    let look = game.look_direction();
+    assert!(moved.x * look.x + moved.z * look.z > SPEED * 0.9);
+    assert!(moved.y.abs() < 1e-3);
+}
+
+#[test]
+pub fn test_bob_offset() {
+    use std::f32::consts::{PI, TAU};
+
+    // Nothing while standing still.
+    for i in 0..16 {
+        assert_eq!(
+            bob_offset(Angle(i as f32 / 16.0 * TAU), 0.0),
+            ViewBob::default()
+        );
+    }
+
+    // Back to level between the steps.
+    assert_eq!(bob_offset(Angle(0.0), SPEED), ViewBob::default());
+    assert!(bob_offset(Angle(PI), SPEED).vertical.abs() < 1e-6);
+    assert!(bob_offset(Angle(PI), SPEED).roll.abs() < 1e-6);
+
+    // Highest at the middle of each step, rolling to one side and then the other.
+    let first = bob_offset(Angle(PI / 2.0), SPEED);
+    let second = bob_offset(Angle(PI * 1.5), SPEED);
+    assert!((first.vertical - BOB_HEIGHT).abs() < 1e-6);
+    assert!((second.vertical - BOB_HEIGHT).abs() < 1e-6);
+    assert!((first.roll - BOB_ROLL).abs() < 1e-6);
+    assert!((second.roll + BOB_ROLL).abs() < 1e-6);
+
+    for i in 0..64 {
+        let phase = Angle(i as f32 / 64.0 * TAU);
+        let offset = bob_offset(phase, SPEED);
+        assert!((0.0..=BOB_HEIGHT + 1e-6).contains(&offset.vertical));
+        assert!(offset.roll.abs() <= BOB_ROLL + 1e-6);
+
+        // Slower walking bobs less, faster than walking doesn't bob more.
+        let slow = bob_offset(phase, SPEED / 2.0);
+        assert!((slow.vertical - offset.vertical / 2.0).abs() < 1e-6);
+        assert_eq!(bob_offset(phase, SPEED * 10.0), offset);
+    }
+}
+
+#[test]
+pub fn test_world_border() {
+    use crate::collision::WorldBorder;
+
+    let mut game = Game::new();
+    for x in -8..24 {
+        for z in -8..24 {
+            game.set_block(Vec3::new(x, 40, z), Block::STONE);
+            for y in 41..=44 {
+                game.set_block(Vec3::new(x, y, z), Block::AIR);
+            }
+        }
+    }
+    game.camera.position = Vec3::new(8.5, 42.6, 8.5);
+    for _ in 0..TICK_RATE {
+        game.update(&InputState::pressing(&[], &[]));
+    }
+    assert!(game.on_ground);
+
+    // Without a border walking forward just keeps going.
+    let mut free = game.clone();
+    for _ in 0..TICK_RATE * 2 {
+        free.update(&InputState::pressing(&[Keycode::W], &[]));
+    }
+    let walked = free.camera.position - game.camera.position;
+    assert!(walked.x.hypot(walked.z) > 10.0);
+
+    let border = Aabb {
+        min: Vec3::new(6, 0, 6),
+        max: Vec3::new(11, 64, 11),
+    };
+    let mut bordered = game.clone();
+    bordered.world_border = Some(WorldBorder(border));
+    for keys in [[Keycode::W], [Keycode::S], [Keycode::A], [Keycode::D]] {
+        for _ in 0..TICK_RATE * 2 {
+            bordered.update(&InputState::pressing(&keys, &[]));
+            let player_box = bordered.player_aabb_at(bordered.camera.position);
+            assert!(player_box.min.x >= 6.0 - 1e-3 && player_box.max.x <= 11.0 + 1e-3);
+            assert!(player_box.min.z >= 6.0 - 1e-3 && player_box.max.z <= 11.0 + 1e-3);
+        }
+    }
+    // Pressed right up against the border rather than stopped short of it.
+    let player_box = bordered.player_aabb_at(bordered.camera.position);
+    assert!((player_box.min.x - 6.0).abs() < 1e-3 || (player_box.max.x - 11.0).abs() < 1e-3);
+}
+
+#[test]
+pub fn test_penetration_resolution() {
+    let no_input = InputState::default();
+    let player_box = |game: &Game| Aabb {
+        min: game.camera.position - PLAYER_ORIGIN,
+        max: game.camera.position - PLAYER_ORIGIN + PLAYER_SIZE,
+    };
+
+    let mut game = Game::new();
+    for x in 4..12 {
+        for z in 4..12 {
+            game.set_block(Vec3::new(x, 40, z), Block::STONE);
+            for y in 41..=44 {
+                game.set_block(Vec3::new(x, y, z), Block::AIR);
+            }
+        }
+    }
+    game.set_block(Vec3::new(8, 41, 8), Block::STONE);
+
+    // Standing on the ground isn't overlapping it.
+    game.camera.position = Vec3::new(6.5, 42.6, 6.5);
+    for _ in 0..TICK_RATE {
+        game.update(&no_input);
+    }
+    assert!(game.on_ground);
+    assert_eq!(
+        resolve_penetration(&game.world, player_box(&game)),
+        Vec3::zero()
+    );
+    assert_eq!((game.camera.position.x, game.camera.position.z), (6.5, 6.5));
+
+    // Teleported into the stone block.
+    game.camera.position = Vec3::new(8.5, 42.7, 8.5);
+    game.update(&no_input);
+    assert_eq!(
+        resolve_penetration(&game.world, player_box(&game)),
+        Vec3::zero()
+    );
+    assert!(!player_box(&game).collides_with_aabb(Aabb {
+        min: Vec3::new(8.01, 41.01, 8.01),
+        max: Vec3::new(8.99, 41.99, 8.99),
+    }));
+}
+
+#[test]
+pub fn test_swimming() {
+    // A pool with a stone bottom, filled with `ty` up to `surface`, as high as the loaded world goes.
+    let pool = |ty: BlockType, surface: i32| {
+        let mut game = Game::new();
+        for (x, y, z) in itertools::iproduct!(6..=10, 20..=47, 6..=10) {
+            let block = match y {
+                20 => Block::STONE,
+                _ if y <= surface => Block::new(ty),
+                _ => Block::AIR,
+            };
+            game.set_block(Vec3::new(x, y, z), block);
+        }
+        game.camera.position = Vec3::new(8.5, 40.5, 8.5);
+        game
+    };
+    let fall = |mut game: Game| {
+        let start = game.camera.position.y;
+        for _ in 0..TICK_RATE {
+            game.apply_intent(MoveIntent::default());
+        }
+        start - game.camera.position.y
+    };
+
+    assert_eq!(pool(BlockType::Water, 46).submersion(), 1.0);
+    assert_eq!(pool(BlockType::Air, 46).submersion(), 0.0);
+    let in_air = fall(pool(BlockType::Air, 46));
+    let in_water = fall(pool(BlockType::Water, 46));
+    assert!(
+        in_water > 0.0 && in_water < in_air / 3.0,
+        "{in_water} {in_air}"
+    );
+
+    // Feet in the water and head out only gets part of the buoyancy.
+    let partial = pool(BlockType::Water, 39);
+    let submersion = partial.submersion();
+    assert!(
+        (submersion - 1.0 / PLAYER_SIZE.y).abs() < 1e-4,
+        "{submersion}"
+    );
+    let up = partial.physics.up();
+    let buoyancy = |submersion| partial.physics.acceleration(submersion).dot(up);
+    assert!(buoyancy(0.0) < buoyancy(submersion) && buoyancy(submersion) < buoyancy(1.0));
+
+    // Holding jump swims up.
+    let mut game = pool(BlockType::Water, 46);
+    let start = game.camera.position.y;
+    for _ in 0..TICK_RATE {
+        game.apply_intent(MoveIntent {
+            jump: true,
+            ..Default::default()
+        });
+    }
+    assert!(game.camera.position.y > start + 1.0);
+
+    // Standing on the bottom doesn't let the player jump out of the water.
+    let mut game = pool(BlockType::Water, 46);
+    game.camera.position.y = 21.0 + PLAYER_ORIGIN.y;
+    game.apply_intent(MoveIntent::default());
+    assert!(game.on_ground);
+    game.apply_intent(MoveIntent {
+        jump: true,
+        ..Default::default()
+    });
+    let up_speed = game.velocity.dot(game.physics.up());
+    assert!(up_speed <= game.physics.swim_up_speed, "{up_speed}");
+}
+
+#[test]
+pub fn test_middle_click_lantern() {
+    let mut game = Game::new();
+    for (x, y, z) in itertools::iproduct!(6..=10, 40..=46, 6..=10) {
+        let block = if y == 40 { Block::STONE } else { Block::AIR };
+        game.set_block(Vec3::new(x, y, z), block);
+    }
+    game.camera.position = Vec3::new(8.5, 41.0 + PLAYER_ORIGIN.y, 8.5);
+    // Looking at the floor under the player's feet.
+    game.camera.pitch = Angle(std::f32::consts::FRAC_PI_2);
+    game.update(&InputState::default());
+    assert!(game.on_ground);
+
+    let middle_click =
+        InputState::pressing(&[], &[(MouseButton::Middle, ButtonState::JustPressed)]);
+    let feet = Vec3::new(8, 41, 8);
+
+    // Same checks as placing from the hotbar, so it doesn't go inside of the player.
+    assert_eq!(game.clone().update(&middle_click).placed, None);
+
+    game.prevent_placing_in_player = false;
+    let result = game.update(&middle_click);
+    assert_eq!(result.placed, Some((feet, Block::LANTERN)));
+    assert_eq!(
+        game.world.get_block(feet).map(|b| b.ty),
+        Some(BlockType::Lantern)
+    );
+}
+
+#[test]
+pub fn test_place_on_support() {
+    let mut game = Game::new();
+    for (x, y, z) in itertools::iproduct!(6..=10, 40..=46, 6..=10) {
+        let block = if y == 40 { Block::STONE } else { Block::AIR };
+        game.set_block(Vec3::new(x, y, z), block);
+    }
+    game.camera.position = Vec3::new(8.5, 41.0 + PLAYER_ORIGIN.y, 8.5);
+    // Looking at the sky.
+    game.camera.pitch = Angle(-std::f32::consts::FRAC_PI_2);
+    game.hotbar.slots[game.hotbar.active] = Some(BlockOrItem::Block(BlockType::Stone));
+    game.update(&InputState::default());
+    assert!(game.on_ground);
+    assert_eq!(game.place_raycast(), None);
+
+    let right_click = InputState::pressing(&[], &[(MouseButton::Right, ButtonState::JustPressed)]);
+    let on_support = Vec3::new(8, 41, 8);
+
+    // Off by default.
+    assert_eq!(game.support_target(), None);
+    assert_eq!(game.clone().update(&right_click).placed, None);
+
+    // The spot is taken by the player, so it's only placed there if they can be trapped.
+    game.place_on_support = true;
+    assert_eq!(
+        game.support_target().map(|target| target.position),
+        Some(Vec3::new(8, 40, 8))
+    );
+    assert_eq!(game.clone().update(&right_click).placed, None);
+
+    game.prevent_placing_in_player = false;
+    let result = game.update(&right_click);
+    assert_eq!(result.placed, Some((on_support, Block::STONE)));
+    assert_eq!(
+        game.world.get_block(on_support).map(|b| b.ty),
+        Some(BlockType::Stone)
+    );
+}
+
+#[test]
+pub fn test_load_radius() {
+    let mut game = Game::new();
+    assert_eq!(game.load_radius(), game.max_load_radius());
+    game.set_load_radius(100.0);
+    assert_eq!(game.load_radius(), game.max_load_radius());
+
+    for chunk_coord in game.world.loaded_chunks().collect_vec() {
+        game.world.unload(chunk_coord).unwrap();
+    }
+    let full = game.chunks_to_load();
+    assert!(!full.is_empty());
+
+    game.set_load_radius(3.0);
+    assert_eq!(game.load_radius(), 3.0);
+    let smaller = game.chunks_to_load();
+    assert!(smaller.len() < full.len());
+    assert!(smaller.iter().all(|chunk_coord| full.contains(chunk_coord)));
+    assert!(smaller
+        .iter()
+        .all(|chunk_coord| { (chunk_coord - game.world.origin()).as_::<f32>().magnitude() < 3.0 }));
+}
+
+#[test]
+pub fn test_coyote_time() {
+    use std::f32::consts::FRAC_PI_2;
+
+    // A ledge ending at x = 9.
+    let mut game = Game::new();
+    for x in 2..14 {
+        for z in 6..11 {
+            for y in 36..=44 {
+                let ground = y == 40 && x < 9;
+                game.set_block(
+                    Vec3::new(x, y, z),
+                    if ground { Block::STONE } else { Block::AIR },
+                );
+            }
+        }
+    }
+    game.camera.position = Vec3::new(6.5, 42.6, 8.5);
+    game.camera.yaw = Angle(FRAC_PI_2);
+    for _ in 0..TICK_RATE {
+        game.update(&InputState::pressing(&[], &[]));
+    }
+    assert!(game.on_ground);
+
+    while game.on_ground {
+        game.update(&InputState::pressing(&[Keycode::W], &[]));
+        assert!(game.camera.position.x < 10.0);
+    }
+
+    // Jumping a tick after walking off still works.
+    game.update(&InputState::pressing(&[Keycode::W, Keycode::Space], &[]));
+    let jump_velocity = game.velocity.y;
+    assert!(jump_velocity > 0.0);
+
+    // But only once.
+    game.update(&InputState::pressing(&[Keycode::W, Keycode::Space], &[]));
+    assert!(game.velocity.y < jump_velocity);
+    game.update(&InputState::pressing(&[Keycode::W, Keycode::Space], &[]));
+    assert!(game.velocity.y < jump_velocity - 1.0);
+}
+
+#[test]
+pub fn test_bucket() {
+    let mut game = Game::new();
+    game.flying = true;
+    game.camera.position = Vec3::new(8.5, 44.5, 8.5);
+    game.camera.pitch = Angle(std::f32::consts::FRAC_PI_2);
+
+    game.set_block(Vec3::new(8, 41, 8), Block::STONE);
+    game.set_block(Vec3::new(8, 42, 8), Block::WATER);
+    for y in 43..=46 {
+        game.set_block(Vec3::new(8, y, 8), Block::AIR);
+    }
+    game.hotbar.active = 6;
+
+    // Picking up water fills the bucket.
+    game.update(&InputState::pressing(
+        &[],
+        &[(MouseButton::Right, ButtonState::JustPressed)],
+    ));
+    assert_eq!(
+        game.world.get_block(Vec3::new(8, 42, 8)).map(|b| b.ty),
+        Some(BlockType::Air)
+    );
+    assert_eq!(
+        game.hotbar.slots[6],
+        Some(BlockOrItem::Item(Item::WaterBucket))
+    );
+
+    // Using it on the stone below places the water back on top of it.
+    game.update(&InputState::pressing(
+        &[],
+        &[(MouseButton::Right, ButtonState::JustPressed)],
+    ));
+    assert_eq!(
+        game.world.get_block(Vec3::new(8, 42, 8)).map(|b| b.ty),
+        Some(BlockType::Water)
+    );
+    assert_eq!(game.hotbar.slots[6], Some(BlockOrItem::Item(Item::Bucket)));
+
+    // An empty bucket used on something other than water does nothing.
+    game.set_block(Vec3::new(8, 42, 8), Block::AIR);
+    game.update(&InputState::pressing(
+        &[],
+        &[(MouseButton::Right, ButtonState::JustPressed)],
+    ));
+    assert_eq!(
+        game.world.get_block(Vec3::new(8, 41, 8)).map(|b| b.ty),
+        Some(BlockType::Stone)
+    );
+    assert_eq!(game.hotbar.slots[6], Some(BlockOrItem::Item(Item::Bucket)));
+
+    // Water can't be poured further away than blocks can be placed.
+    for y in 43..=64 {
+        game.set_block(Vec3::new(8, y, 8), Block::AIR);
+    }
+    game.hotbar.slots[6] = Some(BlockOrItem::Item(Item::WaterBucket));
+    game.camera.position.y = 42.0 + game.reach + 4.0;
+    game.update(&InputState::pressing(
+        &[],
+        &[(MouseButton::Right, ButtonState::JustPressed)],
+    ));
+    assert_eq!(
+        game.world.get_block(Vec3::new(8, 42, 8)).map(|b| b.ty),
+        Some(BlockType::Air)
+    );
+    assert_eq!(
+        game.hotbar.slots[6],
+        Some(BlockOrItem::Item(Item::WaterBucket))
+    );
+}
+
+#[test]
+pub fn test_mining_speed() {
+    let mut game = Game::new();
+    game.flying = true;
+    game.camera.position = Vec3::new(8.5, 44.5, 8.5);
+    game.camera.pitch = Angle(std::f32::consts::FRAC_PI_2);
+    for y in 30..=42 {
+        game.set_block(Vec3::new(8, y, 8), Block::STONE);
+    }
+    for y in 43..=46 {
+        game.set_block(Vec3::new(8, y, 8), Block::AIR);
+    }
+
+    let ticks_to_break = |mut game: Game, slot: Option<BlockOrItem>| {
+        game.hotbar.slots[game.hotbar.active] = slot;
+        for tick in 0..100 {
+            let state = if tick == 0 {
+                ButtonState::JustPressed
+            } else {
+                ButtonState::KeptPressed
+            };
+            game.update(&InputState::pressing(&[], &[(MouseButton::Left, state)]));
+            if game.world.get_block(Vec3::new(8, 42, 8)).unwrap().ty != BlockType::Stone {
+                return Some(tick + 1);
+            }
+        }
+        None
+    };
+
+    let hand = ticks_to_break(game.clone(), None);
+    let pickaxe = ticks_to_break(game.clone(), Some(BlockOrItem::Item(Item::Pickaxe)));
+    assert_eq!(hand, Some(30));
+    assert_eq!(pickaxe, Some(8));
+
+    let mut creative = game.clone();
+    creative.creative = true;
+    assert_eq!(ticks_to_break(creative, None), Some(1));
+
+    // Every block breaks eventually, whatever is held.
+    for (ty, item) in itertools::iproduct!(BlockType::ALL, Item::ALL) {
+        let mut game = game.clone();
+        game.hotbar.slots[game.hotbar.active] = Some(BlockOrItem::Item(item));
+        assert!(game.mining_progress(ty) > 0.0);
+    }
+}
+
+#[test]
+pub fn test_mode_switch_resets_mining() {
+    let mut game = Game::new();
+    game.flying = true;
+    game.camera.position = Vec3::new(8.5, 44.5, 8.5);
+    game.camera.pitch = Angle(std::f32::consts::FRAC_PI_2);
+    for y in 30..=42 {
+        game.set_block(Vec3::new(8, y, 8), Block::STONE);
+    }
+    for y in 43..=46 {
+        game.set_block(Vec3::new(8, y, 8), Block::AIR);
+    }
+    game.hotbar.slots[game.hotbar.active] = None;
+
+    let left_mouse = |game: &mut Game, state: ButtonState| {
+        game.update(&InputState::pressing(&[], &[(MouseButton::Left, state)]));
+    };
+    let stone_left =
+        |game: &Game| game.world.get_block(Vec3::new(8, 42, 8)).unwrap().ty == BlockType::Stone;
+
+    // A single click breaks the block in creative, but only starts mining it outside of creative.
+    {
+        let mut game = game.clone();
+        game.creative = true;
+        left_mouse(&mut game, ButtonState::JustPressed);
+        assert!(!stone_left(&game));
+    }
+    left_mouse(&mut game, ButtonState::JustPressed);
+    for _ in 1..20 {
+        left_mouse(&mut game, ButtonState::KeptPressed);
+    }
+    assert!(stone_left(&game));
+    assert!(game.mining.progress > 0.5);
+
+    // A tick in creative loses the progress, without breaking the block since the hold just started there.
+    game.creative = true;
+    left_mouse(&mut game, ButtonState::KeptPressed);
+    assert!(stone_left(&game));
+    assert_eq!(game.mining, Mining::default());
+
+    game.creative = false;
+    let mut ticks = 0;
+    while stone_left(&game) && ticks < 100 {
+        left_mouse(&mut game, ButtonState::KeptPressed);
+        ticks += 1;
+    }
+    assert_eq!(ticks, 30);
+}
+
+#[test]
+pub fn test_item_texture_layers() {
+    let mut layers = Item::ALL
+        .into_iter()
+        .filter_map(|item| item.texture_layer())
+        .collect::<Vec<_>>();
+    layers.sort();
+    // Every layer of the item texture array is used by exactly one item.
+    assert_eq!(layers, (0..layers.len() as u32).collect::<Vec<_>>());
+    assert_eq!(Item::Empty.texture_layer(), None);
+}
+
+#[test]
+pub fn test_placement_target() {
+    let mut game = Game::new();
+    game.flying = true;
+    for (x, y, z) in itertools::iproduct!(4..=12, 40..=46, 4..=12) {
+        game.set_block(
+            Vec3::new(x, y, z),
+            if y == 40 { Block::STONE } else { Block::AIR },
+        );
+    }
+    game.set_block(Vec3::new(6, 41, 8), Block::STONE);
+    game.hotbar.slots[0] = Some(BlockOrItem::Block(BlockType::Wood));
+    game.hotbar.active = 0;
+    game.camera.position = Vec3::new(8.5, 41.0 + PLAYER_ORIGIN.y, 8.5);
+    game.camera.yaw = Angle(std::f32::consts::PI * 1.5);
+
+    // Looking down at the wall in front of the player.
+    let mut aimed = game.clone();
+    aimed.camera.pitch = Angle(0.6);
+    aimed.update(&InputState::pressing(
+        &[],
+        &[(MouseButton::Right, ButtonState::KeptReleased)],
+    ));
+    let target = aimed.placement_target().unwrap();
+    assert_eq!(target, Vec3::new(7, 41, 8));
+    aimed.update(&InputState::pressing(
+        &[],
+        &[(MouseButton::Right, ButtonState::JustPressed)],
+    ));
+    assert_eq!(aimed.world.get_block(target).unwrap().ty, BlockType::Wood);
+
+    // Nothing highlighted.
+    let mut sky = game.clone();
+    sky.camera.pitch = Angle(-1.5);
+    sky.update(&InputState::pressing(
+        &[],
+        &[(MouseButton::Right, ButtonState::KeptReleased)],
+    ));
+    assert_eq!(sky.look_at_raycast, None);
+    assert_eq!(sky.placement_target(), None);
+
+    // Looking straight down, the block would go where the player is standing.
+    let mut trapped = game.clone();
+    trapped.camera.position.x = 7.5;
+    trapped.camera.pitch = Angle(1.5);
+    trapped.update(&InputState::pressing(
+        &[],
+        &[(MouseButton::Right, ButtonState::KeptReleased)],
+    ));
+    assert_eq!(
+        trapped.look_at_raycast.map(|raycast| raycast.position),
+        Some(Vec3::new(7, 40, 8))
+    );
+    assert_eq!(trapped.placement_target(), None);
+
+    // Items aren't placed.
+    aimed.hotbar.slots[0] = Some(BlockOrItem::Item(Item::Pickaxe));
+    assert_eq!(aimed.placement_target(), None);
+}
+
+#[test]
+pub fn test_sideways_gravity() {
+    // A wall at x = 12, the player floats in the air next to it.
+    let mut game = Game::new();
+    for (x, y, z) in itertools::iproduct!(2..14, 38..=48, 4..13) {
+        let wall = x == 12;
+        game.set_block(
+            Vec3::new(x, y, z),
+            if wall { Block::STONE } else { Block::AIR },
+        );
+    }
+    game.physics.gravity = Vec3::new(GRAVITY, 0.0, 0.0);
+    assert_eq!(game.physics.up(), -Vec3::unit_x());
+    game.camera.position = Vec3::new(6.5, 43.5, 8.5);
+
+    for _ in 0..TICK_RATE * 2 {
+        game.update(&InputState::pressing(&[], &[]));
+    }
+    assert!(game.on_ground);
+    assert!((game.player_aabb().max.x - 12.0).abs() < 0.01);
+    assert_eq!(game.camera.position.y, 43.5);
+
+    // Jumping pushes away from the wall.
+    game.update(&InputState::pressing(&[Keycode::Space], &[]));
+    assert!(game.velocity.x < 0.0);
+    game.update(&InputState::pressing(&[], &[]));
+    assert!(game.player_aabb().max.x < 12.0);
+    assert!(!game.on_ground);
+}
+
+#[test]
+pub fn test_biome_grass_tint() {
+    use std::collections::HashSet;
+
+    assert_eq!(Biome::Forest.grass_tint(), [255, 255, 255]);
+
+    // Plains are yellowish and cold biomes bluish.
+    let [r, g, b] = Biome::Plains.grass_tint();
+    assert!(r > b && g > b);
+    let [r, g, b] = Biome::Cold.grass_tint();
+    assert!(b > r && b > g);
+
+    // All of them show up somewhere.
+    let terrain = TerrainSampler::new(DEFAULT_SEED);
+    let biomes = itertools::iproduct!(0..40, 0..40)
+        .map(|(x, z)| terrain.biome(Vec2::new(x, z) * 50))
+        .collect::<HashSet<_>>();
+    assert_eq!(biomes.len(), 3);
+}
+
+#[test]
+pub fn test_custom_loadout() {
+    assert_eq!(Game::new().hotbar.slots, DEFAULT_LOADOUT);
+
+    let mut loadout = [None; 9];
+    loadout[3] = Some(BlockOrItem::Item(Item::Pickaxe));
+    let game = Game::with_loadout(loadout);
+    assert_eq!(game.hotbar.slots, loadout);
+    assert_eq!(game.hotbar.active, 0);
+    assert!(Game::with_loadout([None; 9])
+        .hotbar
+        .slots
+        .iter()
+        .all(Option::is_none));
+}
+
+#[test]
+pub fn test_relight_all() {
+    // A closed off tunnel, so only the lantern lights it.
+    let mut game = Game::new();
+    game.world = World::new(Vec3::zero());
+    let mut chunk = Chunk::from_blocks(ndarray::Array3::from_elem(
+        (CHUNK_SIZE, CHUNK_SIZE, CHUNK_SIZE),
+        Block::STONE,
+    ));
+    for x in 1..15 {
+        chunk.set_block(Vec3::new(x, 8, 8), Block::AIR);
+    }
+    game.world.load(Vec3::zero(), chunk);
+
+    game.set_block(Vec3::new(4, 8, 8), Block::LANTERN);
+    while !game.dirty_blocks.is_empty() {
+        game.update_blocks();
+    }
+    let light = |game: &Game| {
+        game.world
+            .chunk_at(Vec3::zero())
+            .unwrap()
+            .iter_blocks()
+            .map(|(_, block)| block.light)
+            .collect_vec()
+    };
+    let incremental = light(&game);
+    assert!(incremental.iter().filter(|&&light| light > 0).count() > 5);
+
+    game.relight_all();
+    assert!(light(&game).iter().all(|&light| light == 0));
+    assert!(!game.dirty_blocks.is_empty());
+
+    game.relight_all_blocking();
+    assert!(game.dirty_blocks.is_empty());
+    assert_eq!(light(&game), incremental);
+
+    // Compressed chunks are relit too.
+    game.world.compress_distant(-1);
+    assert!(game.world.is_compressed(Vec3::zero()));
+    game.relight_all();
+    assert!(light(&game).iter().all(|&light| light == 0));
+    game.relight_all_blocking();
+    assert_eq!(light(&game), incremental);
+}
+
+#[test]
+pub fn test_relight_all_batches() {
+    // A closed off tunnel, half of it is lanterns lighting up the other half.
+    let mut game = Game::new();
+    game.world = World::new(Vec3::zero());
+    let mut chunk = Chunk::from_blocks(ndarray::Array3::from_elem(
+        (CHUNK_SIZE, CHUNK_SIZE, CHUNK_SIZE),
+        Block::STONE,
+    ));
+    for x in 1..15 {
+        let block = if x < 8 { Block::LANTERN } else { Block::AIR };
+        chunk.set_block(Vec3::new(x, 8, 8), block);
+    }
+    game.world.load(Vec3::zero(), chunk);
+    let light = |game: &Game| {
+        (1..15)
+            .map(|x| game.world.get_block(Vec3::new(x, 8, 8)).unwrap().light)
+            .collect_vec()
+    };
+
+    game.relight_all_blocking();
+    let expected = light(&game);
+    assert!(expected.iter().all(|&light| light > 0));
+
+    // 7 sources don't fit in batches of 2, the light still spreads like when they're queued at once.
+    game.limits.max_pending_updates = 16;
+    game.relight_all();
+    assert!(game.pending_block_updates() <= 16);
+    game.relight_all_blocking();
+    assert_eq!(light(&game), expected);
+}
+
+#[test]
+pub fn test_reachable_blocks() {
+    let mut game = Game::new();
+    for (y, z) in itertools::iproduct!(38..=46, 2..=14) {
+        game.set_block(Vec3::new(12, y, z), Block::STONE);
+        for x in 4..12 {
+            game.set_block(Vec3::new(x, y, z), Block::AIR);
+        }
+    }
+    game.camera.position = Vec3::new(8.5, 42.5, 8.5);
+    game.camera.pitch = Angle(0.0);
+    game.camera.yaw = Angle(std::f32::consts::FRAC_PI_2);
+    assert_eq!(game.camera.look_at().x.round(), 1.0);
+
+    let reachable = game.reachable_blocks(0.5, 32);
+    assert!(reachable.contains(&Vec3::new(12, 42, 8)));
+    assert!(reachable.len() > 1 && reachable.len() <= 32);
+    assert!(reachable.iter().all(|position| position.x == 12));
+
+    assert!(game.reachable_blocks(0.5, 0).is_empty());
+    // Out of reach.
+    game.reach = 3.0;
+    assert!(game.reachable_blocks(0.5, 32).is_empty());
+}
+
+#[test]
+pub fn test_dirty_mesh_chunks() {
+    let mut game = Game::new();
+    game.update(&InputState::default());
+
+    game.dirty_mesh_chunks.0.clear();
+    game.set_block(Vec3::new(8, 42, 8), Block::STONE);
+    assert_eq!(
+        *game.dirty_mesh_chunks(),
+        HashSet::from_iter([coords::world_to_chunk(Vec3::new(8, 42, 8))])
+    );
+
+    // On the border between chunk x 0 and x 1, both show the faces against it.
+    game.dirty_mesh_chunks.0.clear();
+    game.set_block(Vec3::new(16, 42, 8), Block::STONE);
+    let chunk_coord = coords::world_to_chunk(Vec3::new(16, 42, 8));
+    assert_eq!(
+        *game.dirty_mesh_chunks(),
+        HashSet::from_iter([chunk_coord, chunk_coord - Vec3::unit_x()])
+    );
+
+    // On a corner the diagonal chunks are included, their ambient occlusion looks at it.
+    game.dirty_mesh_chunks.0.clear();
+    game.set_block(Vec3::new(16, 42, 15), Block::STONE);
+    let chunk_coord = coords::world_to_chunk(Vec3::new(16, 42, 15));
+    assert_eq!(
+        *game.dirty_mesh_chunks(),
+        HashSet::from_iter([
+            chunk_coord,
+            chunk_coord - Vec3::unit_x(),
+            chunk_coord + Vec3::unit_z(),
+            chunk_coord - Vec3::unit_x() + Vec3::unit_z(),
+        ])
+    );
+}
+
+#[test]
+pub fn test_teleport() {
+    let mut game = Game::new();
+    let destination = Vec3::new(1000.5, 60.5, -1000.5);
+    game.teleport(destination);
+    assert_eq!(game.camera.position, destination);
+    assert_eq!(game.world.origin(), game.chunk_coordinate());
+    assert!(!game.current_chunk_loaded());
+
+    // Held in the air until the chunk it's in gets generated.
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while !game.current_chunk_loaded() && std::time::Instant::now() < deadline {
+        game.update(&InputState::default());
+        if !game.current_chunk_loaded() {
+            assert_eq!(game.camera.position, destination);
+        }
+    }
+    assert!(game.current_chunk_loaded());
+
+    // Teleporting within the loaded chunks doesn't wait.
+    game.teleport(destination + Vec3::unit_x());
+    assert!(game.current_chunk_loaded());
+    game.update(&InputState::default());
+    assert_ne!(game.camera.position, destination + Vec3::unit_x());
+}
+
+#[test]
+pub fn test_walk_through_blocks() {
+    // Walks along a corridor towards a wall of `ty`, returns how far the player got.
+    let walk = |ty: BlockType| {
+        let mut game = Game::new();
+        for (x, z) in itertools::iproduct!(2..=16, 6..=10) {
+            game.set_block(Vec3::new(x, 40, z), Block::STONE);
+            for y in 41..=44 {
+                game.set_block(Vec3::new(x, y, z), Block::AIR);
+            }
+        }
+        for (y, z) in itertools::iproduct!(41..=42, 6..=10) {
+            game.set_block(Vec3::new(10, y, z), Block::new(ty));
+        }
+        game.camera.position = Vec3::new(6.5, 42.6, 8.5);
+        game.camera.pitch = Angle(0.0);
+        game.camera.yaw = Angle(std::f32::consts::FRAC_PI_2);
+        for _ in 0..TICK_RATE {
+            game.apply_intent(MoveIntent {
+                direction: Vec2::new(0.0, 1.0),
+                ..Default::default()
+            });
+        }
+        game.camera.position.x
+    };
+
+    assert!(walk(BlockType::Mesh) > 11.0);
+    assert!(walk(BlockType::Grass) < 10.0);
+
+    // Still drawn and looked at like any other cube.
+    assert!(BlockType::Mesh.texture_layer().is_some());
+    assert_eq!(BlockType::Mesh.shape(), crate::BlockShape::Full);
+}
+
+#[test]
+pub fn test_random_ticks() {
+    // Grass with dirt next to it and dirt further away, in a world of a single chunk.
+    let spread = |seed: u32| {
+        let mut game = Game::new();
+        game.world = World::new(Vec3::zero());
+        game.world.load(Vec3::zero(), Chunk::new());
+        game.random_ticks = RandomTicks::new(seed);
+        // About one tick of the grass block every tick.
+        game.random_ticks.per_chunk = (CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE) as u32;
+        game.flying = true;
+        game.camera.position = Vec3::new(8.5, 12.5, 8.5);
+        game.set_block(Vec3::new(4, 4, 4), Block::GRASS);
+        game.set_block(Vec3::new(5, 4, 4), Block::DIRT);
+        game.set_block(Vec3::new(9, 4, 4), Block::DIRT);
+        game.set_block(Vec3::new(4, 4, 9), Block::STONE);
+
+        let ticks = (1..=500).find(|_| {
+            game.update(&InputState::default());
+            game.world.get_block(Vec3::new(5, 4, 4)).unwrap().ty == BlockType::Grass
+        });
+        (ticks, game)
+    };
+
+    let (ticks, game) = spread(1234);
+    assert!(ticks.is_some());
+    // Blocks without a random tick and dirt out of reach stay the same.
+    assert_eq!(
+        game.world.get_block(Vec3::new(9, 4, 4)).unwrap().ty,
+        BlockType::Dirt
+    );
+    assert_eq!(
+        game.world.get_block(Vec3::new(4, 4, 9)).unwrap().ty,
+        BlockType::Stone
+    );
+    // The same seed spreads on the same tick.
+    assert_eq!(spread(1234).0, ticks);
+}
+
+#[test]
+pub fn test_raycast_from_camera() {
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    let mut game = Game::new();
+    for (x, y, z) in itertools::iproduct!(4..=14, 41..=44, 4..=14) {
+        game.set_block(Vec3::new(x, y, z), Block::AIR);
+    }
+    // One block straight ahead in +x, one in +z and one on the diagonal between them.
+    for position in [
+        Vec3::new(13, 42, 8),
+        Vec3::new(8, 42, 13),
+        Vec3::new(11, 42, 11),
+    ] {
+        game.set_block(position, Block::STONE);
+    }
+    game.camera.position = Vec3::new(8.5, 42.5, 8.5);
+    game.camera.pitch = Angle(0.0);
+
+    let mut prev = game.clone();
+    prev.camera.yaw = Angle(FRAC_PI_2);
+    let mut curr = game.clone();
+    curr.camera.yaw = Angle(PI);
+    for game in [&mut prev, &mut curr] {
+        game.look_at_raycast = game.raycast_from_camera();
+    }
+    assert_eq!(prev.highlighted_position(), Some(Vec3::new(13, 42, 8)));
+    assert_eq!(curr.highlighted_position(), Some(Vec3::new(8, 42, 13)));
+
+    // The blended raycast snaps to one of the ticks, the camera itself is in between them.
+    let blended = prev.blend(&curr, 0.5);
+    assert_ne!(blended.highlighted_position(), Some(Vec3::new(11, 42, 11)));
+    let raycast = blended.raycast_from_camera().unwrap();
+    assert_eq!(raycast.position, Vec3::new(11, 42, 11));
+    assert_eq!(blended.place_raycast(), Some(raycast));
+
+    // Same reach as the ticks.
+    let mut short = blended.clone();
+    short.reach = 2.0;
+    assert_eq!(short.raycast_from_camera(), None);
+}
+
+#[test]
+pub fn test_fly_speed() {
+    let forward = InputState::pressing(&[Keycode::W], &[]);
+
+    let mut game = Game::new();
+    game.flying = true;
+    game.camera.position = Vec3::new(8.5, 100.5, 8.5);
+    game.camera.yaw = Angle(0.0);
+    game.camera.pitch = Angle(0.0);
+
+    let fly = |fly: FlyParams| {
+        let mut game = game.clone();
+        game.fly = fly;
+        let start = game.camera.position;
+        game.update(&forward);
+        (game.camera.position - start).magnitude()
+    };
+
+    let default = fly(FlyParams::default());
+    assert!(
+        (default - SPEED * 10.0 * TICK_DELTA).abs() < 0.001,
+        "{default}"
+    );
+
+    let fast = fly(FlyParams {
+        horizontal_speed: 120.0,
+        ..FlyParams::default()
+    });
+    assert!(fast > default, "{fast} <= {default}");
+
+    let smooth = fly(FlyParams {
+        acceleration: 20.0,
+        ..FlyParams::default()
+    });
+    assert!(smooth < default, "{smooth} >= {default}");
+}
+
+#[test]
+pub fn test_tick_number() {
+    let mut game = Game::new();
+    assert_eq!(game.tick_number(), 0);
+
+    const N: u64 = 25;
+    for _ in 0..N {
+        game.update(&InputState::default());
+    }
+    assert_eq!(game.tick_number(), N);
+
+    // Blending picks one of the ticks instead of a tick in between.
+    let mut next = game.clone();
+    next.update(&InputState::default());
+    assert_eq!(game.blend(&next, 0.4).tick_number(), N);
+    assert_eq!(game.blend(&next, 0.6).tick_number(), N + 1);
+}
+
+#[test]
+pub fn test_spawn_pinned() {
+    let game = Game::new();
+    if let Some(radius) = SPAWN_PIN_RADIUS {
+        assert!(game.world.is_pinned(Vec3::zero()));
+        assert!(game.world.is_pinned(Vec3::new(radius, -radius, radius)));
+        assert!(!game.world.is_pinned(Vec3::new(radius + 1, 0, 0)));
+    }
 }
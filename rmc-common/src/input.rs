@@ -1,14 +1,22 @@
 use std::{collections::HashMap, hash::Hash};
 
+use itertools::Itertools;
 use sdl2::{keyboard::Keycode, mouse::MouseButton};
 use vek::Vec2;
 
-#[derive(Debug, Clone)]
+use crate::net::ByteReader;
+
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct InputState {
     pub keys: HashMap<Keycode, ButtonState>,
     pub mouse_buttons: HashMap<MouseButton, ButtonState>,
     pub mouse_delta: Vec2<f32>,
     pub scroll_delta: i32,
+    /// Tick passed to the last `update_held_status`, presses are recorded at this tick.
+    pub tick: u64,
+    /// Ticks of the latest press of each key and of the press before it.
+    /// Only a release followed by a press counts, holding a key down doesn't record anything.
+    pub key_presses: HashMap<Keycode, (u64, Option<u64>)>,
 }
 
 impl InputState {
@@ -28,10 +36,95 @@ impl InputState {
 
         Vec2::new(rgh_lft as f32, fwd_bck as f32)
     }
+
+    /// The movement these keys ask for, what the player does with them is the same as for any other `MoveIntent`.
+    pub fn move_intent(&self) -> MoveIntent {
+        MoveIntent {
+            direction: self.get_movement_vector(),
+            jump: self.get_key(Keycode::Space).pressed(),
+            crouch: self.get_key(Keycode::LShift).pressed(),
+        }
+    }
+
+    /// Whether `keycode` was just pressed for the second time, at most `within_ticks` after the first press.
+    pub fn double_tapped(&self, keycode: Keycode, within_ticks: u64) -> bool {
+        if !self.get_key(keycode).just_pressed() {
+            return false;
+        }
+
+        match self.key_presses.get(&keycode) {
+            Some(&(latest, Some(previous))) => latest - previous <= within_ticks,
+            _ => false,
+        }
+    }
+
+    /// Layout (little endian), keys and buttons are sorted so equal states always encode the same.
+    /// The tick and press history aren't encoded, they're only needed where the input is collected:
+    /// - key count: u16, keys: (keycode: i32, state: u8)
+    /// - mouse button count: u8, mouse buttons: (button: u8, state: u8)
+    /// - mouse delta: 2 * f32
+    /// - scroll delta: i32
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend((self.keys.len() as u16).to_le_bytes());
+        for (&key, &state) in self.keys.iter().sorted_by_key(|(&key, _)| key as i32) {
+            bytes.extend((key as i32).to_le_bytes());
+            bytes.push(state as u8);
+        }
+        bytes.push(self.mouse_buttons.len() as u8);
+        for (&button, &state) in self
+            .mouse_buttons
+            .iter()
+            .sorted_by_key(|(&button, _)| button as u8)
+        {
+            bytes.extend([button as u8, state as u8]);
+        }
+        bytes.extend(self.mouse_delta.x.to_le_bytes());
+        bytes.extend(self.mouse_delta.y.to_le_bytes());
+        bytes.extend(self.scroll_delta.to_le_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut reader = ByteReader::new(bytes);
+        let input = Self::read(&mut reader)?;
+        reader.is_empty().then_some(input)
+    }
+
+    fn read(reader: &mut ByteReader) -> Option<Self> {
+        let key_count = reader.u16()?;
+        let keys = (0..key_count)
+            .map(|_| {
+                Some((
+                    Keycode::from_i32(reader.i32()?)?,
+                    ButtonState::from_id(reader.u8()?)?,
+                ))
+            })
+            .collect::<Option<HashMap<_, _>>>()?;
+        let button_count = reader.u8()?;
+        let mouse_buttons = (0..button_count)
+            .map(|_| {
+                Some((
+                    MouseButton::from_ll(reader.u8()?),
+                    ButtonState::from_id(reader.u8()?)?,
+                ))
+            })
+            .collect::<Option<HashMap<_, _>>>()?;
+
+        Some(InputState {
+            keys,
+            mouse_buttons,
+            mouse_delta: reader.vec2_f32()?,
+            scroll_delta: reader.i32()?,
+            ..Default::default()
+        })
+    }
 }
 
 impl InputState {
-    pub fn update_held_status(&mut self) {
+    /// `tick` should go up by one every call, it's what presses pushed after this are recorded at.
+    pub fn update_held_status(&mut self, tick: u64) {
+        self.tick = tick;
         for keycode in self.keys.keys().cloned().collect::<Vec<_>>() {
             self.keys.insert(
                 keycode,
@@ -61,6 +154,8 @@ impl InputState {
                 if self.get_key(event.key).pressed() {
                     ButtonState::KeptPressed
                 } else {
+                    let previous = self.key_presses.get(&event.key).map(|&(latest, _)| latest);
+                    self.key_presses.insert(event.key, (self.tick, previous));
                     ButtonState::JustPressed
                 }
             } else {
@@ -93,6 +188,32 @@ impl InputState {
     }
 }
 
+#[cfg(test)]
+impl InputState {
+    /// Input holding down `keys`, with the mouse `buttons` in the given states.
+    pub fn pressing(keys: &[Keycode], buttons: &[(MouseButton, ButtonState)]) -> InputState {
+        InputState {
+            keys: keys
+                .iter()
+                .map(|&key| (key, ButtonState::KeptPressed))
+                .collect(),
+            mouse_buttons: buttons.iter().copied().collect(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Movement the player is asked to do for a tick, without going through keys.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct MoveIntent {
+    /// Right in x and forward in y relative to the camera, like `InputState::get_movement_vector`.
+    pub direction: Vec2<f32>,
+    /// Jumps on the ground, flies up while flying.
+    pub jump: bool,
+    /// Crouches on the ground, flies down while flying.
+    pub crouch: bool,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ButtonState {
     KeptPressed,
@@ -102,6 +223,19 @@ pub enum ButtonState {
 }
 
 impl ButtonState {
+    pub const ALL: [ButtonState; 4] = [
+        ButtonState::KeptPressed,
+        ButtonState::JustPressed,
+        ButtonState::KeptReleased,
+        ButtonState::JustReleased,
+    ];
+
+    pub fn from_id(id: u8) -> Option<ButtonState> {
+        ButtonState::ALL
+            .into_iter()
+            .find(|&state| state as u8 == id)
+    }
+
     pub fn just_pressed(self) -> bool {
         self == ButtonState::JustPressed
     }
@@ -370,3 +504,43 @@ pub fn test_key_buffer() {
     assert_eq!(buffer.pull(Keycode::A), None);
     assert_eq!(buffer.pull(Keycode::B), None);
 }
+
+#[test]
+fn test_double_tapped() {
+    let mut input = InputState::default();
+    let mut tick = 0;
+    let mut step = |input: &mut InputState, state: Option<ButtonStateEvent>| {
+        tick += 1;
+        input.update_held_status(tick);
+        if let Some(state) = state {
+            input.push_keyboard_event(KeyboardEvent {
+                key: Keycode::W,
+                state,
+            });
+        }
+    };
+
+    // Press, release and press again within the window.
+    step(&mut input, Some(ButtonStateEvent::Press));
+    assert!(!input.double_tapped(Keycode::W, 5));
+    step(&mut input, Some(ButtonStateEvent::Release));
+    step(&mut input, Some(ButtonStateEvent::Press));
+    assert!(input.double_tapped(Keycode::W, 5));
+    // Only on the tick of the second press.
+    step(&mut input, None);
+    assert!(!input.double_tapped(Keycode::W, 5));
+
+    // Holding the key and pressing it again without a release isn't a tap.
+    step(&mut input, Some(ButtonStateEvent::Press));
+    assert!(!input.double_tapped(Keycode::W, 5));
+
+    // Too slow.
+    step(&mut input, Some(ButtonStateEvent::Release));
+    for _ in 0..10 {
+        step(&mut input, None);
+    }
+    step(&mut input, Some(ButtonStateEvent::Press));
+    assert!(input.get_key(Keycode::W).just_pressed());
+    assert!(!input.double_tapped(Keycode::W, 5));
+    assert!(input.double_tapped(Keycode::W, 20));
+}
@@ -1,21 +1,29 @@
 use std::ops::{Add, Mul, Neg, Sub};
 use vek::num_traits::{One, Zero};
 
+pub mod ao;
 pub mod game;
 pub mod world;
 pub use game::Game;
 pub mod collision;
+pub mod coords;
+pub mod entity;
 pub mod input;
 pub mod light;
+pub mod net;
+pub mod physics;
+pub mod power;
 pub mod raycast;
+pub mod replay;
 
 mod blend;
 mod block;
 mod camera;
 mod camera_ext;
+mod events;
 pub use blend::{Blend, DiscreteBlend};
-pub use block::{Block, BlockType};
-pub use camera::Camera;
+pub use block::{Block, BlockShape, BlockType};
+pub use camera::{Angle, Camera, CameraPath, ViewBob};
 pub use camera_ext::CameraExt;
 
 pub trait Apply: Sized {
@@ -1,17 +1,144 @@
 use crate::{
     world::{face_neighbors, World},
-    Block,
+    Block, DiscreteBlend,
 };
 use itertools::Itertools;
 use vek::Vec3;
 
+/// How much block light is lost per block of distance from the source by default,
+/// lanterns reach about 14 blocks with this.
+pub const DEFAULT_LIGHT_FALLOFF: f32 = 16.0;
+
+/// How much sky light is lost for every solid block it passes through.
+pub const SKY_LIGHT_FALLOFF: u8 = 32;
+
+/// How many bits block light is kept with.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LightBits {
+    /// 0 to 255.
+    #[default]
+    Eight,
+    /// 16 levels like the classic lighting model, scaled back up to 0 to 255 so rendering doesn't change.
+    Four,
+}
+
+impl DiscreteBlend for LightBits {}
+
+impl LightBits {
+    /// Rounds `light` down to the closest value that can be kept with these bits.
+    /// Rounding down makes sure light still fades out with a falloff below one level.
+    pub fn quantize(self, light: u8) -> u8 {
+        match self {
+            LightBits::Eight => light,
+            LightBits::Four => unpack_level(pack_level(light)),
+        }
+    }
+}
+
+/// How bright a texture is drawn with `light`, from 0 for black to 1 for its own colors.
+/// Every renderer shades with this, `cube.frag` and `isometric_block.frag` do the same on the GPU.
+pub fn light_to_shade(light: u8) -> f32 {
+    light as f32 / 255.0
+}
+
+/// 4-bit level of `light`, 0 to 15.
+pub fn pack_level(light: u8) -> u8 {
+    light / 17
+}
+
+/// Inverse of `pack_level`, 15 is full light.
+pub fn unpack_level(level: u8) -> u8 {
+    level * 17
+}
+
+/// 4-bit light levels packed two per byte, the first of every pair in the low bits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackedLight {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl PackedLight {
+    pub fn from_lights(lights: impl IntoIterator<Item = u8>) -> Self {
+        let mut packed = PackedLight {
+            bytes: Vec::new(),
+            len: 0,
+        };
+        for light in lights {
+            let level = pack_level(light);
+            if packed.len.is_multiple_of(2) {
+                packed.bytes.push(level);
+            } else {
+                *packed.bytes.last_mut().unwrap() |= level << 4;
+            }
+            packed.len += 1;
+        }
+        packed
+    }
+
+    /// Light at `index` scaled back up to 0 to 255, None if it's out of bounds.
+    pub fn get(&self, index: usize) -> Option<u8> {
+        if index >= self.len {
+            return None;
+        }
+        let byte = self.bytes[index / 2];
+        let level = if index.is_multiple_of(2) {
+            byte & 0xF
+        } else {
+            byte >> 4
+        };
+        Some(unpack_level(level))
+    }
+
+    /// Replaces the light at `index`, rounded down to a whole level like `LightBits::Four` does.
+    pub fn set(&mut self, index: usize, light: u8) {
+        assert!(index < self.len);
+        let byte = &mut self.bytes[index / 2];
+        let level = pack_level(light);
+        *byte = if index.is_multiple_of(2) {
+            (*byte & 0xF0) | level
+        } else {
+            (*byte & 0x0F) | (level << 4)
+        };
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// Sky light of a block with `depth` solid blocks above it.
+pub fn sky_light_at_depth(depth: i32) -> u8 {
+    255_i32
+        .saturating_sub(depth.max(0).saturating_mul(SKY_LIGHT_FALLOFF as i32))
+        .max(0) as u8
+}
+
+/// Sky light of a block given the block above it, or None if it's above the loaded world.
+pub fn calculate_sky_light(above: Option<Block>) -> u8 {
+    match above {
+        None => 255,
+        Some(above) if above.ty.light_passing() => above.sky_light,
+        Some(above) => above.sky_light.saturating_sub(SKY_LIGHT_FALLOFF),
+    }
+}
+
 pub fn calculate_block_light(
     world: &World,
     position: Vec3<i32>,
     block: Block,
     source: Option<Vec3<i32>>,
+    falloff: f32,
 ) -> u8 {
-    if block.ty.light_passing() && block.open_to_sky {
+    if block.ty.light_passing() && block.sky_light == 255 {
         return 255;
     }
 
@@ -24,7 +151,7 @@ pub fn calculate_block_light(
             .filter_map(|(p, b)| b.map(|b| (p, b)))
             .collect_vec();
 
-        calculate_light((position, block), all_neighbors, source)
+        calculate_light((position, block), all_neighbors, source, falloff).max(block.sky_light)
     } else {
         0
     }
@@ -34,10 +161,11 @@ fn calculate_light(
     (position, block): (Vec3<i32>, Block),
     checks: impl IntoIterator<Item = (Vec3<i32>, Block)>,
     source: Option<Vec3<i32>>,
+    falloff: f32,
 ) -> u8 {
     checks
         .into_iter()
-        .map(|(p, b)| calculate_light_from((position, block), (p, b), source))
+        .map(|(p, b)| calculate_light_from((position, block), (p, b), source, falloff))
         .max()
         .unwrap_or(0)
 }
@@ -46,13 +174,21 @@ fn calculate_light_from(
     (position, block): (Vec3<i32>, Block),
     (p, b): (Vec3<i32>, Block),
     source: Option<Vec3<i32>>,
+    falloff: f32,
 ) -> u8 {
     let distance = position.as_::<f32>().distance(p.as_::<f32>());
     assert!(distance <= 2.0);
-    let new_light = b.light.checked_sub((16.0 * distance) as u8).unwrap_or(0);
+    let new_light = b.light.saturating_sub((falloff * distance) as u8);
     if new_light < block.light && Some(p) == source {
         return 0;
     }
 
     new_light
 }
+
+#[test]
+fn test_light_to_shade() {
+    assert_eq!(light_to_shade(0), 0.0);
+    assert!((light_to_shade(128) - 0.502).abs() < 1e-3);
+    assert_eq!(light_to_shade(255), 1.0);
+}
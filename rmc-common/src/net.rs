@@ -0,0 +1,375 @@
+use std::{collections::HashSet, sync::Arc};
+
+use itertools::Itertools;
+use vek::{Vec2, Vec3};
+
+use crate::{
+    camera::Angle,
+    game::{BlockOrItem, Hotbar, Item},
+    input::InputState,
+    world::CompressedChunk,
+    BlockType, Game,
+};
+
+/// Reads little endian values from the front of a byte slice,
+/// every read returns None once there aren't enough bytes left.
+pub struct ByteReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> ByteReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        ByteReader { bytes }
+    }
+
+    pub fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let (taken, rest) = self.bytes.split_at_checked(len)?;
+        self.bytes = rest;
+        Some(taken)
+    }
+
+    pub fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    pub fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_le_bytes(self.take(2)?.try_into().ok()?))
+    }
+
+    pub fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    pub fn i32(&mut self) -> Option<i32> {
+        Some(i32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    pub fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    pub fn f32(&mut self) -> Option<f32> {
+        Some(f32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    pub fn vec2_f32(&mut self) -> Option<Vec2<f32>> {
+        Some(Vec2::new(self.f32()?, self.f32()?))
+    }
+
+    pub fn vec3_f32(&mut self) -> Option<Vec3<f32>> {
+        Some(Vec3::new(self.f32()?, self.f32()?, self.f32()?))
+    }
+
+    pub fn vec3_i32(&mut self) -> Option<Vec3<i32>> {
+        Some(Vec3::new(self.i32()?, self.i32()?, self.i32()?))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+}
+
+/// The state of the game the server is authoritative over.
+/// Entities aren't included yet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub position: Vec3<f32>,
+    pub pitch: f32,
+    pub yaw: f32,
+    pub velocity: Vec3<f32>,
+    pub on_ground: bool,
+    pub flying: bool,
+    pub hotbar: Hotbar,
+
+    pub origin: Vec3<i32>,
+    /// Every loaded chunk by chunk coordinate.
+    pub chunks: Vec<(Vec3<i32>, CompressedChunk)>,
+}
+
+impl Snapshot {
+    pub fn capture(game: &Game) -> Self {
+        Snapshot {
+            position: game.camera.position,
+            pitch: game.camera.pitch.0,
+            yaw: game.camera.yaw.0,
+            velocity: game.velocity,
+            on_ground: game.on_ground,
+            flying: game.flying,
+            hotbar: game.hotbar,
+
+            origin: game.world.origin(),
+            chunks: game
+                .world
+                .loaded_chunks()
+                .filter_map(|chunk_coord| {
+                    Some((chunk_coord, game.world.chunk_at(chunk_coord)?.compress()))
+                })
+                .collect(),
+        }
+    }
+
+    /// Overwrites the state of `game` with this snapshot, chunks it doesn't have are unloaded.
+    pub fn apply(&self, game: &mut Game) {
+        game.camera.position = self.position;
+        game.camera.pitch = Angle(self.pitch);
+        game.camera.yaw = Angle(self.yaw);
+        game.velocity = self.velocity;
+        game.on_ground = self.on_ground;
+        game.flying = self.flying;
+        game.hotbar = self.hotbar;
+
+        game.world.set_origin(self.origin);
+        let kept = self
+            .chunks
+            .iter()
+            .map(|&(chunk_coord, _)| chunk_coord)
+            .collect::<HashSet<_>>();
+        for chunk_coord in game.world.loaded_chunks().collect_vec() {
+            if !kept.contains(&chunk_coord) {
+                game.world.unload(chunk_coord).unwrap();
+            }
+        }
+        for (chunk_coord, chunk) in &self.chunks {
+            game.world
+                .load(*chunk_coord, Arc::unwrap_or_clone(chunk.decompress()));
+        }
+    }
+
+    /// Layout (little endian):
+    /// - position: 3 * f32, pitch: f32, yaw: f32, velocity: 3 * f32
+    /// - on_ground: u8, flying: u8
+    /// - active hotbar slot: u8, slots: 9 * (tag: u8, id: u8), tag is 0 for empty, 1 for blocks, 2 for items
+    /// - origin: 3 * i32
+    /// - chunk count: u32, chunks: (chunk coordinate: 3 * i32, length: u32, `CompressedChunk` bytes)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for v in self.position.into_iter().chain([self.pitch, self.yaw]) {
+            bytes.extend(v.to_le_bytes());
+        }
+        for v in self.velocity {
+            bytes.extend(v.to_le_bytes());
+        }
+        bytes.extend([self.on_ground as u8, self.flying as u8]);
+
+        bytes.push(self.hotbar.active as u8);
+        for slot in self.hotbar.slots {
+            bytes.extend(match slot {
+                None => [0, 0],
+                Some(BlockOrItem::Block(ty)) => [1, ty as u8],
+                Some(BlockOrItem::Item(item)) => [2, item as u8],
+            });
+        }
+
+        for v in self.origin {
+            bytes.extend(v.to_le_bytes());
+        }
+        bytes.extend((self.chunks.len() as u32).to_le_bytes());
+        for (chunk_coord, chunk) in &self.chunks {
+            for v in chunk_coord {
+                bytes.extend(v.to_le_bytes());
+            }
+            let chunk_bytes = chunk.to_bytes();
+            bytes.extend((chunk_bytes.len() as u32).to_le_bytes());
+            bytes.extend(chunk_bytes);
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut reader = ByteReader::new(bytes);
+        let snapshot = Self::read(&mut reader)?;
+        reader.is_empty().then_some(snapshot)
+    }
+
+    fn read(reader: &mut ByteReader) -> Option<Self> {
+        let position = reader.vec3_f32()?;
+        let pitch = reader.f32()?;
+        let yaw = reader.f32()?;
+        let velocity = reader.vec3_f32()?;
+        let on_ground = reader.u8()? != 0;
+        let flying = reader.u8()? != 0;
+
+        let mut hotbar = Hotbar::new();
+        hotbar.active = reader.u8()? as usize;
+        if hotbar.active >= hotbar.slots.len() {
+            return None;
+        }
+        for slot in &mut hotbar.slots {
+            *slot = match (reader.u8()?, reader.u8()?) {
+                (0, _) => None,
+                (1, id) => Some(BlockOrItem::Block(BlockType::from_id(id)?)),
+                (2, id) => Some(BlockOrItem::Item(Item::from_id(id)?)),
+                _ => return None,
+            };
+        }
+
+        let origin = reader.vec3_i32()?;
+        let chunk_count = reader.u32()?;
+        let chunks = (0..chunk_count)
+            .map(|_| {
+                let chunk_coord = reader.vec3_i32()?;
+                let len = reader.u32()? as usize;
+                Some((chunk_coord, CompressedChunk::from_bytes(reader.take(len)?)?))
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Snapshot {
+            position,
+            pitch,
+            yaw,
+            velocity,
+            on_ground,
+            flying,
+            hotbar,
+            origin,
+            chunks,
+        })
+    }
+}
+
+/// Sent from the client to the server.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClientMessage {
+    /// The input for the next tick.
+    Input(InputState),
+}
+
+impl ClientMessage {
+    /// A tag byte followed by the message.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            ClientMessage::Input(input) => [0].into_iter().chain(input.to_bytes()).collect(),
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let (&tag, rest) = bytes.split_first()?;
+        match tag {
+            0 => Some(ClientMessage::Input(InputState::from_bytes(rest)?)),
+            _ => None,
+        }
+    }
+}
+
+/// Sent from the server to the client.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServerMessage {
+    /// The authoritative state after `tick` ticks.
+    Snapshot { tick: u64, snapshot: Box<Snapshot> },
+}
+
+impl ServerMessage {
+    /// A tag byte followed by the message.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            ServerMessage::Snapshot { tick, snapshot } => [0]
+                .into_iter()
+                .chain(tick.to_le_bytes())
+                .chain(snapshot.to_bytes())
+                .collect(),
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut reader = ByteReader::new(bytes);
+        match reader.u8()? {
+            0 => {
+                let tick = reader.u64()?;
+                let snapshot = Snapshot::read(&mut reader)?;
+                reader.is_empty().then_some(ServerMessage::Snapshot {
+                    tick,
+                    snapshot: Box::new(snapshot),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// An in-process server, messages still go through (de)serialization
+/// so it behaves the same as one on the other end of a socket.
+pub struct LoopbackServer {
+    pub game: Game,
+    pub tick: u64,
+}
+
+impl LoopbackServer {
+    pub fn new(game: Game) -> Self {
+        LoopbackServer { game, tick: 0 }
+    }
+
+    /// Handles an encoded `ClientMessage`, returning the encoded `ServerMessage` to reply with.
+    /// Returns None if the message couldn't be decoded.
+    pub fn receive(&mut self, bytes: &[u8]) -> Option<Vec<u8>> {
+        match ClientMessage::from_bytes(bytes)? {
+            ClientMessage::Input(input) => {
+                self.game.update(&input);
+                self.tick += 1;
+                let reply = ServerMessage::Snapshot {
+                    tick: self.tick,
+                    snapshot: Box::new(Snapshot::capture(&self.game)),
+                };
+                Some(reply.to_bytes())
+            }
+        }
+    }
+}
+
+#[test]
+fn test_snapshot_round_trip() {
+    use crate::{input::ButtonState, Block};
+    use sdl2::keyboard::Keycode;
+    use std::collections::HashMap;
+
+    let mut client = Game::new();
+    let mut server_game = client.clone();
+    // Only the server knows about this block until it sends a snapshot.
+    server_game.set_block(Vec3::new(3, 30, 3), Block::WOOD);
+    let mut server = LoopbackServer::new(server_game);
+
+    let world_hash = |game: &Game| {
+        let origin = game.world.origin();
+        game.world
+            .region_hash(origin - game.world.extents, origin + game.world.extents)
+    };
+    assert_ne!(world_hash(&client), world_hash(&server.game));
+
+    let input = InputState {
+        keys: HashMap::from([
+            (Keycode::W, ButtonState::KeptPressed),
+            (Keycode::D, ButtonState::JustPressed),
+        ]),
+        mouse_buttons: HashMap::new(),
+        mouse_delta: Vec2::new(0.5, -0.25),
+        scroll_delta: 1,
+        ..Default::default()
+    };
+    for expected_tick in 1..=2 {
+        let message = ClientMessage::Input(input.clone());
+        let bytes = message.to_bytes();
+        assert_eq!(ClientMessage::from_bytes(&bytes), Some(message));
+
+        let reply = server.receive(&bytes).unwrap();
+        let Some(ServerMessage::Snapshot { tick, snapshot }) = ServerMessage::from_bytes(&reply)
+        else {
+            panic!("couldn't decode the server's reply");
+        };
+        assert_eq!(tick, expected_tick);
+        assert_eq!(ServerMessage::from_bytes(&[reply, vec![0]].concat()), None);
+        assert_eq!(*snapshot, Snapshot::capture(&server.game));
+        snapshot.apply(&mut client);
+    }
+
+    assert_eq!(client.camera.position, server.game.camera.position);
+    assert_eq!(client.hotbar, server.game.hotbar);
+    assert_eq!(world_hash(&client), world_hash(&server.game));
+    assert_eq!(Snapshot::capture(&client), Snapshot::capture(&server.game));
+
+    // A chunk the server doesn't have loaded is unloaded on the client too.
+    let unloaded = server.game.chunk_coordinate();
+    assert!(client.world.is_chunk_loaded(unloaded));
+    server.game.world.unload(unloaded).unwrap();
+    Snapshot::capture(&server.game).apply(&mut client);
+    assert!(!client.world.is_chunk_loaded(unloaded));
+    assert_eq!(Snapshot::capture(&client), Snapshot::capture(&server.game));
+}
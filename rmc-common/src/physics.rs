@@ -0,0 +1,142 @@
+use vek::{Aabb, Extent3, Vec3};
+
+use crate::{game::GRAVITY, Blend, DiscreteBlend};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhysicsParams {
+    /// Acceleration of the player, it also decides which way is up for jumping and landing.
+    pub gravity: Vec3<f32>,
+    /// How much of `gravity` still pulls on the player while fully in a fluid.
+    pub fluid_gravity_scale: f32,
+    /// Acceleration pushing the player up while fully in a fluid.
+    pub buoyancy: f32,
+    /// Speed the player swims up at while holding jump in a fluid.
+    pub swim_up_speed: f32,
+    /// Fraction of the velocity lost per second while fully in a fluid.
+    pub fluid_drag: f32,
+}
+
+impl PhysicsParams {
+    /// Opposite of gravity, straight up if there is no gravity.
+    pub fn up(&self) -> Vec3<f32> {
+        (-self.gravity).try_normalized().unwrap_or(Vec3::unit_y())
+    }
+
+    /// Acceleration of the player with `submersion` of their box in fluids, from 0 for none to 1 for all of it.
+    /// Fluids lessen gravity and push up in proportion to how much of the player is in them.
+    pub fn acceleration(&self, submersion: f32) -> Vec3<f32> {
+        let gravity_scale = 1.0_f32.blend(&self.fluid_gravity_scale, submersion);
+        self.gravity * gravity_scale + self.up() * self.buoyancy * submersion
+    }
+
+    /// What the velocity is multiplied by over `delta` seconds with `submersion` of the player in fluids.
+    pub fn drag(&self, submersion: f32, delta: f32) -> f32 {
+        (1.0 - self.fluid_drag * submersion * delta).max(0.0)
+    }
+}
+
+impl Default for PhysicsParams {
+    fn default() -> Self {
+        PhysicsParams {
+            gravity: Vec3::new(0.0, -GRAVITY, 0.0),
+            fluid_gravity_scale: 0.5,
+            buoyancy: GRAVITY * 0.25,
+            swim_up_speed: 3.0,
+            fluid_drag: 2.0,
+        }
+    }
+}
+
+impl DiscreteBlend for PhysicsParams {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlyParams {
+    /// Fastest the player flies along the ground.
+    pub horizontal_speed: f32,
+    /// Fastest the player flies up and down.
+    pub vertical_speed: f32,
+    /// How quickly the flying velocity reaches what is held, infinite makes it instant.
+    pub acceleration: f32,
+}
+
+impl FlyParams {
+    /// Moves `velocity` towards `target` by at most `acceleration` over `delta` seconds.
+    pub fn accelerate(&self, velocity: Vec3<f32>, target: Vec3<f32>, delta: f32) -> Vec3<f32> {
+        let difference = target - velocity;
+        let step = self.acceleration * delta;
+        if difference.magnitude() <= step {
+            target
+        } else {
+            velocity + difference.normalized() * step
+        }
+    }
+}
+
+impl Default for FlyParams {
+    fn default() -> Self {
+        FlyParams {
+            horizontal_speed: 60.0,
+            vertical_speed: 10.0,
+            acceleration: f32::INFINITY,
+        }
+    }
+}
+
+impl DiscreteBlend for FlyParams {}
+
+/// The box covering everywhere `collider` passes through when moved by `velocity`,
+/// only what's inside of it can be hit by the move.
+pub fn broad_phase_box(collider: Aabb<f32>, velocity: Vec3<f32>) -> Aabb<f32> {
+    let position = collider
+        .min
+        .zip(velocity)
+        .map(|(p, v)| if v > 0.0 { p } else { p + v });
+    Aabb {
+        min: position,
+        max: position
+            + collider
+                .size()
+                .zip(Extent3::<f32>::from(velocity))
+                .map(|(s, v)| s + v.abs()),
+    }
+}
+
+#[test]
+fn test_broad_phase_box() {
+    let collider = Aabb {
+        min: Vec3::new(1.0, 2.0, 3.0),
+        max: Vec3::new(1.5, 4.0, 3.5),
+    };
+    for velocity in [
+        Vec3::zero(),
+        Vec3::new(2.0, 0.0, 0.0),
+        Vec3::new(0.0, -3.0, 0.0),
+        Vec3::new(-0.25, 0.5, 1.0),
+        Vec3::new(-1.0, -1.0, -1.0),
+    ] {
+        let broad_box = broad_phase_box(collider, velocity);
+        let end = Aabb {
+            min: collider.min + velocity,
+            max: collider.max + velocity,
+        };
+        assert!(broad_box.contains_aabb(collider), "{velocity}");
+        assert!(broad_box.contains_aabb(end), "{velocity}");
+        // Nothing more than the start and end is covered.
+        assert_eq!(broad_box, collider.union(end), "{velocity}");
+    }
+}
+
+#[test]
+fn test_fly_accelerate() {
+    let target = Vec3::new(3.0, 0.0, 4.0);
+    let instant = FlyParams::default();
+    assert_eq!(instant.accelerate(Vec3::zero(), target, 0.05), target);
+
+    let smooth = FlyParams {
+        acceleration: 20.0,
+        ..FlyParams::default()
+    };
+    let velocity = smooth.accelerate(Vec3::zero(), target, 0.05);
+    assert!((velocity.magnitude() - 1.0).abs() < 1e-5, "{velocity}");
+    assert_eq!(smooth.accelerate(velocity, target, 1.0), target);
+}
@@ -0,0 +1,26 @@
+use crate::{
+    world::{face_neighbors, World},
+    Block, BlockType,
+};
+use vek::Vec3;
+
+/// Strongest signal a block can carry, wires lose one for every block away from the source.
+pub const MAX_POWER: u8 = 15;
+
+/// Signal of `block` at `position`, recomputed like block light whenever a neighbor changes.
+pub fn calculate_power(world: &World, position: Vec3<i32>, block: Block) -> u8 {
+    if let Some(emission) = block.ty.power_emission() {
+        return emission;
+    }
+    if block.ty != BlockType::Wire {
+        return 0;
+    }
+
+    face_neighbors(position)
+        .into_iter()
+        .filter_map(|position| world.get_block(position))
+        .filter(|neighbor| neighbor.ty == BlockType::Wire || neighbor.ty.power_emission().is_some())
+        .map(|neighbor| neighbor.power.saturating_sub(1))
+        .max()
+        .unwrap_or(0)
+}
@@ -1,8 +1,8 @@
 use std::cmp::Ordering;
 
-use vek::Vec3;
+use vek::{Aabb, Vec3};
 
-use crate::{Block, BlockType, DiscreteBlend};
+use crate::{Block, BlockShape, BlockType, DiscreteBlend};
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub struct RaycastOutput {
@@ -12,6 +12,12 @@ pub struct RaycastOutput {
 
 impl DiscreteBlend for RaycastOutput {}
 
+/// Most voxels a ray can step through within `radius`.
+/// Every step crosses into the next voxel along a single axis, so it's bounded on all 3 axes.
+pub fn max_raycast_steps(radius: f32, voxel_size: f32) -> usize {
+    ((radius / voxel_size).ceil().max(0.0) as usize + 1) * 3
+}
+
 pub fn raycast_generalized<F: FnMut(Vec3<i32>) -> bool>(
     pos: Vec3<f32>,
     dir: Vec3<f32>,
@@ -19,7 +25,9 @@ pub fn raycast_generalized<F: FnMut(Vec3<i32>) -> bool>(
     voxel_size: f32,
     mut has_voxel: F,
 ) -> Option<RaycastOutput> {
-    if dir.normalized().magnitude() == 0.0 {
+    // Nothing can be hit going nowhere, and NaNs would keep the loop below from ever stepping.
+    let finite = pos.into_iter().chain(dir).all(f32::is_finite) && radius.is_finite();
+    if !finite || dir.magnitude_squared() == 0.0 {
         return None;
     }
 
@@ -51,7 +59,11 @@ pub fn raycast_generalized<F: FnMut(Vec3<i32>) -> bool>(
         }
     });
 
-    while pos.distance(grid_pos.map(|e| e as f32)) <= radius {
+    for _ in 0..max_raycast_steps(radius, voxel_size) {
+        if pos.distance(grid_pos.map(|e| e as f32)) > radius {
+            break;
+        }
+
         let min_axis = t_max
             .into_iter()
             .enumerate()
@@ -90,17 +102,78 @@ pub fn raycast_candidates(pos: Vec3<f32>, dir: Vec3<f32>, radius: f32) -> Vec<Ve
     blocks
 }
 
+/// Ray-box intersection, returning the normal of the face the ray enters through.
+/// The normal is zero if the ray starts inside the box.
+fn raycast_aabb(pos: Vec3<f32>, dir: Vec3<f32>, aabb: Aabb<f32>) -> Option<Vec3<i8>> {
+    let mut t_enter = f32::NEG_INFINITY;
+    let mut t_exit = f32::INFINITY;
+    let mut enter_axis = 0;
+
+    for axis in 0..3 {
+        if dir[axis] == 0.0 {
+            if pos[axis] < aabb.min[axis] || pos[axis] > aabb.max[axis] {
+                return None;
+            }
+            continue;
+        }
+
+        let t_min = (aabb.min[axis] - pos[axis]) / dir[axis];
+        let t_max = (aabb.max[axis] - pos[axis]) / dir[axis];
+        let (near, far) = if t_min < t_max {
+            (t_min, t_max)
+        } else {
+            (t_max, t_min)
+        };
+
+        if near > t_enter {
+            t_enter = near;
+            enter_axis = axis;
+        }
+        t_exit = t_exit.min(far);
+    }
+
+    if t_enter > t_exit || t_exit < 0.0 {
+        return None;
+    }
+
+    let mut normal = Vec3::zero();
+    if t_enter >= 0.0 {
+        normal[enter_axis] = -dir[enter_axis].signum() as i8;
+    }
+    Some(normal)
+}
+
+/// Raycast against the blocks returned by `get_block`.
+/// Blocks that aren't full cubes are only hit if the ray touches their actual shape,
+/// in which case the normal is of the face that was hit.
 pub fn raycast(
     pos: Vec3<f32>,
     dir: Vec3<f32>,
     radius: f32,
     get_block: impl Fn(Vec3<i32>) -> Option<Block>,
 ) -> Option<RaycastOutput> {
-    raycast_generalized(pos, dir, radius, 1.0, |grid_pos| {
-        get_block(grid_pos)
-            .map(|b| b.ty != BlockType::Air)
-            .unwrap_or(false)
-    })
+    let mut shape_normal = None;
+    let mut output = raycast_generalized(pos, dir, radius, 1.0, |grid_pos| {
+        let Some(block) = get_block(grid_pos).filter(|b| b.ty != BlockType::Air) else {
+            return false;
+        };
+
+        let shape = block.ty.shape();
+        if shape == BlockShape::Full {
+            return true;
+        }
+
+        let Some(normal) = raycast_aabb(pos, dir, block.aabb(grid_pos)) else {
+            return false;
+        };
+        shape_normal = Some(normal);
+        true
+    })?;
+
+    if let Some(normal) = shape_normal {
+        output.normal = normal;
+    }
+    Some(output)
 }
 
 #[cfg(test)]
@@ -254,6 +327,94 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_raycast_slab() {
+        let raycast_with = |pos: Vec3<f32>, dir: Vec3<f32>, block: Block| {
+            raycast(pos, dir.normalized(), 16.0, |p| {
+                if p == Vec3::new(9, 8, 0) {
+                    Some(block)
+                } else if p == Vec3::new(11, 8, 0) {
+                    Some(Block::TEST)
+                } else {
+                    Some(Block::AIR)
+                }
+            })
+        };
+
+        // From above at an angle, the ray enters the block's cell through the side
+        // but only touches the slab once it reaches its top.
+        let from_above = (Vec3::new(8.5, 9.2, 0.5), Vec3::new(1.0, -0.5, 0.0));
+        assert_eq!(
+            raycast_with(from_above.0, from_above.1, Block::TEST),
+            Some(RaycastOutput {
+                position: Vec3::new(9, 8, 0),
+                normal: Vec3::new(-1, 0, 0),
+            })
+        );
+        assert_eq!(
+            raycast_with(from_above.0, from_above.1, Block::STONE_SLAB),
+            Some(RaycastOutput {
+                position: Vec3::new(9, 8, 0),
+                normal: Vec3::new(0, 1, 0),
+            })
+        );
+
+        // From the side above the slab's height, the ray passes over it.
+        let from_side_high = (Vec3::new(8.5, 8.75, 0.5), Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(
+            raycast_with(from_side_high.0, from_side_high.1, Block::TEST),
+            Some(RaycastOutput {
+                position: Vec3::new(9, 8, 0),
+                normal: Vec3::new(-1, 0, 0),
+            })
+        );
+        assert_eq!(
+            raycast_with(from_side_high.0, from_side_high.1, Block::STONE_SLAB),
+            Some(RaycastOutput {
+                position: Vec3::new(11, 8, 0),
+                normal: Vec3::new(-1, 0, 0),
+            })
+        );
+
+        // From the side below the slab's height, it behaves like a full block.
+        let from_side_low = (Vec3::new(8.5, 8.25, 0.5), Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(
+            raycast_with(from_side_low.0, from_side_low.1, Block::STONE_SLAB),
+            raycast_with(from_side_low.0, from_side_low.1, Block::TEST),
+        );
+    }
+
+    #[test]
+    fn test_raycast_degenerate_direction() {
+        let get_block = |_| Some(Block::TEST);
+        let pos = Vec3::new(0.5, 0.5, 0.5);
+
+        assert_eq!(raycast(pos, Vec3::zero(), 16.0, get_block), None);
+        assert_eq!(
+            raycast(pos, Vec3::broadcast(f32::NAN), 16.0, get_block),
+            None
+        );
+        assert_eq!(
+            raycast(pos, Vec3::new(0.0, f32::NAN, 1.0), 16.0, get_block),
+            None
+        );
+        assert_eq!(
+            raycast(pos, Vec3::new(f32::INFINITY, 0.0, 0.0), 16.0, get_block),
+            None
+        );
+
+        // A normal direction still hits.
+        let wall = |p: Vec3<i32>| Some(if p.x >= 3 { Block::TEST } else { Block::AIR });
+        assert_eq!(
+            raycast(pos, Vec3::unit_x(), 16.0, wall),
+            Some(RaycastOutput {
+                position: Vec3::new(3, 0, 0),
+                normal: Vec3::new(-1, 0, 0),
+            })
+        );
+        assert_eq!(raycast(pos, Vec3::unit_x(), 2.0, wall), None);
+    }
+
     #[test]
     fn test_raycast() {
         let mut blocks: Array3<Block> = Array3::default((16, 16, 16));
@@ -0,0 +1,111 @@
+use crate::{input::InputState, net::ByteReader, Game};
+
+/// The inputs of every tick of a session, replaying them into a game with the same seed reproduces it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    pub seed: u32,
+    pub inputs: Vec<InputState>,
+}
+
+impl Replay {
+    pub fn new(seed: u32) -> Self {
+        Replay {
+            seed,
+            inputs: Vec::new(),
+        }
+    }
+
+    /// Records the input of the next tick.
+    pub fn record(&mut self, input: &InputState) {
+        self.inputs.push(input.clone());
+    }
+
+    /// Updates `game` once for every recorded tick.
+    pub fn apply(&self, game: &mut Game) {
+        for input in &self.inputs {
+            game.update(input);
+        }
+    }
+
+    /// Replays the whole session into a new game.
+    pub fn play(&self) -> Game {
+        let mut game = Game::new_with_seed(self.seed);
+        self.apply(&mut game);
+        game
+    }
+
+    /// Layout (little endian):
+    /// - seed: u32
+    /// - tick count: u32, inputs: (length: u32, `InputState` bytes)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend(self.seed.to_le_bytes());
+        bytes.extend((self.inputs.len() as u32).to_le_bytes());
+        for input in &self.inputs {
+            let input_bytes = input.to_bytes();
+            bytes.extend((input_bytes.len() as u32).to_le_bytes());
+            bytes.extend(input_bytes);
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut reader = ByteReader::new(bytes);
+        let seed = reader.u32()?;
+        let tick_count = reader.u32()?;
+        let inputs = (0..tick_count)
+            .map(|_| {
+                let len = reader.u32()? as usize;
+                InputState::from_bytes(reader.take(len)?)
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        reader.is_empty().then_some(Replay { seed, inputs })
+    }
+}
+
+#[test]
+fn test_replay() {
+    use crate::input::ButtonState;
+    use sdl2::{keyboard::Keycode, mouse::MouseButton};
+    use std::collections::HashMap;
+    use vek::Vec2;
+
+    let input = |keys: &[Keycode], mouse_delta: Vec2<f32>| InputState {
+        keys: keys
+            .iter()
+            .map(|&key| (key, ButtonState::KeptPressed))
+            .collect(),
+        mouse_buttons: HashMap::from([(MouseButton::Left, ButtonState::KeptReleased)]),
+        mouse_delta,
+        scroll_delta: 0,
+        ..Default::default()
+    };
+
+    // Insertion order doesn't change the encoding.
+    let a = input(&[Keycode::W, Keycode::A, Keycode::Space], Vec2::zero());
+    let b = input(&[Keycode::Space, Keycode::W, Keycode::A], Vec2::zero());
+    assert_eq!(a.to_bytes(), b.to_bytes());
+    assert_eq!(InputState::from_bytes(&a.to_bytes()), Some(a));
+
+    let seed = 4321;
+    let mut game = Game::new_with_seed(seed);
+    let mut replay = Replay::new(seed);
+    for tick in 0..40 {
+        let input = match tick {
+            0..=9 => input(&[], Vec2::zero()),
+            10..=24 => input(&[Keycode::W], Vec2::new(0.05, 0.0)),
+            25 => input(&[Keycode::W, Keycode::Space], Vec2::zero()),
+            _ => input(&[Keycode::D], Vec2::new(0.0, 0.02)),
+        };
+        replay.record(&input);
+        game.update(&input);
+    }
+
+    let replay = Replay::from_bytes(&replay.to_bytes()).unwrap();
+    assert_eq!(replay.inputs.len(), 40);
+    let replayed = replay.play();
+    assert_eq!(replayed.camera.position, game.camera.position);
+    assert_eq!(replayed.camera.yaw, game.camera.yaw);
+    assert_eq!(replayed.camera.pitch, game.camera.pitch);
+}
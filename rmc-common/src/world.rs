@@ -1,16 +1,98 @@
-use std::{mem, sync::Arc};
+use std::{
+    collections::HashMap,
+    hash::{BuildHasherDefault, Hash, Hasher},
+    mem,
+    sync::{Arc, OnceLock},
+};
 
-use itertools::Itertools;
-use ndarray::Array3;
-use vek::{Vec2, Vec3};
+use itertools::{iproduct, Itertools};
+use ndarray::{Array2, Array3};
+use vek::{Aabb, Vec2, Vec3};
 
-use crate::{game::TerrainSampler, Block, BlockType, DiscreteBlend};
+use crate::{
+    coords,
+    events::event,
+    game::TerrainSampler,
+    light::{sky_light_at_depth, LightBits, PackedLight},
+    raycast::{raycast, RaycastOutput},
+    Block, BlockShape, BlockType, DiscreteBlend,
+};
 
 pub const CHUNK_SIZE: usize = 16;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Chunk {
-    pub blocks: Array3<Block>,
+    /// Changing block types directly here doesn't update the air/solid counts, use `set_block`.
+    blocks: Array3<StoredBlock>,
+    /// Block light of `blocks`, in `Array3` iteration order.
+    light: ChunkLight,
+
+    air_count: u16,
+    solid_count: u16,
+}
+
+/// A block the way chunks keep it, without its light, which is kept in `ChunkLight` instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct StoredBlock {
+    ty: BlockType,
+    open_to_sky: bool,
+    sky_light: u8,
+    occluded: bool,
+    orientation: u8,
+    power: u8,
+}
+
+impl StoredBlock {
+    fn new(block: Block) -> Self {
+        StoredBlock {
+            ty: block.ty,
+            open_to_sky: block.open_to_sky,
+            sky_light: block.sky_light,
+            occluded: block.occluded,
+            orientation: block.orientation,
+            power: block.power,
+        }
+    }
+
+    fn with_light(self, light: u8) -> Block {
+        Block {
+            ty: self.ty,
+            light,
+            open_to_sky: self.open_to_sky,
+            sky_light: self.sky_light,
+            occluded: self.occluded,
+            orientation: self.orientation,
+            power: self.power,
+        }
+    }
+}
+
+/// Block light of a chunk, a byte per block or two blocks per byte depending on its `LightBits`.
+#[derive(Debug, Clone, PartialEq)]
+enum ChunkLight {
+    Eight(Vec<u8>),
+    Four(PackedLight),
+}
+
+impl ChunkLight {
+    fn get(&self, index: usize) -> u8 {
+        match self {
+            ChunkLight::Eight(lights) => lights[index],
+            ChunkLight::Four(packed) => packed.get(index).unwrap(),
+        }
+    }
+
+    fn set(&mut self, index: usize, light: u8) {
+        match self {
+            ChunkLight::Eight(lights) => lights[index] = light,
+            ChunkLight::Four(packed) => packed.set(index, light),
+        }
+    }
+}
+
+/// Index of `local` in `Array3` iteration order.
+fn local_to_index(local: Vec3<usize>) -> usize {
+    (local.x * CHUNK_SIZE + local.y) * CHUNK_SIZE + local.z
 }
 
 impl Chunk {
@@ -18,12 +100,118 @@ impl Chunk {
         Chunk::default()
     }
 
+    /// The chunk keeps a byte of light per block, see `set_light_bits`.
     pub fn from_blocks(blocks: Array3<Block>) -> Self {
-        Chunk { blocks }
+        let air_count = blocks.iter().filter(|b| b.ty == BlockType::Air).count() as u16;
+        let solid_count = blocks.iter().filter(|b| !b.ty.light_passing()).count() as u16;
+        Chunk {
+            light: ChunkLight::Eight(blocks.iter().map(|block| block.light).collect()),
+            blocks: blocks.map(|&block| StoredBlock::new(block)),
+            air_count,
+            solid_count,
+        }
     }
 
     pub fn set_block(&mut self, local: Vec3<i32>, block: Block) {
-        self.blocks[local.as_().into_tuple()] = block;
+        let local = local.as_::<usize>();
+        let target = &mut self.blocks[local.into_tuple()];
+        self.air_count = self.air_count - (target.ty == BlockType::Air) as u16
+            + (block.ty == BlockType::Air) as u16;
+        self.solid_count =
+            self.solid_count - !target.ty.light_passing() as u16 + !block.ty.light_passing() as u16;
+        *target = StoredBlock::new(block);
+        self.light.set(local_to_index(local), block.light);
+    }
+
+    /// Every block of the chunk with its local position, in `Array3` iteration order.
+    pub fn iter_blocks(&self) -> impl Iterator<Item = (Vec3<i32>, Block)> + '_ {
+        self.blocks.iter().enumerate().map(|(index, block)| {
+            let local = Vec3::new(
+                index / (CHUNK_SIZE * CHUNK_SIZE),
+                index / CHUNK_SIZE % CHUNK_SIZE,
+                index % CHUNK_SIZE,
+            );
+            (local.as_(), block.with_light(self.light.get(index)))
+        })
+    }
+
+    /// Every block of the chunk with its world position, if this is the chunk at `chunk_coord`.
+    pub fn iter_world_blocks(
+        &self,
+        chunk_coord: Vec3<i32>,
+    ) -> impl Iterator<Item = (Vec3<i32>, Block)> + '_ {
+        self.iter_blocks()
+            .map(move |(local, block)| (coords::chunk_to_world(chunk_coord, local), block))
+    }
+
+    /// Block at `local`, or None if it's outside of the chunk.
+    pub fn get_local(&self, local: Vec3<i32>) -> Option<Block> {
+        if local.into_iter().any(|e| e < 0 || e >= CHUNK_SIZE as i32) {
+            return None;
+        }
+        let local = local.as_::<usize>();
+        let light = self.light.get(local_to_index(local));
+        Some(self.blocks[local.into_tuple()].with_light(light))
+    }
+
+    /// Whether every block in the chunk is air.
+    pub fn is_all_air(&self) -> bool {
+        self.air_count as usize == self.blocks.len()
+    }
+
+    /// Whether every block in the chunk is solid and blocks light, so only its outside can be seen.
+    pub fn is_all_solid(&self) -> bool {
+        self.solid_count as usize == self.blocks.len()
+    }
+
+    /// How many bits the chunk keeps the light of each block with.
+    pub fn light_bits(&self) -> LightBits {
+        match self.light {
+            ChunkLight::Eight(_) => LightBits::Eight,
+            ChunkLight::Four(_) => LightBits::Four,
+        }
+    }
+
+    /// Switches how the light of each block is kept, going to `LightBits::Four` rounds it down to whole levels
+    /// and halves the memory it takes.
+    pub fn set_light_bits(&mut self, bits: LightBits) {
+        if bits == self.light_bits() {
+            return;
+        }
+        let lights = (0..self.blocks.len()).map(|index| self.light.get(index));
+        self.light = match bits {
+            LightBits::Eight => ChunkLight::Eight(lights.collect()),
+            LightBits::Four => ChunkLight::Four(PackedLight::from_lights(lights)),
+        };
+    }
+
+    /// Sets the block light of every block to 0.
+    pub fn clear_light(&mut self) {
+        for index in 0..self.blocks.len() {
+            self.light.set(index, 0);
+        }
+    }
+
+    /// Block light of the chunk as 4-bit levels, in `Array3` iteration order.
+    pub fn pack_light(&self) -> PackedLight {
+        PackedLight::from_lights((0..self.blocks.len()).map(|index| self.light.get(index)))
+    }
+
+    /// Replaces the block light of the chunk with `packed`, as created by `pack_light`.
+    pub fn unpack_light(&mut self, packed: &PackedLight) {
+        assert_eq!(packed.len(), self.blocks.len());
+        for index in 0..packed.len() {
+            self.light.set(index, packed.get(index).unwrap());
+        }
+    }
+
+    /// Number of blocks of each type in the chunk, types that don't appear are left out.
+    pub fn count_by_type(&self) -> HashMap<BlockType, u32> {
+        let mut counts = HashMap::new();
+        for block in self.blocks.iter() {
+            *counts.entry(block.ty).or_insert(0) += 1;
+        }
+        counts
     }
 }
 
@@ -31,112 +219,294 @@ impl Default for Chunk {
     fn default() -> Self {
         Chunk::from_blocks(Array3::from_elem(
             (CHUNK_SIZE, CHUNK_SIZE, CHUNK_SIZE),
-            Block {
-                open_to_sky: true,
-                ..Block::AIR
-            },
+            Block::AIR.with_open_to_sky(true).with_sky_light(255),
         ))
     }
 }
 
 pub type ArcChunk = Arc<Chunk>;
 
+impl Chunk {
+    pub fn compress(&self) -> CompressedChunk {
+        CompressedChunk {
+            bytes: self.to_bytes().into(),
+            decompressed: OnceLock::new(),
+        }
+    }
+
+    /// Palette + run-length encoding, cheap to keep around for uniform chunks.
+    ///
+    /// Layout (little endian):
+    /// - palette length: u16
+    /// - palette entries: (type: u8, light: u8, open_to_sky: u8, sky_light: u8, occluded: u8, orientation: u8, power: u8)
+    /// - runs in `Array3` iteration order until the end: (palette index: u16, run length: u16)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut palette: Vec<Block> = Vec::new();
+        let mut runs: Vec<(u16, u16)> = Vec::new();
+        for (_, block) in self.iter_blocks() {
+            let palette_index = match palette.iter().position(|b| *b == block) {
+                Some(i) => i,
+                None => {
+                    palette.push(block);
+                    palette.len() - 1
+                }
+            } as u16;
+
+            match runs.last_mut() {
+                Some((i, length)) if *i == palette_index => *length += 1,
+                _ => runs.push((palette_index, 1)),
+            }
+        }
+
+        let mut bytes = Vec::with_capacity(2 + palette.len() * 7 + runs.len() * 4);
+        bytes.extend((palette.len() as u16).to_le_bytes());
+        for block in &palette {
+            bytes.extend([
+                block.ty as u8,
+                block.light,
+                block.open_to_sky as u8,
+                block.sky_light,
+                block.occluded as u8,
+                block.orientation,
+                block.power,
+            ]);
+        }
+        for &(palette_index, length) in &runs {
+            bytes.extend(palette_index.to_le_bytes());
+            bytes.extend(length.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let palette_len = u16::from_le_bytes(bytes.get(0..2)?.try_into().ok()?) as usize;
+        let (palette_bytes, run_bytes) = bytes[2..].split_at_checked(palette_len * 7)?;
+
+        let palette = palette_bytes
+            .chunks_exact(7)
+            .map(|b| {
+                Some(Block {
+                    ty: BlockType::from_id(b[0])?,
+                    light: b[1],
+                    open_to_sky: b[2] != 0,
+                    sky_light: b[3],
+                    occluded: b[4] != 0,
+                    orientation: b[5],
+                    power: b[6],
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        if run_bytes.len() % 4 != 0 {
+            return None;
+        }
+        let mut blocks = Vec::with_capacity(CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE);
+        for b in run_bytes.chunks_exact(4) {
+            let palette_index = u16::from_le_bytes([b[0], b[1]]) as usize;
+            let length = u16::from_le_bytes([b[2], b[3]]) as usize;
+            blocks.extend(std::iter::repeat_n(*palette.get(palette_index)?, length));
+            if blocks.len() > CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE {
+                return None;
+            }
+        }
+
+        Some(Chunk::from_blocks(
+            Array3::from_shape_vec((CHUNK_SIZE, CHUNK_SIZE, CHUNK_SIZE), blocks).ok()?,
+        ))
+    }
+}
+
+/// A chunk kept as its `Chunk::to_bytes` encoding.
+/// It's only decompressed the first time it's needed, after that the decompressed chunk is reused.
+#[derive(Debug, Clone)]
+pub struct CompressedChunk {
+    bytes: Arc<[u8]>,
+    decompressed: OnceLock<ArcChunk>,
+}
+
+impl CompressedChunk {
+    pub fn decompress(&self) -> ArcChunk {
+        self.decompressed
+            .get_or_init(|| Arc::new(Chunk::from_bytes(&self.bytes).unwrap()))
+            .clone()
+    }
+
+    /// Same bytes, without the decompressed chunk if there was one.
+    fn without_cache(&self) -> Self {
+        CompressedChunk {
+            bytes: self.bytes.clone(),
+            decompressed: OnceLock::new(),
+        }
+    }
+
+    pub fn to_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let chunk = Chunk::from_bytes(bytes)?;
+        Some(CompressedChunk {
+            bytes: bytes.into(),
+            decompressed: OnceLock::from(Arc::new(chunk)),
+        })
+    }
+}
+
+impl PartialEq for CompressedChunk {
+    fn eq(&self, other: &Self) -> bool {
+        self.bytes == other.bytes
+    }
+}
+
+/// A loaded chunk the way `World` stores it.
+#[derive(Debug, Clone)]
+pub enum StoredChunk {
+    Full(ArcChunk),
+    /// Distant chunks are compressed to save memory, see `World::compress_distant`.
+    Compressed(Arc<CompressedChunk>),
+}
+
+impl StoredChunk {
+    /// Compressed chunks are decompressed once and then kept until the chunk is compressed again.
+    pub fn chunk(&self) -> ArcChunk {
+        match self {
+            StoredChunk::Full(chunk) => chunk.clone(),
+            StoredChunk::Compressed(compressed) => compressed.decompress(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum WorldError {
     ChunkNotLoaded,
+    /// The chunk is outside of the area the world keeps chunks for.
+    OutOfRange,
+}
+
+/// What `World::block_state` found at a position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockState {
+    /// The position is in a chunk that isn't loaded, so what's there is unknown.
+    Unloaded,
+    Air,
+    /// Any block that isn't air, including ones that let light through.
+    Solid(Block),
 }
 
+/// Cloning a world only copies the grid of chunk pointers, the chunks themselves are shared
+/// until one of the worlds edits them. That makes `clone` followed by `set_block` the cheap way
+/// to try out edits, only the chunks that were edited get copied.
 #[derive(Clone)]
 pub struct World {
     origin: Vec3<i32>,
 
-    // None means unloaded
-    pub chunks: Array3<Option<ArcChunk>>,
+    // None means unloaded, in the same order as iterating an `Array3` of `shape` would be.
+    chunks: Box<[Option<StoredChunk>]>,
 
-    // Half width to the sides, excluding middle. i.e (chunks.dim() - 1) / 2
-    pub extents: Vec3<i32>,
+    // Chunks kept when the origin moves away from them, with the chunk while it's outside of `chunks`.
+    pinned: HashMap<Vec3<i32>, Option<StoredChunk>, ChunkHasher>,
 
-    pub shape: (usize, usize, usize),
+    // Half width to the sides, excluding middle. i.e (shape - 1) / 2
+    pub extents: Vec3<i32>,
 }
 
 impl World {
     pub fn new(origin: Vec3<i32>) -> Self {
         let extents = Vec3::new(6, 2, 6);
-        let shape = (extents * 2 + Vec3::one()).as_().into_tuple();
         World {
-            chunks: Array3::default(shape),
+            chunks: (0..shape(extents).product()).map(|_| None).collect(),
+            pinned: HashMap::default(),
             extents,
             origin,
-            shape,
         }
     }
 
-    pub fn world_to_chunk(&self, position: Vec3<i32>) -> Vec3<i32> {
-        position.map(|e| (e as f32 / CHUNK_SIZE as f32).floor() as i32)
+    /// How many chunks the world keeps along each axis.
+    pub fn shape(&self) -> (usize, usize, usize) {
+        shape(self.extents).into_tuple()
     }
 
-    pub fn chunk_to_index(&self, chunk_coord: Vec3<i32>) -> Option<Vec3<usize>> {
-        let offset = chunk_coord - self.origin;
+    /// Where the chunk at `index` is kept in `chunks`.
+    fn slot(&self, index: Vec3<usize>) -> usize {
+        index_to_slot(index, self.extents)
+    }
 
-        if offset
-            .zip(self.extents)
-            .into_iter()
-            .any(|(o, e)| o.abs() > e)
-        {
-            return None;
-        }
+    pub fn world_to_chunk(&self, position: Vec3<i32>) -> Vec3<i32> {
+        coords::world_to_chunk(position)
+    }
 
-        let index = offset + self.extents;
-        assert!(index.into_iter().all(|e| e >= 0));
-        Some((index).as_())
+    pub fn chunk_to_index(&self, chunk_coord: Vec3<i32>) -> Option<Vec3<usize>> {
+        coords::chunk_to_index(chunk_coord, self.origin, self.extents)
     }
 
     pub fn origin(&self) -> Vec3<i32> {
         self.origin
     }
 
+    /// Moves the window of chunks, the ones that leave it are unloaded unless they're pinned, see `pin`.
     pub fn set_origin(&mut self, new_origin: Vec3<i32>) {
         let diff = new_origin - self.origin;
 
-        // Let's get the world shifting :)
-        let mut chunks = Array3::default(self.chunks.dim());
-        for (index, chunk) in self
-            .chunks
-            .indexed_iter()
-            .filter_map(|(idx, chunk)| chunk.as_ref().map(|chunk| (idx, chunk)))
-        {
-            let index = Vec3::<usize>::from(index);
-            let (Some(x), Some(y), Some(z)) = index
-                .zip(diff)
-                .map(|(i, o)| i.checked_add_signed(-o as isize))
-                .into_tuple()
-            else {
+        // Pinned chunks leaving the window are set aside before the shift drops them.
+        for (&chunk_coord, parked) in self.pinned.iter_mut() {
+            if coords::chunk_to_index(chunk_coord, new_origin, self.extents).is_some() {
+                continue;
+            }
+            let Some(index) = coords::chunk_to_index(chunk_coord, self.origin, self.extents) else {
                 continue;
             };
-            let new_index = Vec3::new(x, y, z);
+            *parked = self.chunks[index_to_slot(index, self.extents)].take();
+        }
 
-            // Skip out-of-bounds
-            if new_index
-                .zip(Vec3::<usize>::from(self.chunks.dim()))
-                .iter()
-                .any(|&(i, e)| i >= e)
-            {
+        self.chunks = shift_chunks(&mut self.chunks, self.extents, diff);
+        event!(info, "origin moved from {} to {new_origin}", self.origin);
+        self.origin = new_origin;
+
+        for (&chunk_coord, parked) in self.pinned.iter_mut() {
+            let Some(index) = coords::chunk_to_index(chunk_coord, self.origin, self.extents) else {
                 continue;
+            };
+            if let Some(chunk) = parked.take() {
+                self.chunks[index_to_slot(index, self.extents)] = Some(chunk);
             }
-
-            chunks[new_index.into_tuple()] = Some(chunk.clone());
         }
+    }
 
-        self.chunks = chunks;
-        self.origin = new_origin;
+    /// Keeps the chunk at `chunk_coord` when the origin moves away from it, e.g so edits around spawn aren't lost.
+    /// It's put back once the origin comes back, until then it's only reachable through `pinned_chunks`.
+    pub fn pin(&mut self, chunk_coord: Vec3<i32>) {
+        self.pinned.entry(chunk_coord).or_insert(None);
+    }
+
+    /// Lets the chunk be unloaded by origin moves again, it's dropped if it's currently outside of the world.
+    pub fn unpin(&mut self, chunk_coord: Vec3<i32>) {
+        self.pinned.remove(&chunk_coord);
+    }
+
+    pub fn is_pinned(&self, chunk_coord: Vec3<i32>) -> bool {
+        self.pinned.contains_key(&chunk_coord)
     }
 
-    pub fn unload(&mut self, chunk_coordinate: Vec3<i32>) {
+    /// Every loaded pinned chunk, including the ones outside of the world.
+    pub fn pinned_chunks(&self) -> impl Iterator<Item = (Vec3<i32>, ArcChunk)> + '_ {
+        self.pinned.iter().filter_map(|(&chunk_coord, parked)| {
+            let chunk = match parked {
+                Some(parked) => parked.chunk(),
+                None => self.chunk_at(chunk_coord)?,
+            };
+            Some((chunk_coord, chunk))
+        })
+    }
+
+    /// Returns the chunk that was unloaded, None if it was already unloaded.
+    pub fn unload(&mut self, chunk_coordinate: Vec3<i32>) -> Result<Option<ArcChunk>, WorldError> {
         let Some(index) = self.chunk_to_index(chunk_coordinate) else {
-            panic!()
+            return Err(WorldError::OutOfRange);
         };
 
-        mem::take(&mut self.chunks[index.into_tuple()]);
+        let chunk = mem::take(&mut self.chunks[self.slot(index)]);
+        event!(debug, "unloaded chunk {chunk_coordinate}");
+        Ok(chunk.map(|chunk| chunk.chunk()))
     }
 
     pub fn load(&mut self, chunk_coordinate: Vec3<i32>, chunk: Chunk) {
@@ -144,7 +514,62 @@ impl World {
             return;
         };
 
-        self.chunks[index.into_tuple()] = Some(Arc::new(chunk));
+        self.chunks[self.slot(index)] = Some(StoredChunk::Full(Arc::new(chunk)));
+        event!(debug, "loaded chunk {chunk_coordinate}");
+    }
+
+    /// Compresses the loaded chunks further than `distance` chunks away from the origin on any axis.
+    /// Compressed chunks are still loaded, but decompressed the first time they're accessed.
+    /// Chunks that were decompressed that way are compressed again.
+    pub fn compress_distant(&mut self, distance: i32) {
+        for (index, slot) in self.chunks.iter_mut().enumerate() {
+            let offset = slot_to_index(index, self.extents).as_::<i32>() - self.extents;
+            if offset.into_iter().all(|e| e.abs() <= distance) {
+                continue;
+            }
+
+            *slot = match slot.take() {
+                Some(StoredChunk::Full(chunk)) => {
+                    Some(StoredChunk::Compressed(Arc::new(chunk.compress())))
+                }
+                Some(StoredChunk::Compressed(compressed)) => Some(StoredChunk::Compressed(
+                    Arc::new(compressed.without_cache()),
+                )),
+                None => None,
+            };
+        }
+    }
+
+    /// Moves a compressed chunk back into `chunks`. Does nothing if the chunk isn't compressed.
+    pub fn decompress(&mut self, chunk_coordinate: Vec3<i32>) {
+        let Some(index) = self.chunk_to_index(chunk_coordinate) else {
+            return;
+        };
+
+        let slot = &mut self.chunks[index_to_slot(index, self.extents)];
+        if let Some(StoredChunk::Compressed(compressed)) = slot {
+            *slot = Some(StoredChunk::Full(compressed.decompress()));
+        }
+    }
+
+    /// Whether the chunk is loaded, compressed or not. Chunks outside of the world never are.
+    pub fn is_chunk_loaded(&self, chunk_coord: Vec3<i32>) -> bool {
+        self.chunk_to_index(chunk_coord)
+            .is_some_and(|index| self.chunks[self.slot(index)].is_some())
+    }
+
+    /// Whether the chunk containing the block at `position` is loaded, see `is_chunk_loaded`.
+    pub fn is_block_loaded(&self, position: Vec3<i32>) -> bool {
+        self.is_chunk_loaded(self.world_to_chunk(position))
+    }
+
+    pub fn is_compressed(&self, chunk_coordinate: Vec3<i32>) -> bool {
+        self.chunk_to_index(chunk_coordinate).is_some_and(|index| {
+            matches!(
+                self.chunks[self.slot(index)],
+                Some(StoredChunk::Compressed(_))
+            )
+        })
     }
 
     pub fn chunk_at_world(&self, position: Vec3<i32>) -> Option<ArcChunk> {
@@ -152,65 +577,337 @@ impl World {
     }
 
     /// Chunk coords to chunk.
+    /// Compressed chunks stay compressed, see `StoredChunk::chunk`.
     pub fn chunk_at(&self, position: Vec3<i32>) -> Option<ArcChunk> {
-        self.chunks
-            .get(self.chunk_to_index(position)?.into_tuple())
-            .cloned()
-            .flatten()
+        let index = self.chunk_to_index(position)?;
+        self.chunks[self.slot(index)]
+            .as_ref()
+            .map(StoredChunk::chunk)
     }
 
     /// World coords to chunk.
+    /// Compressed chunks are decompressed in place.
     pub fn chunk_at_world_mut(&mut self, position: Vec3<i32>) -> Option<&mut ArcChunk> {
-        self.chunks
-            .get_mut(
-                self.chunk_to_index(self.world_to_chunk(position))?
-                    .into_tuple(),
-            )
-            .map(|r| r.as_mut())
-            .flatten()
+        let chunk_coordinate = self.world_to_chunk(position);
+        self.decompress(chunk_coordinate);
+
+        let slot = self.slot(self.chunk_to_index(chunk_coordinate)?);
+        match &mut self.chunks[slot] {
+            Some(StoredChunk::Full(chunk)) => Some(chunk),
+            _ => None,
+        }
     }
 
     pub fn get_block(&self, position: Vec3<i32>) -> Option<Block> {
-        let chunk = self.chunk_at_world(position)?;
-        let chunk_offset = position.map(|e| (e as i32).rem_euclid(CHUNK_SIZE as i32));
+        let index = self.chunk_to_index(self.world_to_chunk(position))?;
+        let chunk_offset = coords::world_to_local(position);
+
+        self.chunks[self.slot(index)]
+            .as_ref()?
+            .chunk()
+            .get_local(chunk_offset)
+    }
 
-        chunk.blocks.get(chunk_offset.as_().into_tuple()).cloned()
+    /// Every loaded block overlapping `aabb`, which is in blocks. Blocks only touching it are included too.
+    pub fn blocks_in_aabb(&self, aabb: Aabb<f32>) -> impl Iterator<Item = (Vec3<i32>, Block)> + '_ {
+        let min = aabb.min.map(|e| e.floor() as i32);
+        let max = aabb.max.map(|e| e.floor() as i32);
+        iproduct!(min.x..=max.x, min.y..=max.y, min.z..=max.z).filter_map(|(x, y, z)| {
+            let position = Vec3::new(x, y, z);
+            Some((position, self.get_block(position)?))
+        })
     }
 
-    #[must_use]
+    /// Like `get_block`, but tells air and unloaded chunks apart without having to check the block type.
+    pub fn block_state(&self, position: Vec3<i32>) -> BlockState {
+        match self.get_block(position) {
+            None => BlockState::Unloaded,
+            Some(block) if block.ty.is_air() => BlockState::Air,
+            Some(block) => BlockState::Solid(block),
+        }
+    }
+
+    /// Like `get_block`, but indexes `chunk` directly when `position` is inside of it,
+    /// `chunk` has to be the chunk at `chunk_coord`.
+    pub fn get_block_cached(
+        &self,
+        chunk_coord: Vec3<i32>,
+        chunk: &Chunk,
+        position: Vec3<i32>,
+    ) -> Option<Block> {
+        chunk
+            .get_local(position - coords::chunk_to_world(chunk_coord, Vec3::zero()))
+            .or_else(|| self.get_block(position))
+    }
+
+    #[must_use = "the block isn't set when its chunk isn't loaded"]
     pub fn set_block(&mut self, position: Vec3<i32>, block: Block) -> Result<(), WorldError> {
         let Some(chunk) = self.chunk_at_world_mut(position) else {
             return Err(WorldError::ChunkNotLoaded);
         };
-        let chunk_offset = position.map(|e| (e as i32).rem_euclid(CHUNK_SIZE as i32));
+        let chunk_offset = coords::world_to_local(position);
 
-        let mut new_chunk = Arc::unwrap_or_clone(Arc::clone(&chunk));
-        new_chunk.set_block(chunk_offset, block);
-        *chunk = Arc::new(new_chunk);
+        // Copied only if another world still shares it.
+        Arc::make_mut(chunk).set_block(chunk_offset, block);
         Ok(())
     }
 
+    /// Raycast against the loaded blocks, unloaded chunks are passed through.
+    pub fn raycast(
+        &self,
+        origin: Vec3<f32>,
+        dir: Vec3<f32>,
+        max_distance: f32,
+    ) -> Option<RaycastOutput> {
+        raycast(origin, dir, max_distance, |pos| self.get_block(pos))
+    }
+
+    /// Like `raycast`, but passes through blocks `skip` returns true for as if they were air.
+    pub fn raycast_filtered(
+        &self,
+        origin: Vec3<f32>,
+        dir: Vec3<f32>,
+        max_distance: f32,
+        skip: impl Fn(Block) -> bool,
+    ) -> Option<RaycastOutput> {
+        raycast(origin, dir, max_distance, |pos| {
+            self.get_block(pos)
+                .map(|block| if skip(block) { Block::AIR } else { block })
+        })
+    }
+
+    /// Like `raycast`, but passes through see-through cubes like mesh and water
+    /// so blocks get placed against whatever is behind them, while breaking still targets them.
+    pub fn place_raycast(
+        &self,
+        origin: Vec3<f32>,
+        dir: Vec3<f32>,
+        max_distance: f32,
+    ) -> Option<RaycastOutput> {
+        self.raycast_filtered(origin, dir, max_distance, |block| {
+            block.ty.light_passing() && block.ty.shape() == BlockShape::Full
+        })
+    }
+
+    /// Hashes the blocks of every loaded chunk between `min_chunk` and `max_chunk` (inclusive).
+    /// The hash is stable across runs and machines, unloaded chunks are skipped.
+    pub fn region_hash(&self, min_chunk: Vec3<i32>, max_chunk: Vec3<i32>) -> u64 {
+        let mut hasher = FnvHasher::default();
+        for (x, y, z) in iproduct!(
+            min_chunk.x..=max_chunk.x,
+            min_chunk.y..=max_chunk.y,
+            min_chunk.z..=max_chunk.z
+        ) {
+            let chunk_coord = Vec3::new(x, y, z);
+            let Some(chunk) = self.chunk_at(chunk_coord) else {
+                continue;
+            };
+            chunk_coord.into_array().hash(&mut hasher);
+            for (_, block) in chunk.iter_blocks() {
+                block.hash(&mut hasher);
+            }
+        }
+        hasher.finish()
+    }
+
+    /// The highest block in the column at `x`, `z` that doesn't let light through, with its y.
+    /// Returns None if an unloaded chunk is reached before finding one, since the top could be in it.
+    pub fn column_top(&self, x: i32, z: i32) -> Option<(i32, Block)> {
+        let chunk_size = CHUNK_SIZE as i32;
+        let (chunk_x, chunk_z) = (x.div_euclid(chunk_size), z.div_euclid(chunk_size));
+        let (local_x, local_z) = (x.rem_euclid(chunk_size), z.rem_euclid(chunk_size));
+
+        for chunk_y in (self.origin.y - self.extents.y..=self.origin.y + self.extents.y).rev() {
+            let chunk = self.chunk_at(Vec3::new(chunk_x, chunk_y, chunk_z))?;
+            if chunk.is_all_air() {
+                continue;
+            }
+
+            for local_y in (0..chunk_size).rev() {
+                let block = chunk.get_local(Vec3::new(local_x, local_y, local_z))?;
+                if !block.ty.light_passing() {
+                    return Some((chunk_y * chunk_size + local_y, block));
+                }
+            }
+        }
+        None
+    }
+
+    /// Height and type of the highest non-air block of every column between `min` and `max` (inclusive),
+    /// where x is the world x and y is the world z. Indexed by `[x - min.x, z - min.y]`.
+    /// Columns with no loaded non-air blocks are `(i32::MIN, BlockType::Air)`.
+    pub fn surface_heightmap(&self, min: Vec2<i32>, max: Vec2<i32>) -> Array2<(i32, BlockType)> {
+        let size = (max - min + Vec2::one()).map(|e| e.max(0) as usize);
+        let mut heightmap = Array2::from_elem(size.into_tuple(), (i32::MIN, BlockType::Air));
+
+        let chunk_size = CHUNK_SIZE as i32;
+        let min_chunk = min.map(|e| e.div_euclid(chunk_size));
+        let max_chunk = max.map(|e| e.div_euclid(chunk_size));
+        for (chunk_x, chunk_z) in iproduct!(min_chunk.x..=max_chunk.x, min_chunk.y..=max_chunk.y) {
+            // Top to bottom, so the first non-air block found is the surface.
+            let column = (self.origin.y - self.extents.y..=self.origin.y + self.extents.y)
+                .rev()
+                .filter_map(|chunk_y| {
+                    let chunk_coord = Vec3::new(chunk_x, chunk_y, chunk_z);
+                    Some((chunk_coord, self.chunk_at(chunk_coord)?))
+                })
+                .filter(|(_, chunk)| !chunk.is_all_air())
+                .collect_vec();
+
+            for (local_x, local_z) in iproduct!(0..chunk_size, 0..chunk_size) {
+                let position =
+                    Vec2::new(chunk_x, chunk_z) * chunk_size + Vec2::new(local_x, local_z);
+                if position.x < min.x
+                    || position.y < min.y
+                    || position.x > max.x
+                    || position.y > max.y
+                {
+                    continue;
+                }
+
+                let surface = column.iter().find_map(|(chunk_coord, chunk)| {
+                    (0..chunk_size).rev().find_map(|local_y| {
+                        let block = chunk.get_local(Vec3::new(local_x, local_y, local_z))?;
+                        (!block.ty.is_air())
+                            .then_some((chunk_coord.y * chunk_size + local_y, block.ty))
+                    })
+                });
+                if let Some(surface) = surface {
+                    heightmap[(position - min).as_::<usize>().into_tuple()] = surface;
+                }
+            }
+        }
+        heightmap
+    }
+
     pub fn index_to_chunk(&self, index: Vec3<usize>) -> Vec3<i32> {
-        index.as_::<i32>() - self.extents + self.origin
+        coords::index_to_chunk(index, self.origin, self.extents)
     }
 
     pub fn unloaded_chunks(&self) -> impl Iterator<Item = Vec3<i32>> + '_ {
-        self.chunks.indexed_iter().filter_map(|(idx, chunk)| {
+        self.chunks.iter().enumerate().filter_map(|(slot, chunk)| {
             if chunk.is_none() {
-                Some(self.index_to_chunk(Vec3::<usize>::from(idx)))
+                Some(self.index_to_chunk(slot_to_index(slot, self.extents)))
             } else {
                 None
             }
         })
     }
 
+    pub fn loaded_chunks(&self) -> impl Iterator<Item = Vec3<i32>> + '_ {
+        self.chunks.iter().enumerate().filter_map(|(slot, chunk)| {
+            if chunk.is_some() {
+                Some(self.index_to_chunk(slot_to_index(slot, self.extents)))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Number of blocks of each type over all loaded chunks.
+    pub fn count_by_type(&self) -> HashMap<BlockType, u32> {
+        let mut counts = HashMap::new();
+        for (_, chunk) in self.chunks_iter() {
+            for (ty, count) in chunk.count_by_type() {
+                *counts.entry(ty).or_insert(0) += count;
+            }
+        }
+        counts
+    }
+
+    /// Iterates all loaded chunks, compressed chunks stay compressed, see `StoredChunk::chunk`.
     pub fn chunks_iter(&self) -> impl Iterator<Item = (Vec3<i32>, ArcChunk)> + '_ {
-        self.chunks.indexed_iter().filter_map(|(index, chunk)| {
-            chunk
-                .clone()
-                .map(|chunk| (self.index_to_chunk(Vec3::<usize>::from(index)), chunk))
+        self.chunks.iter().enumerate().filter_map(|(slot, chunk)| {
+            let chunk = chunk.as_ref()?.chunk();
+            Some((
+                self.index_to_chunk(slot_to_index(slot, self.extents)),
+                chunk,
+            ))
         })
     }
+
+    /// Like `chunks_iter`, but sorted by chunk coordinate (x, then y, then z) instead of by where the chunks
+    /// are stored, so the order doesn't change with the origin. The same order `region_hash` hashes chunks in.
+    pub fn chunks_sorted(&self) -> impl Iterator<Item = (Vec3<i32>, ArcChunk)> + '_ {
+        self.loaded_chunks()
+            .sorted_by_key(|chunk_coord| chunk_coord.into_tuple())
+            .filter_map(|chunk_coord| Some((chunk_coord, self.chunk_at(chunk_coord)?)))
+    }
+
+    /// The chunks next to the one at `chunk_coord`, in the same order as `face_neighbors`.
+    /// None where a neighbor isn't loaded or is outside of the world.
+    pub fn chunk_neighbors(&self, chunk_coord: Vec3<i32>) -> [Option<ArcChunk>; 6] {
+        face_neighbors(chunk_coord).map(|neighbor| self.chunk_at(neighbor))
+    }
+}
+
+/// 64-bit FNV-1a, used instead of `DefaultHasher` so hashes don't depend on the std version.
+pub struct FnvHasher(u64);
+
+/// Hasher for sets and maps keyed by chunk coordinates, smaller and faster than `RandomState`.
+pub type ChunkHasher = BuildHasherDefault<FnvHasher>;
+
+impl Default for FnvHasher {
+    fn default() -> Self {
+        FnvHasher(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
+/// How many chunks a world reaching `extents` chunks out from its origin keeps along each axis.
+fn shape(extents: Vec3<i32>) -> Vec3<usize> {
+    (extents * 2 + Vec3::one()).as_()
+}
+
+/// Where the chunk at `index` is kept in `World::chunks`, the same order an `Array3` would keep it in.
+fn index_to_slot(index: Vec3<usize>, extents: Vec3<i32>) -> usize {
+    let shape = shape(extents);
+    (index.x * shape.y + index.y) * shape.z + index.z
+}
+
+/// Inverse of `index_to_slot`.
+fn slot_to_index(slot: usize, extents: Vec3<i32>) -> Vec3<usize> {
+    let shape = shape(extents);
+    Vec3::new(
+        slot / (shape.y * shape.z),
+        slot / shape.z % shape.y,
+        slot % shape.z,
+    )
+}
+
+/// Moves every chunk `diff` chunks towards the start of the array, dropping the ones that fall outside.
+fn shift_chunks<T>(
+    chunks: &mut [Option<T>],
+    extents: Vec3<i32>,
+    diff: Vec3<i32>,
+) -> Box<[Option<T>]> {
+    // Let's get the world shifting :)
+    (0..chunks.len())
+        .map(|slot| {
+            let index = slot_to_index(slot, extents).as_::<i32>() + diff;
+            // Chunks shifted in from outside of the array start out unloaded.
+            if index
+                .zip(shape(extents).as_::<i32>())
+                .iter()
+                .any(|&(i, e)| i < 0 || i >= e)
+            {
+                return None;
+            }
+            chunks[index_to_slot(index.as_(), extents)].take()
+        })
+        .collect()
 }
 
 impl Default for World {
@@ -221,6 +918,280 @@ impl Default for World {
 
 impl DiscreteBlend for World {}
 
+#[test]
+fn test_chunk_compression() {
+    let uniform = Chunk::new();
+    let compressed = uniform.compress();
+    assert!(compressed.to_bytes().len() <= 16);
+    assert_eq!(*compressed.decompress(), uniform);
+    assert_eq!(
+        CompressedChunk::from_bytes(compressed.to_bytes()),
+        Some(compressed)
+    );
+
+    let mut mixed = Chunk::new();
+    mixed.set_block(Vec3::new(3, 4, 5), Block::STONE.with_light(12));
+    mixed.set_block(Vec3::new(15, 15, 15), Block::LANTERN);
+    assert_eq!(Chunk::from_bytes(&mixed.to_bytes()), Some(mixed.clone()));
+    assert_eq!(*mixed.compress().decompress(), mixed);
+    assert_eq!(Chunk::from_bytes(&mixed.to_bytes()[..10]), None);
+
+    // Compressed chunks are transparent to get_block and set_block.
+    let mut world = World::new(Vec3::zero());
+    world.load(Vec3::new(2, 0, 0), mixed.clone());
+    world.compress_distant(1);
+    assert!(world.is_compressed(Vec3::new(2, 0, 0)));
+    assert_eq!(world.unloaded_chunks().count(), world.chunks.len() - 1);
+    assert_eq!(
+        world.get_block(Vec3::new(32 + 3, 4, 5)),
+        Some(Block::STONE.with_light(12))
+    );
+    // Decompressed once, then reused until it's compressed again.
+    let chunk = world.chunk_at(Vec3::new(2, 0, 0)).unwrap();
+    assert!(Arc::ptr_eq(
+        &chunk,
+        &world.chunk_at(Vec3::new(2, 0, 0)).unwrap()
+    ));
+    world.compress_distant(1);
+    assert!(!Arc::ptr_eq(
+        &chunk,
+        &world.chunk_at(Vec3::new(2, 0, 0)).unwrap()
+    ));
+
+    world.set_block(Vec3::new(32, 0, 0), Block::GRASS).unwrap();
+    assert!(!world.is_compressed(Vec3::new(2, 0, 0)));
+    assert_eq!(world.get_block(Vec3::new(32, 0, 0)), Some(Block::GRASS));
+    assert_eq!(
+        world.get_block(Vec3::new(32 + 15, 15, 15)),
+        Some(Block::LANTERN)
+    );
+}
+
+#[test]
+fn test_world_raycast() {
+    let mut world = World::new(Vec3::zero());
+    world.load(Vec3::zero(), Chunk::new());
+    world.set_block(Vec3::new(4, 2, 8), Block::STONE).unwrap();
+
+    let origin = Vec3::new(4.5, 2.5, 2.5);
+    assert_eq!(
+        world.raycast(origin, Vec3::unit_z(), 7.5),
+        Some(RaycastOutput {
+            position: Vec3::new(4, 2, 8),
+            normal: Vec3::new(0, 0, -1),
+        })
+    );
+    assert_eq!(world.raycast(origin, Vec3::unit_z(), 4.0), None);
+    assert_eq!(world.raycast(origin, -Vec3::unit_z(), 7.5), None);
+    assert_eq!(
+        world.raycast(origin, Vec3::unit_z(), 7.5),
+        raycast(origin, Vec3::unit_z(), 7.5, |pos| world.get_block(pos))
+    );
+}
+
+#[test]
+fn test_place_raycast() {
+    let mut world = World::new(Vec3::zero());
+    world.load(Vec3::zero(), Chunk::new());
+    world.set_block(Vec3::new(4, 2, 6), Block::MESH).unwrap();
+    world.set_block(Vec3::new(4, 2, 8), Block::STONE).unwrap();
+
+    // Breaking targets the mesh, placing goes against the stone behind it.
+    let origin = Vec3::new(4.5, 2.5, 2.5);
+    assert_eq!(
+        world.raycast(origin, Vec3::unit_z(), 7.5).unwrap().position,
+        Vec3::new(4, 2, 6)
+    );
+    assert_eq!(
+        world.place_raycast(origin, Vec3::unit_z(), 7.5),
+        Some(RaycastOutput {
+            position: Vec3::new(4, 2, 8),
+            normal: Vec3::new(0, 0, -1),
+        })
+    );
+
+    // Blocks that aren't full cubes are still placed against.
+    world
+        .set_block(Vec3::new(4, 2, 6), Block::new(BlockType::StoneSlab))
+        .unwrap();
+    assert_eq!(
+        world.place_raycast(
+            origin + Vec3::unit_y(),
+            -Vec3::unit_y() + Vec3::unit_z(),
+            7.5
+        ),
+        world.raycast(
+            origin + Vec3::unit_y(),
+            -Vec3::unit_y() + Vec3::unit_z(),
+            7.5
+        )
+    );
+}
+
+#[test]
+fn test_chunk_air_solid_flags() {
+    let mut chunk = Chunk::new();
+    assert!(chunk.is_all_air());
+    assert!(!chunk.is_all_solid());
+
+    chunk.set_block(Vec3::new(1, 2, 3), Block::MESH);
+    assert!(!chunk.is_all_air());
+    chunk.set_block(Vec3::new(1, 2, 3), Block::AIR);
+    assert!(chunk.is_all_air());
+
+    let mut chunk = Chunk::from_blocks(Array3::from_elem(
+        (CHUNK_SIZE, CHUNK_SIZE, CHUNK_SIZE),
+        Block::STONE,
+    ));
+    assert!(chunk.is_all_solid());
+    chunk.set_block(Vec3::new(1, 2, 3), Block::WATER);
+    assert!(!chunk.is_all_solid());
+    assert!(!chunk.is_all_air());
+    chunk.set_block(Vec3::new(1, 2, 3), Block::WOOD);
+    assert!(chunk.is_all_solid());
+    assert_eq!(Chunk::from_bytes(&chunk.to_bytes()).unwrap(), chunk);
+}
+
+#[test]
+fn test_get_local() {
+    let chunk_coord = Vec3::new(-1, 2, -1);
+    let mut world = World::new(Vec3::zero());
+    world.load(
+        chunk_coord,
+        generate_chunk(&TerrainSampler::new(42), chunk_coord),
+    );
+    let chunk = world.chunk_at(chunk_coord).unwrap();
+    let chunk_origin = chunk_coord * CHUNK_SIZE as i32;
+
+    for (x, y, z) in iproduct!(0..CHUNK_SIZE, 0..CHUNK_SIZE, 0..CHUNK_SIZE) {
+        let local = Vec3::new(x, y, z).as_::<i32>();
+        let position = chunk_origin + local;
+        assert_eq!(chunk.get_local(local), world.get_block(position));
+        assert_eq!(
+            world.get_block_cached(chunk_coord, &chunk, position),
+            world.get_block(position)
+        );
+    }
+
+    // Negative locals don't wrap around into the chunk.
+    assert_eq!(chunk.get_local(Vec3::new(-1, 0, 0)), None);
+    assert_eq!(chunk.get_local(Vec3::new(0, -1, 0)), None);
+    assert_eq!(chunk.get_local(Vec3::new(0, 0, CHUNK_SIZE as i32)), None);
+
+    // Outside of the cached chunk it falls back to the world.
+    let neighbor = Vec3::new(0, 2, -1);
+    world.load(neighbor, generate_chunk(&TerrainSampler::new(42), neighbor));
+    let position = neighbor * CHUNK_SIZE as i32 + Vec3::new(0, 3, 5);
+    assert!(world.get_block(position).is_some());
+    assert_eq!(
+        world.get_block_cached(chunk_coord, &chunk, position),
+        world.get_block(position)
+    );
+}
+
+#[test]
+fn test_count_by_type() {
+    let mut chunk = Chunk::new();
+    chunk.set_block(Vec3::new(0, 0, 0), Block::STONE);
+    chunk.set_block(Vec3::new(1, 0, 0), Block::STONE);
+    chunk.set_block(Vec3::new(5, 3, 2), Block::WOOD);
+    let counts = chunk.count_by_type();
+    assert_eq!(counts.len(), 3);
+    assert_eq!(counts[&BlockType::Stone], 2);
+    assert_eq!(counts[&BlockType::Wood], 1);
+    assert_eq!(counts[&BlockType::Air], 16 * 16 * 16 - 3);
+
+    let mut world = World::new(Vec3::zero());
+    world.load(Vec3::new(0, 0, 0), chunk.clone());
+    world.load(Vec3::new(1, 0, 0), chunk);
+    let counts = world.count_by_type();
+    assert_eq!(counts[&BlockType::Stone], 4);
+    assert_eq!(counts.values().sum::<u32>(), 2 * 16 * 16 * 16);
+
+    // Compressed chunks are counted too.
+    world.compress_distant(0);
+    assert_eq!(world.count_by_type(), counts);
+
+    let world = crate::Game::new().world;
+    assert!(world.count_by_type()[&BlockType::Grass] > 0);
+}
+
+#[test]
+fn test_packed_light() {
+    let mut chunk = Chunk::new();
+    let lights = [0, 16, 17, 100, 224, 255];
+    for (x, &light) in lights.iter().enumerate() {
+        chunk.set_block(Vec3::new(x as i32, 3, 4), Block::AIR.with_light(light));
+    }
+
+    let packed = chunk.pack_light();
+    assert_eq!(packed.len(), CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE);
+    assert_eq!(packed.as_bytes().len(), packed.len() / 2);
+
+    let mut unpacked = Chunk::new();
+    unpacked.unpack_light(&packed);
+    for (x, &light) in lights.iter().enumerate() {
+        let block = unpacked.get_local(Vec3::new(x as i32, 3, 4)).unwrap();
+        assert_eq!(block.light, LightBits::Four.quantize(light));
+    }
+    assert_eq!(
+        unpacked.get_local(Vec3::new(4, 3, 4)).unwrap().light,
+        13 * 17
+    );
+    assert_eq!(unpacked.get_local(Vec3::new(5, 3, 4)).unwrap().light, 255);
+    assert_eq!(unpacked.pack_light(), packed);
+
+    // Chunks can keep their light packed too, the accessors unpack it.
+    assert_eq!(chunk.light_bits(), LightBits::Eight);
+    chunk.set_light_bits(LightBits::Four);
+    assert_eq!(chunk.light_bits(), LightBits::Four);
+    for (x, &light) in lights.iter().enumerate() {
+        let local = Vec3::new(x as i32, 3, 4);
+        let block = chunk.get_local(local).unwrap();
+        assert_eq!(block.light, LightBits::Four.quantize(light));
+        assert!(chunk
+            .iter_blocks()
+            .any(|(position, b)| position == local && b == block));
+    }
+    chunk.set_block(Vec3::new(9, 3, 4), Block::LANTERN.with_light(224));
+    assert_eq!(
+        chunk.get_local(Vec3::new(9, 3, 4)),
+        Some(Block::LANTERN.with_light(13 * 17))
+    );
+
+    // Going back to a byte per block keeps the levels.
+    chunk.set_light_bits(LightBits::Eight);
+    assert_eq!(chunk.get_local(Vec3::new(4, 3, 4)).unwrap().light, 13 * 17);
+}
+
+#[test]
+fn test_region_hash() {
+    let generate = || {
+        let terrain = TerrainSampler::new(42);
+        let mut world = World::new(Vec3::zero());
+        for chunk_coord in [Vec3::new(0, 2, 0), Vec3::new(1, 2, 0), Vec3::new(0, 2, 1)] {
+            world.load(chunk_coord, generate_chunk(&terrain, chunk_coord));
+        }
+        world
+    };
+
+    let min = Vec3::new(-1, 0, -1);
+    let max = Vec3::new(1, 3, 1);
+
+    let mut world = generate();
+    let hash = world.region_hash(min, max);
+    assert_eq!(generate().region_hash(min, max), hash);
+    assert_ne!(World::new(Vec3::zero()).region_hash(min, max), hash);
+
+    world.set_block(Vec3::new(3, 40, 5), Block::WOOD).unwrap();
+    assert_ne!(world.region_hash(min, max), hash);
+
+    // Compression doesn't change the contents.
+    let edited = world.region_hash(min, max);
+    world.compress_distant(0);
+    assert_eq!(world.region_hash(min, max), edited);
+}
+
 // #[test]
 // fn test_world() {
 //     let mut world = World::default();
@@ -243,6 +1214,290 @@ impl DiscreteBlend for World {}
 //     assert_eq!(world.get_block(Vec3::new(-4, 4, -1)), Some(Block::GRASS));
 // }
 
+#[test]
+fn test_block_state() {
+    let mut world = World::new(Vec3::zero());
+    world.load(Vec3::zero(), Chunk::new());
+    world.set_block(Vec3::new(15, 3, 3), Block::STONE).unwrap();
+    world.set_block(Vec3::new(14, 3, 3), Block::WATER).unwrap();
+
+    assert_eq!(
+        world.block_state(Vec3::new(15, 3, 3)),
+        BlockState::Solid(Block::STONE)
+    );
+    assert_eq!(
+        world.block_state(Vec3::new(14, 3, 3)),
+        BlockState::Solid(Block::WATER)
+    );
+    assert_eq!(world.block_state(Vec3::new(15, 4, 3)), BlockState::Air);
+    // The neighboring chunk isn't loaded.
+    assert_eq!(world.block_state(Vec3::new(16, 3, 3)), BlockState::Unloaded);
+    // Outside of the world.
+    assert_eq!(
+        world.block_state(Vec3::new(1000, 3, 3)),
+        BlockState::Unloaded
+    );
+
+    world.load(Vec3::unit_x(), Chunk::new());
+    assert_eq!(world.block_state(Vec3::new(16, 3, 3)), BlockState::Air);
+}
+
+#[test]
+fn test_surface_heightmap() {
+    let mut world = World::new(Vec3::zero());
+    world.load(Vec3::new(0, 0, 0), Chunk::new());
+    world.load(Vec3::new(0, 1, 0), Chunk::new());
+    world.load(Vec3::new(-1, 0, 0), Chunk::new());
+
+    world.set_block(Vec3::new(1, 3, 2), Block::STONE).unwrap();
+    world.set_block(Vec3::new(1, 21, 2), Block::GRASS).unwrap();
+    world.set_block(Vec3::new(4, 0, 4), Block::WOOD).unwrap();
+    world.set_block(Vec3::new(4, 9, 4), Block::WATER).unwrap();
+    world.set_block(Vec3::new(-1, 7, 0), Block::WOOD).unwrap();
+
+    let min = Vec2::new(-2, 0);
+    let heightmap = world.surface_heightmap(min, Vec2::new(5, 5));
+    assert_eq!(heightmap.dim(), (8, 6));
+
+    let at = |x: i32, z: i32| heightmap[(Vec2::new(x, z) - min).as_::<usize>().into_tuple()];
+    // The block in the chunk above hides the one below it.
+    assert_eq!(at(1, 2), (21, BlockType::Grass));
+    // Fluids count as the surface.
+    assert_eq!(at(4, 4), (9, BlockType::Water));
+    assert_eq!(at(-1, 0), (7, BlockType::Wood));
+    assert_eq!(at(0, 0), (i32::MIN, BlockType::Air));
+
+    let solid_columns = heightmap
+        .iter()
+        .filter(|(_, ty)| *ty != BlockType::Air)
+        .count();
+    assert_eq!(solid_columns, 3);
+}
+
+#[test]
+fn test_column_top() {
+    let terrain = TerrainSampler::new(crate::game::DEFAULT_SEED);
+    // Terrain is between y 32 and 52, so the loaded chunks have to reach y 63.
+    let mut world = World::new(Vec3::new(0, 1, 0));
+    for chunk_y in -1..=3 {
+        let chunk_coord = Vec3::new(0, chunk_y, 0);
+        world.load(chunk_coord, generate_chunk(&terrain, chunk_coord));
+    }
+
+    for (x, z) in iproduct!(0..CHUNK_SIZE as i32, 0..CHUNK_SIZE as i32) {
+        let surface = terrain.height(Vec2::new(x, z)) as i32 - 1;
+        let (y, block) = world.column_top(x, z).unwrap();
+        if terrain.cave(Vec3::new(x, surface, z)) {
+            assert!(y < surface);
+        } else {
+            assert_eq!(y, surface);
+            assert!(matches!(block.ty, BlockType::Grass | BlockType::Stone));
+        }
+    }
+
+    // Light passing blocks aren't the top.
+    let (y, _) = world.column_top(3, 3).unwrap();
+    world
+        .set_block(Vec3::new(3, y + 1, 3), Block::MESH)
+        .unwrap();
+    world
+        .set_block(Vec3::new(3, y + 2, 3), Block::WATER)
+        .unwrap();
+    assert_eq!(world.column_top(3, 3).map(|(y, _)| y), Some(y));
+    world
+        .set_block(Vec3::new(3, y + 3, 3), Block::WOOD)
+        .unwrap();
+    assert_eq!(world.column_top(3, 3), Some((y + 3, Block::WOOD)));
+
+    assert_eq!(world.column_top(CHUNK_SIZE as i32, 0), None);
+    world.unload(Vec3::new(0, 3, 0)).unwrap();
+    assert_eq!(world.column_top(0, 0), None);
+}
+
+#[test]
+fn test_speculative_edit() {
+    let terrain = TerrainSampler::new(42);
+    let mut world = World::new(Vec3::zero());
+    for chunk_coord in [
+        Vec3::zero(),
+        Vec3::unit_x(),
+        Vec3::unit_y(),
+        -Vec3::unit_z(),
+    ] {
+        world.load(chunk_coord, generate_chunk(&terrain, chunk_coord));
+    }
+
+    let position = Vec3::new(3, 4, 3);
+    let edited_chunk = world.world_to_chunk(position);
+    let mut speculative = world.clone();
+    speculative.set_block(position, Block::WOOD).unwrap();
+
+    assert_eq!(speculative.get_block(position).unwrap().ty, BlockType::Wood);
+    assert_ne!(world.get_block(position).unwrap().ty, BlockType::Wood);
+    for chunk_coord in world.loaded_chunks() {
+        let original = world.chunk_at(chunk_coord).unwrap();
+        let copy = speculative.chunk_at(chunk_coord).unwrap();
+        assert_eq!(Arc::ptr_eq(&original, &copy), chunk_coord != edited_chunk);
+    }
+
+    // Once nothing else shares the chunk it's edited in place.
+    drop(world);
+    let before = Arc::as_ptr(&speculative.chunk_at(edited_chunk).unwrap());
+    speculative.set_block(position, Block::STONE).unwrap();
+    let after = Arc::as_ptr(&speculative.chunk_at(edited_chunk).unwrap());
+    assert_eq!(before, after);
+}
+
+#[test]
+fn test_chunks_sorted() {
+    let terrain = TerrainSampler::new(42);
+    let chunk_coords = [
+        Vec3::new(1, 0, -1),
+        Vec3::new(-2, 1, 0),
+        Vec3::new(-2, 0, 3),
+        Vec3::new(0, -1, 0),
+        Vec3::new(1, -1, 2),
+    ];
+    let sorted = chunk_coords
+        .iter()
+        .copied()
+        .sorted_by_key(|chunk_coord| chunk_coord.into_tuple())
+        .collect_vec();
+
+    for origin in [Vec3::zero(), Vec3::new(-1, 0, 1), Vec3::new(1, 1, 0)] {
+        let mut world = World::new(origin);
+        for chunk_coord in chunk_coords {
+            world.load(chunk_coord, generate_chunk(&terrain, chunk_coord));
+        }
+        // Compressed chunks keep their place.
+        world.compress_distant(1);
+
+        let yielded = world.chunks_sorted().collect_vec();
+        assert_eq!(yielded.iter().map(|(c, _)| *c).collect_vec(), sorted);
+        for (chunk_coord, chunk) in yielded {
+            assert_eq!(*chunk, generate_chunk(&terrain, chunk_coord));
+        }
+    }
+}
+
+#[test]
+fn test_chunk_neighbors() {
+    let mut world = World::new(Vec3::zero());
+    let chunk_coord = Vec3::new(1, 0, -1);
+    world.load(chunk_coord, Chunk::new());
+    let mut above = Chunk::new();
+    above.set_block(Vec3::new(3, 0, 5), Block::STONE);
+    world.load(chunk_coord + Vec3::unit_y(), above);
+
+    let neighbors = world.chunk_neighbors(chunk_coord);
+    for (face, neighbor) in neighbors.iter().enumerate() {
+        assert_eq!(
+            neighbor.is_some(),
+            face_to_normal(face as u8) == Vec3::unit_y()
+        );
+    }
+    assert_eq!(
+        neighbors[1].as_ref().unwrap().get_local(Vec3::new(3, 0, 5)),
+        Some(Block::STONE)
+    );
+
+    // The chunk above sees the first one below it.
+    let below = world.chunk_neighbors(chunk_coord + Vec3::unit_y());
+    assert!(below[4].is_some());
+    assert_eq!(below.iter().flatten().count(), 1);
+    // Neighbors past the edge of the world are None too.
+    assert!(world
+        .chunk_neighbors(world.extents)
+        .iter()
+        .all(Option::is_none));
+}
+
+#[cfg(feature = "log")]
+#[test]
+fn test_load_event() {
+    use std::sync::Mutex;
+
+    struct TestLogger(Mutex<Vec<String>>);
+
+    impl log::Log for TestLogger {
+        fn enabled(&self, _metadata: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("{} {}", record.level(), record.args()));
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: TestLogger = TestLogger(Mutex::new(Vec::new()));
+    // Fails if it was already set, which is fine since it's only set here.
+    let _ = log::set_logger(&LOGGER);
+    log::set_max_level(log::LevelFilter::Trace);
+
+    let chunk_coord = Vec3::new(3, -2, 5);
+    let mut world = World::new(Vec3::zero());
+    world.load(chunk_coord, Chunk::new());
+    assert!(LOGGER
+        .0
+        .lock()
+        .unwrap()
+        .contains(&format!("DEBUG loaded chunk {chunk_coord}")));
+}
+
+#[test]
+fn test_iter_world_blocks() {
+    let chunk_coord = Vec3::new(-2, 1, -1);
+    let mut chunk = Chunk::new();
+    chunk.set_block(Vec3::new(3, 0, 15), Block::STONE);
+
+    let blocks = chunk.iter_world_blocks(chunk_coord).collect_vec();
+    assert_eq!(blocks.len(), CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE);
+    for ((position, block), (local, expected)) in blocks.iter().zip(chunk.iter_blocks()) {
+        assert_eq!(*position, chunk_coord * CHUNK_SIZE as i32 + local);
+        assert_eq!(*block, expected);
+    }
+    assert_eq!(blocks[0].0, Vec3::new(-32, 16, -16));
+    assert!(blocks.contains(&(Vec3::new(-29, 16, -1), Block::STONE)));
+    // Every position is in the chunk it came from.
+    assert!(blocks
+        .iter()
+        .all(|(position, _)| coords::world_to_chunk(*position) == chunk_coord));
+}
+
+#[test]
+fn test_unload() {
+    let terrain = TerrainSampler::new(42);
+    let mut world = World::new(Vec3::zero());
+    let chunk_coord = Vec3::new(1, 0, -1);
+    let chunk = generate_chunk(&terrain, chunk_coord);
+    world.load(chunk_coord, chunk.clone());
+
+    let unloaded = world.unload(chunk_coord).unwrap().unwrap();
+    assert_eq!(*unloaded, chunk);
+    assert!(world.chunk_at(chunk_coord).is_none());
+
+    // Already unloaded.
+    assert_eq!(world.unload(chunk_coord).map(|c| c.is_none()), Ok(true));
+
+    // Compressed chunks come back decompressed.
+    world.load(chunk_coord, chunk.clone());
+    world.compress_distant(0);
+    let unloaded = world.unload(chunk_coord).unwrap().unwrap();
+    assert_eq!(*unloaded, chunk);
+    assert_eq!(world.unload(chunk_coord).map(|c| c.is_none()), Ok(true));
+
+    let outside = world.extents + 1;
+    assert_eq!(
+        world.unload(outside).map(|c| c.is_none()),
+        Err(WorldError::OutOfRange)
+    );
+}
+
 pub fn face_to_normal(face: u8) -> Vec3<i32> {
     match face {
         0 => Vec3::unit_x(),
@@ -255,6 +1510,11 @@ pub fn face_to_normal(face: u8) -> Vec3<i32> {
     }
 }
 
+/// Inverse of `face_to_normal`, None if `normal` isn't a unit axis.
+pub fn normal_to_face(normal: Vec3<i32>) -> Option<u8> {
+    (0..6).find(|&face| face_to_normal(face) == normal)
+}
+
 pub fn face_neighbors(position: Vec3<i32>) -> [Vec3<i32>; 6] {
     [0, 1, 2, 3, 4, 5].map(|face| position + face_to_normal(face))
 }
@@ -286,11 +1546,10 @@ pub fn generate_chunk(terrain: &TerrainSampler, chunk_coordinate: Vec3<i32>) ->
 
     let mut blocks = Array3::from_elem(
         (CHUNK_SIZE, CHUNK_SIZE, CHUNK_SIZE),
-        Block {
-            open_to_sky: true,
-            light: 255,
-            ..Block::AIR
-        },
+        Block::AIR
+            .with_open_to_sky(true)
+            .with_sky_light(255)
+            .with_light(255),
     );
 
     for x in 0..CHUNK_SIZE {
@@ -314,7 +1573,10 @@ pub fn generate_chunk(terrain: &TerrainSampler, chunk_coordinate: Vec3<i32>) ->
                     // }
 
                     *target = if is_cave { Block::AIR } else { Block::STONE };
-                    // target.occluded = y < 14;
+                    target.sky_light = sky_light_at_depth(height as i32 - 1 - world_coord.y);
+                    if is_cave {
+                        target.light = target.sky_light;
+                    }
                 }
             } else if chunk_coordinate.y == chunk_y {
                 for y in 0..local.y {
@@ -323,11 +1585,14 @@ pub fn generate_chunk(terrain: &TerrainSampler, chunk_coordinate: Vec3<i32>) ->
 
                     *target = if is_top { Block::GRASS } else { Block::STONE };
                     target.open_to_sky = is_top;
+                    target.sky_light = sky_light_at_depth(local.y - 1 - y);
                 }
             }
         }
     }
 
+    // Same rule as the block updates use, except that the blocks on the chunk's border stay visible
+    // since the neighboring chunks might not be generated yet.
     for x in 0..CHUNK_SIZE {
         for y in 0..CHUNK_SIZE {
             for z in 0..CHUNK_SIZE {
@@ -336,11 +1601,8 @@ pub fn generate_chunk(terrain: &TerrainSampler, chunk_coordinate: Vec3<i32>) ->
                 blocks[local.as_().into_tuple()].occluded =
                     face_neighbors(local).into_iter().all(|position| {
                         match blocks.get(position.as_().into_tuple()) {
-                            Some(Block {
-                                ty: BlockType::Air, ..
-                            }) => false,
+                            Some(block) => !block.ty.light_passing(),
                             None => false,
-                            Some(_) => true,
                         }
                     });
             }
@@ -350,3 +1612,71 @@ pub fn generate_chunk(terrain: &TerrainSampler, chunk_coordinate: Vec3<i32>) ->
     // println!("done!");
     Chunk::from_blocks(blocks)
 }
+
+#[test]
+fn test_pinned_chunks() {
+    let spawn = Vec3::new(0, 0, 0);
+    let neighbor = Vec3::new(1, 0, 0);
+    let mut world = World::new(Vec3::zero());
+    world.load(spawn, Chunk::new());
+    world.load(neighbor, Chunk::new());
+    world.pin(spawn);
+    world.set_block(Vec3::new(3, 4, 5), Block::STONE).unwrap();
+    world.set_block(Vec3::new(19, 4, 5), Block::STONE).unwrap();
+    // Compressed chunks are kept too.
+    world.compress_distant(-1);
+    assert!(world.is_compressed(spawn));
+
+    // Far enough that neither chunk is in the world anymore.
+    world.set_origin(Vec3::new(100, 0, 0));
+    assert!(world.chunk_at(spawn).is_none());
+    assert_eq!(
+        world
+            .pinned_chunks()
+            .map(|(chunk_coord, _)| chunk_coord)
+            .collect_vec(),
+        vec![spawn]
+    );
+
+    world.set_origin(Vec3::zero());
+    assert_eq!(world.get_block(Vec3::new(3, 4, 5)), Some(Block::STONE));
+    assert!(world.loaded_chunks().contains(&spawn));
+    assert!(world.chunk_at(neighbor).is_none());
+
+    // Unpinned chunks are dropped like any other.
+    world.unpin(spawn);
+    world.set_origin(Vec3::new(100, 0, 0));
+    world.set_origin(Vec3::zero());
+    assert!(world.chunk_at(spawn).is_none());
+    assert_eq!(world.pinned_chunks().count(), 0);
+}
+
+#[test]
+fn test_is_chunk_loaded() {
+    let mut world = World::new(Vec3::zero());
+    world.load(Vec3::new(0, 0, 0), Chunk::new());
+    world.load(Vec3::new(-1, 1, 2), Chunk::new());
+    world.load(Vec3::new(2, 0, 0), Chunk::new());
+    world.compress_distant(1);
+
+    assert!(world.is_chunk_loaded(Vec3::new(0, 0, 0)));
+    assert!(world.is_chunk_loaded(Vec3::new(-1, 1, 2)));
+    // Compressed chunks are still loaded.
+    assert!(world.is_compressed(Vec3::new(2, 0, 0)));
+    assert!(world.is_chunk_loaded(Vec3::new(2, 0, 0)));
+
+    assert!(!world.is_chunk_loaded(Vec3::new(1, 0, 0)));
+    assert!(!world.is_chunk_loaded(Vec3::new(0, -1, 0)));
+
+    // Outside of the world.
+    assert!(!world.is_chunk_loaded(world.extents + Vec3::unit_x()));
+    assert!(!world.is_chunk_loaded(Vec3::new(0, -100, 0)));
+
+    let size = CHUNK_SIZE as i32;
+    assert!(world.is_block_loaded(Vec3::new(0, 0, 0)));
+    assert!(world.is_block_loaded(Vec3::new(size - 1, size - 1, size - 1)));
+    assert!(world.is_block_loaded(Vec3::new(-1, size, size * 2)));
+    assert!(!world.is_block_loaded(Vec3::new(size, 0, 0)));
+    assert!(!world.is_block_loaded(Vec3::new(0, -1, 0)));
+    assert!(!world.is_block_loaded(Vec3::new(0, -size * 100, 0)));
+}